@@ -36,6 +36,7 @@ struct Endpoints {
 #[derive(Serialize, Deserialize, Debug)]
 struct Endpoint {
     name: String,
+    method: String,
 }
 
 fn get_endpoints(api: &str) -> Result<(), reqwest::Error> {
@@ -51,9 +52,9 @@ fn get_endpoints(api: &str) -> Result<(), reqwest::Error> {
     //
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-    table.set_titles(row![b -> "Endpoints", b -> "Deployed Version"]);
+    table.set_titles(row![b -> "Method", b -> "Endpoints", b -> "Deployed Version"]);
     for val in endpoints.endpoints {
-        table.add_row(row![val.name, "..."]);
+        table.add_row(row![val.method, val.name, "..."]);
     }
 
     // Print the table to stdout
@@ -72,6 +73,9 @@ struct Specs {
 struct Spec {
     name: String,
     id: String,
+    spec_type: String,
+    #[serde(default)]
+    source: String,
 }
 
 fn get_specs() -> Result<(), reqwest::Error> {
@@ -86,9 +90,9 @@ fn get_specs() -> Result<(), reqwest::Error> {
     //
     let mut table = Table::new();
     table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-    table.set_titles(row![b -> "Id", b -> "Specs"]);
+    table.set_titles(row![b -> "Id", b -> "Specs", b -> "Type", b -> "Source"]);
     for val in specs.specs {
-        table.add_row(row![val.id, val.name]);
+        table.add_row(row![val.id, val.name, val.spec_type, val.source]);
     }
 
     // Print the table to stdout
@@ -379,7 +383,28 @@ fn list_all_apis() -> Result<(), reqwest::Error> {
     Ok(())
 }
 
-fn update_api_status(api: &str, value: &str) -> Result<(), reqwest::Error> {
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatusChange {
+    pub status: Status,
+    pub actor: String,
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatusHistory {
+    pub status: Status,
+    pub actor: String,
+    pub comment: Option<String>,
+    pub start_date_time: String,
+    pub end_date_time: Option<String>,
+}
+
+fn update_api_status(
+    api: &str,
+    value: &str,
+    actor: &str,
+    comment: Option<&str>,
+) -> Result<(), reqwest::Error> {
     let client = Client::new();
 
     let status = match value {
@@ -388,6 +413,11 @@ fn update_api_status(api: &str, value: &str) -> Result<(), reqwest::Error> {
         "retired" => Status::RETIRED,
         _ => Status::NONE,
     };
+    let change = StatusChange {
+        status: status,
+        actor: actor.to_string(),
+        comment: comment.map(|comment| comment.to_string()),
+    };
 
     let url = format!(
         "http://{address}/v1/apis/{id}/status",
@@ -395,8 +425,43 @@ fn update_api_status(api: &str, value: &str) -> Result<(), reqwest::Error> {
         id = api
     );
     //update and send it and updated version back
-    let mut resp = client.post(&url).json(&status).send()?;
+    let mut resp = client.post(&url).json(&change).send()?;
     debug!("response: {:?}", resp.status());
+    //e.g. a transition that is not allowed
+    if !resp.status().is_success() {
+        println!("Unable to update the status - [{}]", resp.text()?);
+    }
+
+    Ok(())
+}
+
+fn get_api_status_history(api: &str) -> Result<(), reqwest::Error> {
+    let client = Client::new();
+
+    let url = format!(
+        "http://{address}/v1/apis/{id}/status/history",
+        address = &SETTINGS.server.address,
+        id = api
+    );
+    let mut resp = client.get(&url).send()?;
+    debug!("body: {:?}", resp.status());
+
+    let history: Vec<StatusHistory> = resp.json()?;
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    table.set_titles(row![b -> "Status", b -> "Actor", b -> "Comment", b -> "From", b -> "To"]);
+    for status in history {
+        table.add_row(row![
+            format!("{:?}", status.status),
+            status.actor,
+            status.comment.unwrap_or_default(),
+            status.start_date_time,
+            status.end_date_time.unwrap_or_default()
+        ]);
+    }
+
+    // Print the table to stdout
+    table.printstd();
 
     Ok(())
 }
@@ -469,6 +534,174 @@ fn create_env(name: &str, description: &str) -> Result<(), reqwest::Error> {
     Ok(())
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub enum WaiverState {
+    ACTIVE,
+    EXPIRED,
+    REVOKED,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Waiver {
+    pub id: Uuid,
+    pub spec: String,
+    pub path: Option<String>,
+    pub rule: String,
+    pub approver: String,
+    pub expiry_date_time: String,
+    pub state: WaiverState,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Suppression {
+    pub spec_id: String,
+    pub spec: String,
+    pub path: Option<String>,
+    pub method: Option<String>,
+    pub rule: String,
+    pub rationale: Option<String>,
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Waivers {
+    pub waivers: Vec<Waiver>,
+    pub suppressions: Vec<Suppression>,
+}
+
+fn list_waivers() -> Result<(), reqwest::Error> {
+    let client = Client::new();
+    let url = format!(
+        "http://{address}/v1/waivers",
+        address = &SETTINGS.server.address
+    );
+    let mut resp = client.get(&url).send()?;
+    debug!("body: {:?}", resp.status());
+    let waivers: Waivers = resp.json()?;
+    //
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    table.set_titles(
+        row![b -> "Id", b -> "Spec Id", b -> "Path", b -> "Rule", b -> "Approver", b -> "Expiry", b -> "State"],
+    );
+    for waiver in waivers.waivers {
+        table.add_row(row![
+            waiver.id,
+            waiver.spec,
+            waiver.path.unwrap_or_default(),
+            waiver.rule,
+            waiver.approver,
+            waiver.expiry_date_time,
+            format!("{:?}", waiver.state)
+        ]);
+    }
+    table.printstd();
+
+    //x-zally-ignore without active waiver
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    table.set_titles(row![b -> "Spec Id", b -> "Spec", b -> "Path", b -> "Rule", b -> "Rationale", b -> "Status"]);
+    for suppression in waivers.suppressions {
+        table.add_row(row![
+            suppression.spec_id,
+            suppression.spec,
+            suppression.path.unwrap_or_default(),
+            suppression.rule,
+            suppression.rationale.unwrap_or_default(),
+            suppression.status
+        ]);
+    }
+    table.printstd();
+
+    Ok(())
+}
+
+fn create_waiver(
+    spec: &str,
+    path: Option<&str>,
+    rule: &str,
+    approver: &str,
+    expiry: &str,
+) -> Result<(), reqwest::Error> {
+    let client = Client::new();
+
+    //a date (e.g. 2021-03-31) expires at the beginning of the day
+    let expiry_date_time = match expiry.contains('T') {
+        true => expiry.to_string(),
+        false => format!("{}T00:00:00Z", expiry),
+    };
+    let waiver = Waiver {
+        id: Uuid::nil(),
+        spec: spec.to_string(),
+        path: path.map(|path| path.to_string()),
+        rule: rule.to_string(),
+        approver: approver.to_string(),
+        expiry_date_time: expiry_date_time,
+        state: WaiverState::ACTIVE,
+    };
+    let url = format!(
+        "http://{address}/v1/waivers",
+        address = &SETTINGS.server.address
+    );
+    let resp = client.post(&url).json(&waiver).send()?;
+    debug!("body: {:?}", resp.status());
+
+    Ok(())
+}
+
+fn revoke_waiver(id: &str) -> Result<(), reqwest::Error> {
+    let client = Client::new();
+
+    let url = format!(
+        "http://{address}/v1/waivers/{id}",
+        address = &SETTINGS.server.address,
+        id = id
+    );
+    let resp = client.delete(&url).send()?;
+    debug!("Got Response [{:?}]", resp);
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Backfill {
+    days: usize,
+    endpoints_num: usize,
+    endpoints_num_per_audience: usize,
+    zally_violations: usize,
+}
+
+fn backfill_metrics(from: &str, to: Option<&str>) -> Result<(), reqwest::Error> {
+    let client = Client::new();
+
+    let url = format!(
+        "http://{address}/v1/metrics/backfill",
+        address = &SETTINGS.server.address
+    );
+    let mut query = vec![("from", from)];
+    if let Some(to) = to {
+        query.push(("to", to));
+    }
+    let mut resp = client.post(&url).query(&query).send()?;
+    debug!("body: {:?}", resp.status());
+    let backfill: Backfill = resp.json()?;
+    //
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
+    table.set_titles(
+        row![b -> "Days", b -> "Endpoints", b -> "Endpoints per Audience", b -> "Zally Ignore"],
+    );
+    table.add_row(row![
+        backfill.days,
+        backfill.endpoints_num,
+        backfill.endpoints_num_per_audience,
+        backfill.zally_violations
+    ]);
+    table.printstd();
+
+    Ok(())
+}
+
 lazy_static! {
     static ref SETTINGS: settings::Settings = Settings::new().unwrap();
 }
@@ -595,6 +828,31 @@ fn main() {
                                 .possible_values(&["validated", "deprecated", "retired"])
                                 .required(true),
                         )
+                        .arg(
+                            Arg::with_name("api")
+                                .short("a")
+                                .long("api")
+                                .takes_value(true)
+                                .required(true),
+                        )
+                        .arg(
+                            Arg::with_name("actor")
+                                .long("actor")
+                                .takes_value(true)
+                                .required(true)
+                                .help("Who changes the status"),
+                        )
+                        .arg(
+                            Arg::with_name("comment")
+                                .short("c")
+                                .long("comment")
+                                .takes_value(true)
+                                .help("Why the status is changed"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("history")
+                        .about("show the status history of the api")
                         .arg(
                             Arg::with_name("api")
                                 .short("a")
@@ -694,6 +952,95 @@ fn main() {
                         ),
                 ),
         )
+        .subcommand(
+            App::new("waivers")
+                .about("Manage waivers, i.e. time-boxed exceptions to zally rules")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("list")
+                        .about("List all waivers and the suppressions not covered by a waiver"),
+                )
+                .subcommand(
+                    SubCommand::with_name("create")
+                        .about("Create a new waiver")
+                        .arg(
+                            Arg::with_name("spec")
+                                .short("s")
+                                .long("spec")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The id of the spec in the catalog, as listed along with the suppressions"),
+                        )
+                        .arg(
+                            Arg::with_name("path")
+                                .short("p")
+                                .long("path")
+                                .takes_value(true)
+                                .required(false)
+                                .help("The path of the spec the waiver applies to (whole spec if not set)"),
+                        )
+                        .arg(
+                            Arg::with_name("rule")
+                                .short("r")
+                                .long("rule")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The id of the zally rule"),
+                        )
+                        .arg(
+                            Arg::with_name("approver")
+                                .short("a")
+                                .long("approver")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The name of the approver"),
+                        )
+                        .arg(
+                            Arg::with_name("expiry")
+                                .short("e")
+                                .long("expiry")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The expiry date of the waiver (e.g. 2021-03-31)"),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("revoke")
+                        .about("Revoke a waiver")
+                        .arg(
+                            Arg::with_name("id")
+                                .long("id")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The id of the waiver"),
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("metrics")
+                .about("Manage metrics")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("backfill")
+                        .about("Compute the past metrics by replaying the git history of the catalog")
+                        .arg(
+                            Arg::with_name("from")
+                                .short("f")
+                                .long("from")
+                                .takes_value(true)
+                                .required(true)
+                                .help("The first day to compute metrics for (e.g. 2020-10-01)"),
+                        )
+                        .arg(
+                            Arg::with_name("to")
+                                .short("t")
+                                .long("to")
+                                .takes_value(true)
+                                .required(false)
+                                .help("The last day to compute metrics for (today if not set)"),
+                        ),
+                ),
+        )
         // .subcommand(
         //     App::new("xxx - extensions: layers, services etc....").about("DO WE NEED THIS HERE?")
         // )
@@ -787,8 +1134,13 @@ fn main() {
                 update_api_status(
                     matches.value_of("api").unwrap(),
                     matches.value_of("value").unwrap(),
+                    matches.value_of("actor").unwrap(),
+                    matches.value_of("comment"),
                 );
             }
+            ("history", Some(matches)) => {
+                get_api_status_history(matches.value_of("api").unwrap()).unwrap();
+            }
             ("tier", Some(matches)) => {
                 update_api_tier(
                     matches.value_of("api").unwrap(),
@@ -798,6 +1150,32 @@ fn main() {
 
             _ => unreachable!(),
         },
+        ("waivers", Some(waivers)) => match waivers.subcommand() {
+            ("list", Some(_matches)) => {
+                list_waivers().unwrap();
+            }
+            ("create", Some(matches)) => {
+                create_waiver(
+                    matches.value_of("spec").unwrap(),
+                    matches.value_of("path"),
+                    matches.value_of("rule").unwrap(),
+                    matches.value_of("approver").unwrap(),
+                    matches.value_of("expiry").unwrap(),
+                )
+                .unwrap();
+            }
+            ("revoke", Some(matches)) => {
+                revoke_waiver(matches.value_of("id").unwrap()).unwrap();
+            }
+            _ => unreachable!(),
+        },
+        ("metrics", Some(metrics)) => match metrics.subcommand() {
+            ("backfill", Some(matches)) => {
+                backfill_metrics(matches.value_of("from").unwrap(), matches.value_of("to"))
+                    .unwrap();
+            }
+            _ => unreachable!(),
+        },
 
         ("", None) => println!("No subcommand was used"), // If no subcommand was usd it'll match the tuple ("", None)
         _ => unreachable!(), // If all subcommands are defined above, anything else is unreachabe!()
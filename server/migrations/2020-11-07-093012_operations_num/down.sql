@@ -0,0 +1 @@
+drop table if exists metrics_operations_num;
\ No newline at end of file
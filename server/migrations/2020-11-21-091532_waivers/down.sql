@@ -0,0 +1 @@
+drop table if exists waivers;
\ No newline at end of file
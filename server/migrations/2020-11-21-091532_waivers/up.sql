@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS waivers (
+    waiver_id INTEGER PRIMARY KEY,
+    id UUID NOT NULL UNIQUE,
+    spec TEXT NOT NULL,
+    path TEXT,
+    rule TEXT NOT NULL,
+    approver TEXT NOT NULL,
+    expiry_date_time TEXT NOT NULL,
+    start_date_time TEXT NOT NULL,
+    revoked_date_time TEXT
+);
\ No newline at end of file
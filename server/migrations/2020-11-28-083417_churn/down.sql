@@ -0,0 +1 @@
+drop table if exists metrics_churn;
\ No newline at end of file
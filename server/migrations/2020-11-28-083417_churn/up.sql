@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS metrics_churn (
+    date_time TEXT NOT NULL UNIQUE, 
+    data_points TEXT NOT NULL
+);
\ No newline at end of file
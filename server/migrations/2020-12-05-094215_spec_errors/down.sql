@@ -0,0 +1 @@
+drop table if exists metrics_spec_errors;
\ No newline at end of file
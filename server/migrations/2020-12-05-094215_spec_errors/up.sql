@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS metrics_spec_errors (
+    date_time TEXT NOT NULL UNIQUE, 
+    value INTEGER NOT NULL
+);
\ No newline at end of file
@@ -0,0 +1 @@
+drop table if exists api_specs;
\ No newline at end of file
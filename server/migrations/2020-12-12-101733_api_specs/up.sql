@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS api_specs (
+    api_id UUID NOT NULL,
+    spec_id TEXT NOT NULL UNIQUE,
+    link_date_time TEXT NOT NULL
+);
\ No newline at end of file
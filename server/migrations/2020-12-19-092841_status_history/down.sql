@@ -0,0 +1,3 @@
+-- DROP COLUMN not support by sqlite -> no rollback
+-- ALTER TABLE status DROP COLUMN actor;
+-- ALTER TABLE status DROP COLUMN comment;
\ No newline at end of file
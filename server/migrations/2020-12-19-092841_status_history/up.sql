@@ -0,0 +1,3 @@
+-- every change of status is kept, the current one having no end_date_time
+ALTER TABLE status ADD actor TEXT NOT NULL default "N/A";
+ALTER TABLE status ADD comment TEXT;
\ No newline at end of file
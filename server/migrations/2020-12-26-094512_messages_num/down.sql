@@ -0,0 +1 @@
+drop table if exists metrics_messages_num;
\ No newline at end of file
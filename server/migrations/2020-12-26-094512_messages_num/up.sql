@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS metrics_messages_num (
+    date_time TEXT NOT NULL UNIQUE, 
+    value INTEGER NOT NULL
+);
@@ -10,6 +10,7 @@ use reqwest::Client;
 
 #[path = "../dao/mod.rs"]
 mod dao;
+use dao::error::{ApiError, Validator};
 use dao::repo_apis::*;
 use dao::repo_domains::*;
 
@@ -21,8 +22,11 @@ use settings::Settings;
 
 use uuid::Uuid;
 
+use chrono::{DateTime, Utc};
+
 lazy_static! {
     static ref SETTINGS: settings::Settings = Settings::new().unwrap();
+    static ref SOURCES: Vec<settings::CatalogSource> = SETTINGS.get_sources();
 }
 
 /*
@@ -31,34 +35,128 @@ lazy_static! {
 
 #[derive(Serialize, Deserialize)]
 pub struct Endpoints {
+    spec_type: String,
     endpoints: Vec<Endpoint>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct Endpoint {
     name: String,
+    method: String,
+    operation_id: Option<String>,
+    tags: Vec<String>,
+    deprecated: bool,
+    security: Vec<openapiv3::SecurityRequirement>,
+    parameters: Vec<dao::catalog::operations::ParameterItem>,
+    //x-has-authority of the operation (or of its path)
+    permission: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EndpointsFilter {
+    method: Option<String>,
+    tag: Option<String>,
+    deprecated: Option<bool>,
+    //branch, tag or commit to read the spec at, its current revision if not set
+    #[serde(rename = "ref")]
+    git_ref: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CatalogRef {
+    //branch, tag or commit to read the catalog at, HEAD if not set
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+}
+
+impl EndpointsFilter {
+    fn accept(&self, operation: &dao::catalog::operations::OperationItem) -> bool {
+        let method = match &self.method {
+            Some(method) => operation.method.eq_ignore_ascii_case(method),
+            None => true,
+        };
+        let tag = match &self.tag {
+            Some(tag) => operation.tags.contains(tag),
+            None => true,
+        };
+        let deprecated = match self.deprecated {
+            Some(deprecated) => operation.deprecated == deprecated,
+            None => true,
+        };
+
+        method && tag && deprecated
+    }
 }
 
 //#[get("/v1/endpoints/{api}")]
-pub fn get_endpoints(info: web::Path<(String,)>) -> HttpResponse {
+pub async fn get_endpoints(
+    info: web::Path<(String,)>,
+    filter: web::Query<EndpointsFilter>,
+) -> Result<HttpResponse, ApiError> {
     let mut endpoints = Endpoints {
+        spec_type: dao::catalog::SpecType::OpenApi.as_str(),
         endpoints: Vec::new(),
     };
 
-    let mut all_apis = dao::catalog::get_spec(SETTINGS.catalog_path.as_str(), &info.0);
+    let (id, git_ref) = (info.0.clone(), filter.git_ref.clone());
+    let (mut all_apis, mut all_async_apis) = dao::error::blocking(move || {
+        let git_ref = git_ref.as_deref();
+        let all_apis = dao::catalog::get_spec_at(&SOURCES, &id, git_ref);
+        //not an openapi spec, channels are the asyncapi counterpart of paths
+        let all_async_apis = match all_apis.is_empty() {
+            true => dao::catalog::get_async_spec_at(&SOURCES, &id, git_ref),
+            false => Vec::new(),
+        };
+        Ok::<_, ApiError>((all_apis, all_async_apis))
+    })
+    .await?;
+    if all_apis.is_empty() && all_async_apis.is_empty() {
+        return Err(ApiError::not_found(format!("Unknown spec [{}]", info.0)));
+    }
 
+    let mut operations = Vec::new();
     while let Some(api) = all_apis.pop() {
         info!("Analysing file [{:?}]", api.path);
 
         let openapi: OpenAPI = api.api_spec;
-        for val in openapi.paths.keys() {
+        for operation in dao::catalog::operations::get_operations(&openapi) {
+            let permission = api
+                .extensions
+                .get_has_authority(&operation.path, &operation.method);
+            operations.push((operation, permission));
+        }
+    }
+    while let Some(api) = all_async_apis.pop() {
+        info!("Analysing asyncapi file [{:?}]", api.path);
+
+        endpoints.spec_type = dao::catalog::SpecType::AsyncApi.as_str();
+        for operation in dao::catalog::operations::get_async_operations(&api.api_spec) {
+            operations.push((operation, None));
+        }
+    }
+
+    for (operation, permission) in operations {
+        if filter.accept(&operation) {
             let endpoint = Endpoint {
-                name: String::from(val),
+                name: operation.path,
+                method: operation.method,
+                operation_id: operation.operation_id,
+                tags: operation.tags,
+                deprecated: operation.deprecated,
+                security: operation.security,
+                parameters: operation.parameters,
+                permission: permission,
             };
             endpoints.endpoints.push(endpoint);
         }
     }
-    HttpResponse::Ok().json(endpoints)
+    debug!(
+        "Got [{}] endpoints for spec [{}]",
+        endpoints.endpoints.len(),
+        &info.0
+    );
+
+    Ok(HttpResponse::Ok().json(endpoints))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -73,22 +171,54 @@ pub struct Spec {
     version: String,
     description: String,
     id: String,
+    //same for all the revisions of the spec, unlike id
+    stable_id: String,
     audience: String,
+    spec_type: String,
+    spec_version: String,
+    source: String,
+    //registered api the spec is linked to
+    api_id: Option<Uuid>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SpecsFilter {
+    source: Option<String>,
+    #[serde(rename = "ref")]
+    git_ref: Option<String>,
 }
 
 #[get("/v1/specs")]
-pub fn get_all_specs() -> HttpResponse {
+pub async fn get_all_specs(filter: web::Query<SpecsFilter>) -> Result<HttpResponse, ApiError> {
     debug!("get_all_specs()");
     let mut specs = Specs { specs: Vec::new() };
+    let owners = match dao::repo_apis::list_all_api_specs(&SETTINGS.database) {
+        Ok(owners) => owners,
+        Err(why) => {
+            error!("Unable to get the apis of the specs - reason [{}]", why);
+            std::collections::HashMap::new()
+        }
+    };
 
-    let mut all_specs = dao::catalog::list_specs(SETTINGS.catalog_path.as_str());
+    let sources = get_filtered_sources(&filter);
+    let git_ref = filter.git_ref.clone();
+    let listed =
+        dao::error::blocking(move || dao::catalog::list_catalog_at(&sources, git_ref.as_deref()));
+    let (mut all_specs, mut all_async_specs) = match listed.await {
+        Ok(catalog) => catalog,
+        Err(why) => {
+            error!("Unable to list specs - reason [{}]", why);
+            return Err(why);
+        }
+    };
     while let Some(spec) = all_specs.pop() {
         info!("Analysing file [{:?}]", spec.path);
-        let short_path =
-            dao::catalog::get_spec_short_path(String::from(&SETTINGS.catalog_dir), &spec);
+        let short_path = dao::catalog::get_spec_short_path(&SOURCES, &spec);
         let spec = Spec {
             name: String::from(short_path),
             id: spec.id,
+            api_id: owners.get(&spec.stable_id).copied(),
+            stable_id: spec.stable_id,
             title: spec.api_spec.info.title,
             version: spec.api_spec.info.version,
             description: match spec.api_spec.info.description {
@@ -96,10 +226,205 @@ pub fn get_all_specs() -> HttpResponse {
                 None => String::from(""),
             },
             audience: spec.audience,
+            spec_type: dao::catalog::SpecType::OpenApi.as_str(),
+            spec_version: spec.spec_version,
+            source: spec.source,
         };
         specs.specs.push(spec);
     }
-    HttpResponse::Ok().json(specs)
+    while let Some(spec) = all_async_specs.pop() {
+        info!("Analysing asyncapi file [{:?}]", spec.path);
+        let short_path = dao::catalog::get_async_spec_short_path(&SOURCES, &spec);
+        let spec = Spec {
+            name: String::from(short_path),
+            id: spec.id,
+            api_id: owners.get(&spec.stable_id).copied(),
+            stable_id: spec.stable_id,
+            title: spec.api_spec.info.title,
+            version: spec.api_spec.info.version,
+            description: match spec.api_spec.info.description {
+                Some(d) => d,
+                None => String::from(""),
+            },
+            audience: spec.audience,
+            spec_type: dao::catalog::SpecType::AsyncApi.as_str(),
+            spec_version: spec.api_spec.asyncapi,
+            source: spec.source,
+        };
+        specs.specs.push(spec);
+    }
+    Ok(HttpResponse::Ok().json(specs))
+}
+
+fn get_filtered_sources(filter: &SpecsFilter) -> Vec<settings::CatalogSource> {
+    SOURCES
+        .iter()
+        .filter(|source| match &filter.source {
+            Some(name) => &source.name == name,
+            None => true,
+        })
+        .cloned()
+        .collect()
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SpecErrors {
+    errors: Vec<dao::catalog::SpecLoadError>,
+}
+
+//files matching the patterns of the sources that could not be loaded as specs
+pub async fn get_spec_errors(filter: web::Query<SpecsFilter>) -> Result<HttpResponse, ApiError> {
+    debug!("get_spec_errors()");
+
+    let sources = get_filtered_sources(&filter);
+    let git_ref = filter.git_ref.clone();
+    let loaded =
+        dao::error::blocking(move || dao::catalog::load_catalog(&sources, git_ref.as_deref()));
+    match loaded.await {
+        Ok(catalog) => Ok(HttpResponse::Ok().json(SpecErrors {
+            errors: catalog.errors,
+        })),
+        Err(why) => {
+            error!("Unable to list spec errors - reason [{}]", why);
+            Err(why)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BundleFormat {
+    format: Option<String>,
+}
+
+//not a #[get] as the /v1/specs/{id} scope is declared in main.rs
+pub async fn get_bundled_spec(
+    path: web::Path<(String,)>,
+    query: web::Query<BundleFormat>,
+) -> Result<HttpResponse, ApiError> {
+    debug!("get_bundled_spec() - spec [{}]", &path.0);
+
+    Validator::new()
+        .check(
+            matches!(query.format.as_deref(), Some("yaml") | Some("json") | None),
+            "format",
+            "must be yaml or json",
+        )
+        .validate()?;
+    let id = path.0.clone();
+    let bundled = dao::error::blocking(move || dao::catalog::get_bundled_spec(&SOURCES, &id));
+    let bundled = match bundled.await {
+        Ok(bundled) => bundled,
+        Err(why) => {
+            error!("Unable to bundle spec [{}] - reason [{}]", &path.0, why);
+            return Err(why);
+        }
+    };
+
+    match query.format.as_deref() {
+        Some("json") => Ok(HttpResponse::Ok().json(dao::catalog::to_json(&bundled))),
+        _ => match serde_yaml::to_string(&bundled) {
+            Ok(content) => Ok(HttpResponse::Ok()
+                .content_type("application/x-yaml")
+                .body(content)),
+            Err(why) => Err(ApiError::Storage {
+                message: why.to_string(),
+            }),
+        },
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Comparison {
+    breaking: bool,
+    changes: Vec<dao::catalog::compare::Change>,
+}
+
+//changes to go from the spec {id} to {other_id}, being a blob id or a commit id
+pub async fn compare_specs(path: web::Path<(String, String)>) -> Result<HttpResponse, ApiError> {
+    debug!("compare_specs() - spec [{}] with [{}]", &path.0, &path.1);
+
+    let (id, other_id) = (path.0.clone(), path.1.clone());
+    let changes =
+        dao::error::blocking(move || dao::catalog::compare_specs(&SOURCES, &id, &other_id));
+    match changes.await {
+        Ok(changes) => {
+            let comparison = Comparison {
+                breaking: dao::catalog::compare::is_breaking(&changes),
+                changes: changes,
+            };
+            Ok(HttpResponse::Ok().json(comparison))
+        }
+        Err(why) => {
+            error!(
+                "Unable to compare spec [{}] with [{}] - reason [{}]",
+                &path.0, &path.1, why
+            );
+            Err(why)
+        }
+    }
+}
+
+pub async fn lint_spec(path: web::Path<(String,)>) -> Result<HttpResponse, ApiError> {
+    debug!("lint_spec() - spec [{}]", &path.0);
+
+    let id = path.0.clone();
+    let violations = dao::error::blocking(move || dao::catalog::lint_spec_by_id(&SOURCES, &id));
+    match violations.await {
+        Ok(violations) => Ok(HttpResponse::Ok().json(violations)),
+        Err(why) => {
+            error!("Unable to lint spec [{}] - reason [{}]", &path.0, why);
+            Err(why)
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SpecHistory {
+    revisions: Vec<dao::catalog::history::SpecRevision>,
+}
+
+//{id} being the stable id of the spec, or the id of one of its revisions
+pub async fn get_spec_history(path: web::Path<(String,)>) -> Result<HttpResponse, ApiError> {
+    debug!("get_spec_history() - spec [{}]", &path.0);
+
+    let id = path.0.clone();
+    let revisions = dao::error::blocking(move || dao::catalog::get_spec_history(&SOURCES, &id));
+    match revisions.await {
+        Ok(revisions) => Ok(HttpResponse::Ok().json(SpecHistory {
+            revisions: revisions,
+        })),
+        Err(why) => {
+            error!(
+                "Unable to get history of spec [{}] - reason [{}]",
+                &path.0, why
+            );
+            Err(why)
+        }
+    }
+}
+
+pub async fn get_spec_extensions(path: web::Path<(String,)>) -> Result<HttpResponse, ApiError> {
+    debug!("get_spec_extensions() - spec [{}]", &path.0);
+
+    let id = path.0.clone();
+    let extensions = dao::error::blocking(move || {
+        let extensions = match dao::catalog::get_spec(&SOURCES, &id).pop() {
+            Some(spec) => Some(spec.extensions),
+            None => dao::catalog::get_async_spec(&SOURCES, &id)
+                .pop()
+                .map(|spec| spec.extensions),
+        };
+        Ok::<_, ApiError>(extensions)
+    })
+    .await?;
+
+    match extensions {
+        Some(extensions) => Ok(HttpResponse::Ok().json(extensions)),
+        None => {
+            error!("Unable to get extensions of spec [{}]", &path.0);
+            Err(ApiError::not_found(format!("No spec [{}]", &path.0)))
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -110,7 +435,107 @@ pub struct Api {
     pub status: Status,
     pub domain_id: Uuid,
     pub domain_name: String,
+    //stable ids of the linked specs
     pub spec_ids: Vec<String>,
+    #[serde(default)]
+    pub specs: Vec<LinkedSpec>,
+}
+
+//a spec linked to an api, as of the current revision of the catalog
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LinkedSpec {
+    pub stable_id: String,
+    //none if the spec is no longer in the catalog
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiSpecs {
+    //stable ids or ids of one of their revisions
+    pub spec_ids: Vec<String>,
+}
+
+//the catalog (HEAD) is listed off the worker serving the request
+async fn list_current_catalog() -> Result<
+    (
+        Vec<dao::catalog::SpecItem>,
+        Vec<dao::catalog::AsyncSpecItem>,
+    ),
+    ApiError,
+> {
+    dao::error::blocking(|| dao::catalog::list_catalog_at(&SOURCES, None)).await
+}
+
+//specs of the catalog (HEAD) by stable id, along with the ids of their current revision
+async fn get_current_specs() -> Result<std::collections::HashMap<String, LinkedSpec>, ApiError> {
+    let (all_specs, all_async_specs) = list_current_catalog().await?;
+    let specs = all_specs.into_iter().map(|spec| LinkedSpec {
+        stable_id: spec.stable_id,
+        id: Some(spec.id),
+        title: Some(spec.api_spec.info.title),
+        version: Some(spec.api_spec.info.version),
+    });
+    let async_specs = all_async_specs.into_iter().map(|spec| LinkedSpec {
+        stable_id: spec.stable_id,
+        id: Some(spec.id),
+        title: Some(spec.api_spec.info.title),
+        version: Some(spec.api_spec.info.version),
+    });
+
+    Ok(specs
+        .chain(async_specs)
+        .map(|spec| (spec.stable_id.clone(), spec))
+        .collect())
+}
+
+fn get_linked_specs(
+    spec_ids: &[String],
+    current_specs: &std::collections::HashMap<String, LinkedSpec>,
+) -> Vec<LinkedSpec> {
+    spec_ids
+        .iter()
+        .map(|spec_id| match current_specs.get(spec_id) {
+            Some(spec) => spec.clone(),
+            None => LinkedSpec {
+                stable_id: spec_id.clone(),
+                id: None,
+                title: None,
+                version: None,
+            },
+        })
+        .collect()
+}
+
+//stable ids of the given specs, which must be in the catalog
+fn get_stable_spec_ids(
+    spec_ids: &[String],
+    current_specs: &std::collections::HashMap<String, LinkedSpec>,
+) -> Result<Vec<String>, ApiError> {
+    let stable_ids: Vec<Option<String>> = spec_ids
+        .iter()
+        .map(|spec_id| match current_specs.get(spec_id) {
+            Some(spec) => Some(spec.stable_id.clone()),
+            None => current_specs
+                .values()
+                .find(|spec| spec.id.as_ref() == Some(spec_id))
+                .map(|spec| spec.stable_id.clone()),
+        })
+        .collect();
+    spec_ids
+        .iter()
+        .zip(&stable_ids)
+        .fold(Validator::new(), |validator, (spec_id, stable_id)| {
+            validator.check(
+                stable_id.is_some(),
+                "spec_ids",
+                &format!("unknown spec [{}]", spec_id),
+            )
+        })
+        .validate()?;
+
+    Ok(stable_ids.into_iter().flatten().collect())
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -142,31 +567,52 @@ impl Status {
     }
 }
 
+//a change of status, e.g. {"status": "DEPRECATED", "actor": "jdoe", "comment": "replaced by v2"}
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatusChange {
+    pub status: Status,
+    pub actor: String,
+    pub comment: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct StatusHistory {
+    pub status: Status,
+    pub actor: String,
+    pub comment: Option<String>,
+    pub start_date_time: DateTime<Utc>,
+    //not set for the current status
+    pub end_date_time: Option<DateTime<Utc>>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Apis {
     pub apis: Vec<Api>,
 }
 
 #[post("/v1/apis")]
-pub fn create_api(api: Json<Api>) -> HttpResponse {
+pub async fn create_api(api: Json<Api>) -> Result<HttpResponse, ApiError> {
     info!("create api [{:?}]", api);
-
-    dao::repo_apis::add_api(&SETTINGS.database, &api.name, &api.domain_id).unwrap();
-
-    HttpResponse::Ok().json("")
+    Validator::new()
+        .not_blank(&api.name, "name")
+        .check(!api.domain_id.is_nil(), "domain_id", "must be set")
+        .validate()?;
+    //the domain and the specs must exist
+    dao::repo_domains::get_domain(&SETTINGS.database, api.domain_id)?;
+    let current_specs = get_current_specs().await?;
+    let spec_ids = get_stable_spec_ids(&api.spec_ids, &current_specs)?;
+
+    dao::repo_apis::add_api(&SETTINGS.database, &api.name, &api.domain_id, &spec_ids)?;
+
+    Ok(HttpResponse::Ok().json(""))
 }
 
 #[get("/v1/apis")]
-pub fn list_all_apis() -> HttpResponse {
+pub async fn list_all_apis() -> Result<HttpResponse, ApiError> {
     info!("list all apis");
 
-    let mut all_apis: Vec<ApiItem> = match dao::repo_apis::list_all_apis(&SETTINGS.database) {
-        Ok(all_apis) => all_apis,
-        Err(why) => {
-            error!("Unable to get apis: {}", why);
-            panic!();
-        }
-    };
+    let mut all_apis: Vec<ApiItem> = dao::repo_apis::list_all_apis(&SETTINGS.database)?;
+    let current_specs = get_current_specs().await?;
 
     let mut apis = Vec::new();
 
@@ -189,6 +635,7 @@ pub fn list_all_apis() -> HttpResponse {
                 domain
             }
         };
+        let spec_ids = dao::repo_apis::get_api_specs(&SETTINGS.database, api.id)?;
         //
         let api = Api {
             name: api.name,
@@ -197,23 +644,26 @@ pub fn list_all_apis() -> HttpResponse {
             status: Status::from_str(api.status),
             domain_id: domain.id,
             domain_name: domain.name,
-            spec_ids: Vec::new(), //TODO
+            specs: get_linked_specs(&spec_ids, &current_specs),
+            spec_ids: spec_ids,
         };
         apis.push(api);
     }
 
     let apis_obj = Apis { apis: apis };
 
-    HttpResponse::Ok().json(apis_obj)
+    Ok(HttpResponse::Ok().json(apis_obj))
 }
 
-pub fn get_api_by_id(path: web::Path<(String,)>) -> HttpResponse {
+pub async fn get_api_by_id(path: web::Path<(String,)>) -> Result<HttpResponse, ApiError> {
     info!("getting api for id [{:?}]", &path.0);
-    let api = Uuid::parse_str(&path.0).unwrap();
+    let api = dao::error::parse_uuid(&path.0, "api")?;
 
-    let api = dao::repo_apis::get_api_by_id(&SETTINGS.database, api).unwrap();
+    let api = dao::repo_apis::get_api_by_id(&SETTINGS.database, api)?;
 
-    let domain = dao::repo_domains::get_domain(&SETTINGS.database, api.domain_id).unwrap();
+    let domain = dao::repo_domains::get_domain(&SETTINGS.database, api.domain_id)?;
+    let spec_ids = dao::repo_apis::get_api_specs(&SETTINGS.database, api.id)?;
+    let current_specs = get_current_specs().await?;
 
     let api = Api {
         id: api.id,
@@ -222,38 +672,128 @@ pub fn get_api_by_id(path: web::Path<(String,)>) -> HttpResponse {
         status: Status::from_str(api.status),
         domain_id: domain.id,
         domain_name: domain.name,
-        spec_ids: Vec::new(), //TODO
+        specs: get_linked_specs(&spec_ids, &current_specs),
+        spec_ids: spec_ids,
     };
 
-    HttpResponse::Ok().json(api)
+    Ok(HttpResponse::Ok().json(api))
+}
+
+//not a #[get] as the /v1/apis/{api} scope is declared in main.rs
+pub async fn get_reconciliation() -> Result<HttpResponse, ApiError> {
+    debug!("get_reconciliation()");
+
+    let (all_specs, all_async_specs) = list_current_catalog().await?;
+    let reconciliation =
+        dao::repo_apis::reconcile_apis(&SETTINGS.database, &all_specs, &all_async_specs)?;
+
+    Ok(HttpResponse::Ok().json(reconciliation))
+}
+
+//the given specs are added to the ones already linked to the api
+pub async fn link_api_specs(
+    path: web::Path<(String,)>,
+    specs: Json<ApiSpecs>,
+) -> Result<HttpResponse, ApiError> {
+    info!("linking specs [{:?}] to api [{}]", specs.spec_ids, &path.0);
+    let api_id = dao::error::parse_uuid(&path.0, "api")?;
+    Validator::new()
+        .check(!specs.spec_ids.is_empty(), "spec_ids", "must not be empty")
+        .validate()?;
+    dao::repo_apis::get_api_by_id(&SETTINGS.database, api_id)?;
+
+    let current_specs = get_current_specs().await?;
+    let spec_ids = get_stable_spec_ids(&specs.spec_ids, &current_specs)?;
+    dao::repo_apis::link_specs(&SETTINGS.database, api_id, &spec_ids)?;
+
+    let spec_ids = dao::repo_apis::get_api_specs(&SETTINGS.database, api_id)?;
+    Ok(HttpResponse::Ok().json(get_linked_specs(&spec_ids, &current_specs)))
+}
+
+//{spec_id} being the stable id of the spec, or the id of its current revision
+pub async fn unlink_api_spec(path: web::Path<(String, String)>) -> Result<HttpResponse, ApiError> {
+    info!("unlinking spec [{}] from api [{}]", &path.1, &path.0);
+    let api_id = dao::error::parse_uuid(&path.0, "api")?;
+
+    //a spec no longer in the catalog can still be unlinked by its stable id
+    let current_specs = get_current_specs().await?;
+    let spec_id = get_stable_spec_ids(std::slice::from_ref(&path.1), &current_specs)
+        .ok()
+        .and_then(|mut stable_ids| stable_ids.pop())
+        .unwrap_or_else(|| path.1.clone());
+    match dao::repo_apis::unlink_spec(&SETTINGS.database, api_id, &spec_id)? {
+        0 => Err(ApiError::not_found(format!(
+            "Spec [{}] is not linked to api [{}]",
+            &path.1, api_id
+        ))),
+        _ => Ok(HttpResponse::Ok().json("")),
+    }
 }
 
-pub fn update_api_status_by_id(path: web::Path<(String,)>, status: Json<Status>) -> HttpResponse {
+//the transitions not part of the lifecycle are rejected, unless allowed by the settings
+pub async fn update_api_status_by_id(
+    path: web::Path<(String,)>,
+    change: Json<StatusChange>,
+) -> Result<HttpResponse, ApiError> {
     //path: web::Path<(String,)>,
     //&path.0
-    info!("updating api for id [{:?}]", &path.0);
+    info!("updating api for id [{:?}] - [{:?}]", &path.0, change);
+    Validator::new()
+        .not_blank(&change.actor, "actor")
+        .validate()?;
 
     let status_item = StatusItem {
-        api_id: Uuid::parse_str(&path.0).unwrap(),
-        status: status.as_str(),
+        api_id: dao::error::parse_uuid(&path.0, "api")?,
+        status: change.status.as_str(),
     };
+    dao::repo_apis::get_api_by_id(&SETTINGS.database, status_item.api_id)?;
 
-    dao::repo_apis::update_api_status(&SETTINGS.database, status_item).unwrap();
+    dao::repo_apis::update_api_status(
+        &SETTINGS.database,
+        status_item,
+        change.actor.trim(),
+        change.comment.as_deref(),
+        &SETTINGS.status_transitions,
+    )?;
 
-    HttpResponse::Ok().json("")
+    Ok(HttpResponse::Ok().json(""))
 }
 
-pub fn update_api_tier_by_id(path: web::Path<(String,)>, tier: Json<String>) -> HttpResponse {
+pub async fn get_api_status_history(path: web::Path<(String,)>) -> Result<HttpResponse, ApiError> {
+    info!("getting status history for api [{:?}]", &path.0);
+    let api_id = dao::error::parse_uuid(&path.0, "api")?;
+    dao::repo_apis::get_api_by_id(&SETTINGS.database, api_id)?;
+
+    let history: Vec<StatusHistory> =
+        dao::repo_apis::get_status_history(&SETTINGS.database, api_id)?
+            .into_iter()
+            .map(|item| StatusHistory {
+                status: Status::from_str(item.status),
+                actor: item.actor,
+                comment: item.comment,
+                start_date_time: item.start_date_time,
+                end_date_time: item.end_date_time,
+            })
+            .collect();
+
+    Ok(HttpResponse::Ok().json(history))
+}
+
+pub async fn update_api_tier_by_id(
+    path: web::Path<(String,)>,
+    tier: Json<String>,
+) -> Result<HttpResponse, ApiError> {
     //path: web::Path<(String,)>,
     //&path.0
     info!("updating api for id [{:?}] and tier [{}]", &path.0, tier);
 
-    let api_id = Uuid::parse_str(&path.0).unwrap();
-    let tier_id = Uuid::parse_str(tier.as_str()).unwrap();
+    let api_id = dao::error::parse_uuid(&path.0, "api")?;
+    let tier_id = dao::error::parse_uuid(tier.as_str(), "tier")?;
+    dao::repo_apis::get_api_by_id(&SETTINGS.database, api_id)?;
 
-    dao::repo_apis::update_api_tier(&SETTINGS.database, api_id, tier_id).unwrap();
+    dao::repo_apis::update_api_tier(&SETTINGS.database, api_id, tier_id)?;
 
-    HttpResponse::Ok().json("")
+    Ok(HttpResponse::Ok().json(""))
 }
 
 //
@@ -294,10 +834,10 @@ pub struct User {
 }
 
 #[get("/v1/pull-requests")]
-pub fn get_oldest_pr() -> HttpResponse {
+pub async fn get_oldest_pr() -> Result<HttpResponse, ApiError> {
     let limit = 3;
     info!("get oldest pull-request");
-    let pull_requests: PullRequests = get_pull_requests("OPEN");
+    let pull_requests: PullRequests = get_pull_requests("OPEN")?;
 
     let current_epoch = std::time::SystemTime::now();
     let current_epoch = current_epoch.duration_since(std::time::UNIX_EPOCH).unwrap();
@@ -324,20 +864,20 @@ pub fn get_oldest_pr() -> HttpResponse {
     let pull_requests: Vec<_> = pull_requests.iter().map(|val| val.0).take(limit).collect();
 
     //
-    HttpResponse::Ok().json(pull_requests)
+    Ok(HttpResponse::Ok().json(pull_requests))
 }
 
 #[get("/v1/merged-pull-requests")]
-pub fn get_merged_pr() -> HttpResponse {
+pub async fn get_merged_pr() -> Result<HttpResponse, ApiError> {
     info!("get merged pull-request");
-    let pull_requests: PullRequests = get_pull_requests("MERGED");
+    let pull_requests: PullRequests = get_pull_requests("MERGED")?;
 
     let pull_requests: Vec<_> = pull_requests.values;
     //
-    HttpResponse::Ok().json(pull_requests)
+    Ok(HttpResponse::Ok().json(pull_requests))
 }
 
-pub fn get_pull_requests(status: &str) -> PullRequests {
+pub fn get_pull_requests(status: &str) -> Result<PullRequests, ApiError> {
     let access_token = SETTINGS.stash_config.access_token.clone();
     let client = Client::new();
 
@@ -348,14 +888,13 @@ pub fn get_pull_requests(status: &str) -> PullRequests {
     let mut resp = client
         .get(url.as_str())
         .header("Authorization", format!("Bearer {}", access_token))
-        .send()
-        .unwrap();
+        .send()?
+        .error_for_status()?;
 
     debug!("Calling {} - got HTTP Status {:?}", url, resp.status());
-    //TODO manage unwrap withe resp.status().is_success() or is_server_error()
-    let pull_requests: PullRequests = resp.json().unwrap();
+    let pull_requests: PullRequests = resp.json()?;
 
-    pull_requests
+    Ok(pull_requests)
 }
 
 //
@@ -365,6 +904,7 @@ pub enum ObjectType {
     PATH,
     AUDIENCE,
     PERMISSION,
+    OPERATION,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -374,6 +914,7 @@ pub struct Diff {
     #[serde(rename(serialize = "objectType", deserialize = "type"))]
     pub object_type: ObjectType,
     pub line: String,
+    pub pointer: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -381,6 +922,8 @@ pub struct Review {
     pub id: i32,
     pub title: String,
     pub diffs: Vec<Diff>,
+    //None if none of the changed files is an openapi spec
+    pub breaking: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -389,28 +932,17 @@ pub struct Reviews {
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
-struct Line {
-    source: u64,
-    destination: u64,
-    line: String,
-    truncated: bool,
-}
-
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-struct Segment {
-    #[serde(rename(serialize = "type", deserialize = "type"))]
-    typ: String,
-    lines: Vec<Line>,
-}
-
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
-struct Hunk {
-    segments: Vec<Segment>,
+struct DiffPath {
+    #[serde(rename(serialize = "toString", deserialize = "toString"))]
+    to_string: String,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 struct PullRequestDiff {
-    hunks: Vec<Hunk>,
+    //none if the file has been added
+    source: Option<DiffPath>,
+    //none if the file has been deleted
+    destination: Option<DiffPath>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -420,14 +952,55 @@ struct PullRequestDiffs {
     pub diffs: Vec<PullRequestDiff>,
 }
 
+fn get_file_content(client: &Client, path: &Option<DiffPath>, at: &str) -> Option<Vec<u8>> {
+    let path = match path {
+        Some(path) => &path.to_string,
+        None => return None,
+    };
+    let access_token = SETTINGS.stash_config.access_token.clone();
+    let url = format!("{}/raw/{}?at={}", SETTINGS.stash_config.base_uri, path, at);
+
+    match client
+        .get(url.as_str())
+        .header("Authorization", format!("Bearer {}", access_token))
+        .send()
+    {
+        Ok(mut resp) if resp.status().is_success() => {
+            let mut content = Vec::new();
+            match resp.copy_to(&mut content) {
+                Ok(_) => Some(content),
+                Err(why) => {
+                    error!("Unable to read [{}] - reason [{}]", url, why);
+                    None
+                }
+            }
+        }
+        Ok(resp) => {
+            error!("Calling {} - got HTTP Status {:?}", url, resp.status());
+            None
+        }
+        Err(why) => {
+            error!("Unable to call [{}] - reason [{}]", url, why);
+            None
+        }
+    }
+}
+
+fn is_yaml_file(path: &Option<DiffPath>) -> bool {
+    match path {
+        Some(path) => path.to_string.ends_with(".yaml") || path.to_string.ends_with(".yml"),
+        None => false,
+    }
+}
+
 #[get("/v1/reviews")]
-pub fn list_all_reviews() -> HttpResponse {
+pub async fn list_all_reviews() -> Result<HttpResponse, ApiError> {
     info!("list all reviews");
 
     let mut reviews = Vec::new();
 
     //get all Opened PRs
-    let pull_requests: PullRequests = get_pull_requests("OPEN");
+    let pull_requests: PullRequests = get_pull_requests("OPEN")?;
 
     //for each PR, get diff
     let access_token = SETTINGS.stash_config.access_token.clone();
@@ -443,63 +1016,59 @@ pub fn list_all_reviews() -> HttpResponse {
         let mut resp = client
             .get(url.as_str())
             .header("Authorization", format!("Bearer {}", access_token))
-            .send()
-            .unwrap();
+            .send()?
+            .error_for_status()?;
 
-        let response: PullRequestDiffs = resp.json().unwrap();
+        let response: PullRequestDiffs = resp.json()?;
 
+        //the changed specs are compared as a whole (before / after) instead of line per line
         let mut diffs: Vec<Diff> = Vec::new();
+        let mut breaking: Option<bool> = None;
         for diff in &response.diffs {
-            for hunk in &diff.hunks {
-                for segment in &hunk.segments {
-                    if "ADDED".eq_ignore_ascii_case(segment.typ.as_str())
-                        || "REMOVED".eq_ignore_ascii_case(segment.typ.as_str())
-                    {
-                        for line in &segment.lines {
-                            if line.line.trim_start().starts_with("/") {
-                                let diff = Diff {
-                                    typ: String::from(&segment.typ),
-                                    object_type: ObjectType::PATH,
-                                    line: String::from(&line.line),
-                                };
-                                diffs.push(diff);
-                            } else if line.line.trim_start().starts_with("x-zally-ignore") {
-                                let diff = Diff {
-                                    typ: String::from(&segment.typ),
-                                    object_type: ObjectType::ZALLY,
-                                    line: String::from(&line.line),
-                                };
-                                diffs.push(diff);
-                            } else if line.line.trim_start().starts_with("x-has-authority") {
-                                let diff = Diff {
-                                    typ: String::from(&segment.typ),
-                                    object_type: ObjectType::PERMISSION,
-                                    line: String::from(&line.line),
-                                };
-                                diffs.push(diff);
-                            } else if line.line.trim_start().starts_with("x-audience") {
-                                let diff = Diff {
-                                    typ: String::from(&segment.typ),
-                                    object_type: ObjectType::AUDIENCE,
-                                    line: String::from(&line.line),
-                                };
-                                diffs.push(diff);
-                            } else {
-                                debug!(
-                                    "line [{:?}] - does not contain interesting information",
-                                    line.line
-                                );
-                            }
-                        }
-                    }
-                }
+            if !is_yaml_file(&diff.source) && !is_yaml_file(&diff.destination) {
+                debug!(
+                    "diff [{:?}] - does not contain interesting information",
+                    diff
+                );
+                continue;
             }
+            let before = get_file_content(&client, &diff.source, &response.fromHash);
+            let after = get_file_content(&client, &diff.destination, &response.toHash);
+
+            let (items, file_breaking) =
+                dao::catalog::review_spec_contents(before.as_deref(), after.as_deref());
+            debug!(
+                "diff [{:?}] - got [{}] review items and breaking [{:?}]",
+                diff,
+                items.len(),
+                file_breaking
+            );
+            for item in items {
+                let diff = Diff {
+                    typ: item.typ,
+                    object_type: match item.object {
+                        dao::catalog::review::ReviewObject::Operation => ObjectType::OPERATION,
+                        dao::catalog::review::ReviewObject::ZallyIgnore => ObjectType::ZALLY,
+                        dao::catalog::review::ReviewObject::Audience => ObjectType::AUDIENCE,
+                        dao::catalog::review::ReviewObject::Permission => ObjectType::PERMISSION,
+                    },
+                    line: item.value,
+                    pointer: item.pointer,
+                };
+                diffs.push(diff);
+            }
+            breaking = match (breaking, file_breaking) {
+                (Some(val), Some(file_val)) => Some(val || file_val),
+                (None, file_val) => file_val,
+                (val, None) => val,
+            };
         }
 
         let review = Review {
             id: pr_id,
             title: pr_title,
             diffs: diffs,
+            breaking: breaking,
         };
 
         reviews.push(review);
@@ -507,5 +1076,5 @@ pub fn list_all_reviews() -> HttpResponse {
 
     let response = Reviews { reviews: reviews };
 
-    HttpResponse::Ok().json(response)
+    Ok(HttpResponse::Ok().json(response))
 }
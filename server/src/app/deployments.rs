@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 
 #[path = "../dao/mod.rs"]
 mod dao;
+use dao::error::{ApiError, Validator};
 use dao::repo_deployments::*;
 
 use log::{debug, error};
@@ -33,15 +34,19 @@ pub struct Deployments {
 }
 
 #[post("/v1/deployments")]
-pub fn add_deployment(deployment: Json<Deployment>) -> HttpResponse {
+pub async fn add_deployment(deployment: Json<Deployment>) -> Result<HttpResponse, ApiError> {
+    Validator::new()
+        .not_blank(&deployment.api, "api")
+        .not_blank(&deployment.env, "env")
+        .validate()?;
+
     release(
         &SETTINGS.database,
         deployment.api.clone(),
         deployment.env.clone(),
-    )
-    .unwrap();
+    )?;
 
-    HttpResponse::Ok().json("")
+    Ok(HttpResponse::Ok().json(""))
 }
 
 #[get("/v1/deployments")]
@@ -8,6 +8,7 @@ use std::hash::{Hash, Hasher};
 #[path = "../dao/mod.rs"]
 mod dao;
 use dao::catalog::*;
+use dao::error::{ApiError, Validator};
 use dao::repo_domains::*;
 
 #[path = "./apis.rs"]
@@ -23,6 +24,7 @@ use uuid::Uuid;
 
 lazy_static! {
     static ref SETTINGS: settings::Settings = Settings::new().unwrap();
+    static ref SOURCES: Vec<settings::CatalogSource> = SETTINGS.get_sources();
 }
 
 /*
@@ -71,6 +73,22 @@ pub struct DomainError {
     pub spec_domain: String,
     pub spec_path: String,
     pub resources: usize,
+    //how the domain of the spec has been derived
+    pub explanation: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpecDomain {
+    pub spec_path: String,
+    pub spec_id: String,
+    pub domain: String,
+    pub rule: Option<usize>,
+    pub explanation: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpecDomains {
+    pub specs: Vec<SpecDomain>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -79,67 +97,68 @@ pub struct DomainErrors {
 }
 
 #[get("/v1/domains/errors")]
-pub fn get_domains_errors() -> HttpResponse {
+pub async fn get_domains_errors(
+    catalog_ref: web::Query<apis::CatalogRef>,
+) -> Result<HttpResponse, ApiError> {
     info!("get domains errors");
 
     //get all specs
-    let all_specs: Vec<SpecItem> = dao::catalog::list_specs(SETTINGS.catalog_path.as_str());
-    //at this stage data = {"N/A - servers not specified": 11, "/v1/settlement/operational-arrangement": 8, "/v1/market-risk/scenarios": 10,....
-    let data: std::collections::HashMap<String, usize> =
-        dao::catalog::get_endpoints_num_per_subdomain(&all_specs);
-
-    //get all declared (and official) domains
-    let all_domains: Vec<String> = match list_all_domains(&SETTINGS.database) {
-        Ok(all_domains) => all_domains
-            .iter()
-            .map(|val| String::from(&val.name))
-            .collect(),
+    let git_ref = catalog_ref.git_ref.clone();
+    let listed =
+        dao::error::blocking(move || dao::catalog::list_catalog_at(&SOURCES, git_ref.as_deref()));
+    let (all_specs, all_async_specs): (Vec<SpecItem>, Vec<AsyncSpecItem>) = match listed.await {
+        Ok(catalog) => catalog,
         Err(why) => {
-            panic!("Unable to get domains: {}", why);
+            error!("Unable to list specs - reason [{}]", why);
+            return Err(why);
         }
     };
+    //at this stage data = {"NA - no domain rule matched": 11, "/v1/settlement/operational-arrangement": 8, "/v1/market-risk/scenarios": 10,....
+    let data: std::collections::HashMap<String, usize> =
+        dao::catalog::get_endpoints_num_per_subdomain(&all_specs, &all_async_specs);
+
+    //get all declared (and official) domains
+    let all_domains: Vec<String> = list_all_domains(&SETTINGS.database)?
+        .iter()
+        .map(|val| String::from(&val.name))
+        .collect();
 
     //make the check
     let mut errors: Vec<DomainError> = Vec::new();
-    for spec in &all_specs {
-        let short_path =
-            dao::catalog::get_spec_short_path(String::from(&SETTINGS.catalog_dir), &spec);
-        let spec_domain = &spec.domain;
-        //will loop over all_domains to check if domains "match or not". contains() cannot work as the yml contains /v1 and not the domain
-        let mut is_contained = false;
-        for domain in &all_domains {
-            match spec_domain.contains(domain.as_str()) {
-                true => {
-                    // info!(
-                    //     "[{}] is contained - [{}] resources - spec [{}]",
-                    //     &spec.domain,
-                    //     data.get(&spec.domain).unwrap(),
-                    //     short_path
-                    // );
-                    is_contained = true;
-                    break;
-                }
-                false => {
-                    // info!(
-                    //     "[{}] is *not* contained - [{}] resources - spec [{}]",
-                    //     &spec.domain,
-                    //     data.get(&spec.domain).unwrap(),
-                    //     short_path
-                    // );
-                    is_contained = false;
-                }
-            }
-            debug!(
-                "Matching [{}] with [{}] - is_contained [{}]",
-                spec_domain, domain, is_contained
-            );
-        }
+    //asyncapi specs are checked as well as the openapi ones
+    let specs_domains = all_specs
+        .iter()
+        .map(|spec| {
+            (
+                dao::catalog::get_spec_short_path(&SOURCES, spec),
+                &spec.domain_match,
+            )
+        })
+        .chain(all_async_specs.iter().map(|spec| {
+            (
+                dao::catalog::get_async_spec_short_path(&SOURCES, spec),
+                &spec.domain_match,
+            )
+        }));
+    for (short_path, domain_match) in specs_domains {
+        let spec_domain = &domain_match.domain;
+        //contains() on the list cannot work as the yml contains /v1 and not the domain
+        let is_contained = dao::catalog::domain_rules::find_domain(
+            spec_domain,
+            all_domains.iter().map(String::as_str),
+        )
+        .is_some();
+        debug!(
+            "Matching [{}] with the domains - is_contained [{}]",
+            spec_domain, is_contained
+        );
 
         if !is_contained {
             let error = DomainError {
-                spec_domain: String::from(&spec.domain),
+                spec_domain: String::from(spec_domain),
                 spec_path: String::from(short_path),
-                resources: *data.get(&spec.domain).unwrap(),
+                resources: data.get(spec_domain).copied().unwrap_or(0),
+                explanation: domain_match.explanation.clone(),
             };
 
             errors.push(error);
@@ -149,17 +168,28 @@ pub fn get_domains_errors() -> HttpResponse {
     //return the response
     let errors = DomainErrors { errors: errors };
 
-    HttpResponse::Ok().json(errors)
+    Ok(HttpResponse::Ok().json(errors))
 }
 
 #[get("/v1/domains/stats")]
-pub fn get_domains_stats() -> HttpResponse {
+pub async fn get_domains_stats(
+    catalog_ref: web::Query<apis::CatalogRef>,
+) -> Result<HttpResponse, ApiError> {
     info!("get domains stats");
 
-    let all_specs: Vec<SpecItem> = dao::catalog::list_specs(SETTINGS.catalog_path.as_str());
+    let git_ref = catalog_ref.git_ref.clone();
+    let listed =
+        dao::error::blocking(move || dao::catalog::list_catalog_at(&SOURCES, git_ref.as_deref()));
+    let (all_specs, all_async_specs): (Vec<SpecItem>, Vec<AsyncSpecItem>) = match listed.await {
+        Ok(catalog) => catalog,
+        Err(why) => {
+            error!("Unable to list specs - reason [{}]", why);
+            return Err(why);
+        }
+    };
 
     let data: std::collections::HashMap<String, usize> =
-        dao::catalog::get_endpoints_num_per_subdomain(&all_specs);
+        dao::catalog::get_endpoints_num_per_subdomain(&all_specs, &all_async_specs);
 
     //at this stage the  data structure contains
     //{"N/A - servers not specified": 11, "/v1/settlement/operational-arrangement": 8, "/v1/market-risk/scenarios": 10,....
@@ -227,18 +257,46 @@ pub fn get_domains_stats() -> HttpResponse {
     }
 
     let response_as_vec: Vec<Node> = response.into_iter().collect();
-    HttpResponse::Ok().json(response_as_vec)
+    Ok(HttpResponse::Ok().json(response_as_vec))
+}
+
+//which rule gave the domain of each spec
+#[get("/v1/domains/specs")]
+pub async fn get_specs_domains() -> Result<HttpResponse, ApiError> {
+    info!("get specs domains");
+
+    let (all_specs, all_async_specs): (Vec<SpecItem>, Vec<AsyncSpecItem>) =
+        dao::error::blocking(|| dao::catalog::list_catalog_at(&SOURCES, None)).await?;
+
+    let mut specs = Vec::new();
+    for spec in &all_specs {
+        let domain_match = spec.domain_match.clone();
+        specs.push(SpecDomain {
+            spec_path: String::from(dao::catalog::get_spec_short_path(&SOURCES, spec)),
+            spec_id: String::from(&spec.id),
+            domain: domain_match.domain,
+            rule: domain_match.rule,
+            explanation: domain_match.explanation,
+        });
+    }
+    for spec in &all_async_specs {
+        let domain_match = spec.domain_match.clone();
+        specs.push(SpecDomain {
+            spec_path: String::from(dao::catalog::get_async_spec_short_path(&SOURCES, spec)),
+            spec_id: String::from(&spec.id),
+            domain: domain_match.domain,
+            rule: domain_match.rule,
+            explanation: domain_match.explanation,
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(SpecDomains { specs: specs }))
 }
 
 #[get("/v1/domains")]
-pub fn get_domains() -> HttpResponse {
+pub async fn get_domains() -> Result<HttpResponse, ApiError> {
     info!("get domains");
-    let mut all_domains: Vec<DomainItem> = match list_all_domains(&SETTINGS.database) {
-        Ok(all_domains) => all_domains,
-        Err(why) => {
-            panic!("Unable to get domains: {}", why);
-        }
-    };
+    let mut all_domains: Vec<DomainItem> = list_all_domains(&SETTINGS.database)?;
 
     let mut domains = Vec::new();
 
@@ -254,52 +312,47 @@ pub fn get_domains() -> HttpResponse {
 
     let domains_obj = Domains { domains: domains };
 
-    HttpResponse::Ok().json(domains_obj)
+    Ok(HttpResponse::Ok().json(domains_obj))
 }
 
 #[post("/v1/domains")]
-pub fn create_domain(domain: Json<Domain>) -> HttpResponse {
+pub async fn create_domain(domain: Json<Domain>) -> Result<HttpResponse, ApiError> {
+    info!("create domain [{:?}]", domain);
+    Validator::new()
+        .not_blank(&domain.name, "name")
+        .not_blank(&domain.owner, "owner")
+        .validate()?;
+
     let uuid = add_domain(
         &SETTINGS.database,
         &domain.name,
         &domain.description,
         &domain.owner,
-    )
-    .unwrap();
+    )?;
 
-    HttpResponse::Created()
+    Ok(HttpResponse::Created()
         .header("Location", format!("/v1/domains/{}", uuid))
-        .finish()
+        .finish())
 }
 
-pub fn delete_domain(path: web::Path<(String,)>) -> HttpResponse {
+pub async fn delete_domain(path: web::Path<(String,)>) -> Result<HttpResponse, ApiError> {
     //path: web::Path<(String,)>,
     //&path.0
     info!("deleting domain for id [{:?}]", &path.0);
-    let id = Uuid::parse_str(&path.0).unwrap();
+    let id = dao::error::parse_uuid(&path.0, "id")?;
+    get_domain(&SETTINGS.database, id)?;
 
     //check if apis are related to this domain
-    let response = match dao::repo_apis::get_apis_per_domain_id(&SETTINGS.database, id) {
-        Ok(api) => {
-            if api.len() != 0 {
-                error!("Domain [{}] has some APIs attached - cannot be deleted", id);
-                HttpResponse::PreconditionFailed().json(format!(
-                    "Domain [{}] has [{}] apis attached",
-                    id,
-                    api.len()
-                ))
-            } else {
-                info!("No APIs related to domain [{}]", id);
-                dao::repo_domains::delete_domain(&SETTINGS.database, id).unwrap();
-                HttpResponse::Ok().json("")
-            }
-        }
-        Err(why) => {
-            //if not, delete the domain
-            error!("Error while deleting domain [{}] - [{:?}]", id, why);
-            HttpResponse::BadRequest().json("Error while deleting domain")
-        }
-    };
+    let apis = dao::repo_apis::get_apis_per_domain_id(&SETTINGS.database, id)?;
+    if !apis.is_empty() {
+        error!("Domain [{}] has some APIs attached - cannot be deleted", id);
+        return Err(ApiError::Conflict {
+            message: format!("Domain [{}] has [{}] apis attached", id, apis.len()),
+        });
+    }
+
+    info!("No APIs related to domain [{}]", id);
+    dao::repo_domains::delete_domain(&SETTINGS.database, id)?;
 
-    response
+    Ok(HttpResponse::Ok().json(""))
 }
@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 
 #[path = "../dao/mod.rs"]
 mod dao;
+use dao::error::{ApiError, Validator};
 use dao::repo_envs::*;
 
 use log::{debug, info};
@@ -36,35 +37,27 @@ pub struct Env {
 }
 
 #[post("/v1/envs")]
-pub fn create_env(env: Json<Env>) -> HttpResponse {
+pub async fn create_env(env: Json<Env>) -> Result<HttpResponse, ApiError> {
     info!("create env [{:?}]", env);
-    add_env(&SETTINGS.database, &env.name, &env.description).unwrap();
+    Validator::new().not_blank(&env.name, "name").validate()?;
+    //env names are unique, a duplicate is reported as a conflict
+    add_env(&SETTINGS.database, &env.name, &env.description)?;
 
-    HttpResponse::Ok().json("")
+    Ok(HttpResponse::Ok().json(""))
 }
 
-pub fn get_env(path: web::Path<(String,)>) -> HttpResponse {
-    let env_id = Uuid::parse_str(&path.0).unwrap();
+pub async fn get_env(path: web::Path<(String,)>) -> Result<HttpResponse, ApiError> {
+    let env_id = dao::error::parse_uuid(&path.0, "id")?;
 
-    let response = match dao::repo_envs::get_env(&SETTINGS.database, env_id) {
-        Ok(env) => {
-            let returned_env = Env {
-                id: env.id,
-                name: env.name,
-                description: env.description,
-            };
-            debug!("Got Env [{:?}]", returned_env);
-
-            HttpResponse::Ok().json(returned_env)
-        }
-        Err(_) => {
-            debug!("No Env found for api [{:?}]", &path.0);
-
-            HttpResponse::NotFound().finish()
-        }
+    let env = dao::repo_envs::get_env(&SETTINGS.database, env_id)?;
+    let returned_env = Env {
+        id: env.id,
+        name: env.name,
+        description: env.description,
     };
+    debug!("Got Env [{:?}]", returned_env);
 
-    response
+    Ok(HttpResponse::Ok().json(returned_env))
 }
 
 #[get("/v1/envs")]
@@ -1,5 +1,5 @@
-use actix_web::HttpResponse;
 use actix_web::{get, post};
+use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 
 extern crate reqwest;
@@ -8,18 +8,19 @@ use reqwest::Client;
 #[path = "../dao/mod.rs"]
 mod dao;
 use dao::catalog::*;
+use dao::error::{ApiError, Validator};
 
 #[path = "./apis.rs"]
 mod apis;
 use apis::*;
 
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 
 #[path = "../settings/mod.rs"]
 mod settings;
 use settings::Settings;
 
-use chrono::{DateTime, TimeZone, Utc};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
 
 extern crate histogram;
 use histogram::Histogram;
@@ -28,6 +29,7 @@ use std::convert::TryFrom;
 
 lazy_static! {
     static ref SETTINGS: settings::Settings = Settings::new().unwrap();
+    static ref SOURCES: Vec<settings::CatalogSource> = SETTINGS.get_sources();
 }
 
 /*
@@ -39,8 +41,16 @@ pub struct Metrics {
     pub pr_num: Vec<(DateTime<Utc>, i32)>,
     pub pr_ages: Vec<(DateTime<Utc>, i64, i64, i64, i64)>,
     pub endpoints_num: Vec<(DateTime<Utc>, i32)>, //Vec<(DateTime<Utc>, Option<String>, Option<String>, i32)>,
-    pub zally_violations: Vec<(DateTime<Utc>, std::collections::HashMap<i64, usize>)>,
+    pub operations_num: Vec<(DateTime<Utc>, i32)>,
+    //messages of the asyncapi specs, openapi specs have none
+    pub messages_num: Vec<(DateTime<Utc>, i32)>,
+    pub zally_violations: Vec<(DateTime<Utc>, std::collections::HashMap<String, usize>)>,
+    pub lint_violations: Vec<(DateTime<Utc>, std::collections::HashMap<String, usize>)>,
+    pub zally_violations_per_severity: Vec<(DateTime<Utc>, Vec<rules::SeverityStats>)>,
     pub endpoints_num_per_audience: Vec<(DateTime<Utc>, std::collections::HashMap<String, usize>)>,
+    pub churn: Vec<(DateTime<Utc>, Vec<churn::ChurnStats>)>,
+    //files matching the patterns that could not be loaded as specs
+    pub spec_errors: Vec<(DateTime<Utc>, i32)>,
 }
 
 #[get("/v1/metrics")]
@@ -80,7 +90,28 @@ pub fn get_all_metrics() -> HttpResponse {
             }
         };
 
-    let zally_ignore_timeseries: Vec<(DateTime<Utc>, std::collections::HashMap<i64, usize>)> =
+    let operations_number: Vec<(DateTime<Utc>, i32)> =
+        match dao::repo_metrics::get_metrics_operations_number(&SETTINGS.database) {
+            Ok(val) => val.points,
+            Err(why) => {
+                error!(
+                    "Error while getting get_metrics_operations_number [{}]",
+                    why
+                );
+                Vec::new()
+            }
+        };
+
+    let messages_number: Vec<(DateTime<Utc>, i32)> =
+        match dao::repo_metrics::get_metrics_messages_number(&SETTINGS.database) {
+            Ok(val) => val.points,
+            Err(why) => {
+                error!("Error while getting get_metrics_messages_number [{}]", why);
+                Vec::new()
+            }
+        };
+
+    let zally_ignore_timeseries: Vec<(DateTime<Utc>, std::collections::HashMap<String, usize>)> =
         match dao::repo_metrics::get_metrics_zally_ignore(&SETTINGS.database) {
             Ok(val) => val.points,
             Err(why) => {
@@ -88,6 +119,20 @@ pub fn get_all_metrics() -> HttpResponse {
                 Vec::new()
             }
         };
+    let lint_violations_timeseries: Vec<(DateTime<Utc>, std::collections::HashMap<String, usize>)> =
+        match dao::repo_metrics::get_metrics_lint_violations(&SETTINGS.database) {
+            Ok(val) => val.points,
+            Err(why) => {
+                error!("Error while getting get_metrics_lint_violations [{}]", why);
+                Vec::new()
+            }
+        };
+    let rules = rules::get_rules(&SOURCES);
+    let zally_ignore_per_severity: Vec<(DateTime<Utc>, Vec<rules::SeverityStats>)> =
+        zally_ignore_timeseries
+            .iter()
+            .map(|(date, stats)| (*date, rules::group_per_severity(&rules, stats, false)))
+            .collect();
     let endpoints_audience_number: Vec<(DateTime<Utc>, std::collections::HashMap<String, usize>)> =
         match dao::repo_metrics::get_metrics_endpoints_per_audience(&SETTINGS.database) {
             Ok(val) => val.points,
@@ -100,8 +145,32 @@ pub fn get_all_metrics() -> HttpResponse {
             }
         };
 
+    let churn_timeseries: Vec<(DateTime<Utc>, Vec<churn::ChurnStats>)> =
+        match dao::repo_metrics::get_metrics_churn(&SETTINGS.database) {
+            Ok(val) => val.points,
+            Err(why) => {
+                error!("Error while getting get_metrics_churn [{}]", why);
+                Vec::new()
+            }
+        };
+
+    let spec_errors_timeseries: Vec<(DateTime<Utc>, i32)> =
+        match dao::repo_metrics::get_metrics_spec_errors(&SETTINGS.database) {
+            Ok(val) => val.points,
+            Err(why) => {
+                error!("Error while getting get_metrics_spec_errors [{}]", why);
+                Vec::new()
+            }
+        };
+
     //will combine PR informations with metrics
-    let merged_prs: Vec<PullRequest> = apis::get_pull_requests("MERGED").values;
+    let merged_prs: Vec<PullRequest> = match apis::get_pull_requests("MERGED") {
+        Ok(pull_requests) => pull_requests.values,
+        Err(why) => {
+            error!("Error while getting merged pull requests [{}]", why);
+            Vec::new()
+        }
+    };
     let merged_prs: Vec<(DateTime<Utc>, PullRequest)> = merged_prs
         .into_iter()
         .map(|val| {
@@ -144,24 +213,80 @@ pub fn get_all_metrics() -> HttpResponse {
         pr_num: pr_num_timeseries,
         pr_ages: pr_ages_timeseries,
         endpoints_num: endpoints_number,
+        operations_num: operations_number,
+        messages_num: messages_number,
         endpoints_num_per_audience: endpoints_audience_number,
         zally_violations: zally_ignore_timeseries,
+        zally_violations_per_severity: zally_ignore_per_severity,
+        lint_violations: lint_violations_timeseries,
+        churn: churn_timeseries,
+        spec_errors: spec_errors_timeseries,
     };
 
     HttpResponse::Ok().json(metrics)
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SourceRefresh {
+    pub source: String,
+    //commit the source is on after the refresh
+    pub commit: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SpecParsing {
+    pub id: String,
+    //time it took to parse the spec when it has last changed
+    pub parse_time_ms: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Refresh {
+    pub pull_requests: i32,
+    pub sources: Vec<SourceRefresh>,
+    pub specs: Vec<SpecParsing>,
+    //specs no registered api claims and apis whose specs are gone, if the reconciliation is on
+    pub reconciliation: Option<reconciliation::Reconciliation>,
+}
+
+//fetch of the source and indexing of the specs it changed
+fn refresh_source(source: &settings::CatalogSource) -> SourceRefresh {
+    match dao::catalog::refresh_git_repo(source) {
+        Ok(commit) => SourceRefresh {
+            source: source.name.clone(),
+            commit: Some(commit.to_string()),
+            error: None,
+        },
+        Err(why) => {
+            error!("Unable to refresh catalog source - reason [{}]", why);
+            SourceRefresh {
+                source: source.name.clone(),
+                commit: None,
+                error: Some(why.to_string()),
+            }
+        }
+    }
+}
+
 #[post("/v1/metrics/refresh")]
-pub fn refresh_metrics() -> HttpResponse {
+pub async fn refresh_metrics() -> Result<HttpResponse, ApiError> {
     info!("refresh metrics");
-    dao::catalog::refresh_git_repo(&SETTINGS.catalog_path);
+    //metrics are computed even if a source cannot be refreshed, from its current state
+    let sources: Vec<SourceRefresh> =
+        dao::error::blocking(|| Ok::<_, ApiError>(SOURCES.iter().map(refresh_source).collect()))
+            .await?;
     //
-    let pull_requests: PullRequests = get_pull_requests("OPEN");
+    //apis has its own copy of the dao module, hence of the error type
+    let pull_requests: PullRequests =
+        get_pull_requests("OPEN").map_err(|why| ApiError::Upstream {
+            service: String::from("stash"),
+            message: why.to_string(),
+        })?;
 
     //keep metric pr_num
     let metrics = get_metrics_pull_requests_number(&pull_requests);
-    dao::repo_metrics::save_metrics_pull_requests_number(&SETTINGS.database, metrics.0, metrics.1)
-        .unwrap();
+    dao::repo_metrics::save_metrics_pull_requests_number(&SETTINGS.database, metrics.0, metrics.1)?;
     //keep metric pr_age
     let current_epoch = std::time::SystemTime::now();
     let current_epoch = current_epoch.duration_since(std::time::UNIX_EPOCH).unwrap();
@@ -173,30 +298,141 @@ pub fn refresh_metrics() -> HttpResponse {
         isize::try_from(metrics.2).unwrap(),
         isize::try_from(metrics.3).unwrap(),
         isize::try_from(metrics.4).unwrap(),
-    )
-    .unwrap();
+    )?;
 
     //get # of endpoints
-    let all_specs: Vec<SpecItem> = dao::catalog::list_specs(SETTINGS.catalog_path.as_str());
+    let catalog = dao::error::blocking(|| dao::catalog::load_catalog(&SOURCES, None)).await?;
+    let (all_specs, all_async_specs): (Vec<SpecItem>, Vec<AsyncSpecItem>) =
+        (catalog.specs, catalog.async_specs);
+
+    //a broken spec is counted, rather than just missing from the other metrics
+    for error in &catalog.errors {
+        warn!(
+            "Unable to load spec [{}] of source [{}] - reason [{}]",
+            error.path, error.source, error.error
+        );
+    }
+    dao::repo_metrics::save_metrics_spec_errors(
+        &SETTINGS.database,
+        Utc::now(),
+        catalog.errors.len() as i32,
+    )?;
+
+    //propose (or register) the apis of the specs no registered api claims
+    let reconciliation = match SETTINGS.reconciliation {
+        settings::Reconciliation::Off => None,
+        ref mode => {
+            match dao::repo_apis::reconcile_apis(&SETTINGS.database, &all_specs, &all_async_specs) {
+                Ok(reconciliation) => {
+                    for proposal in &reconciliation.proposals {
+                        info!(
+                            "Reconciliation proposes [{:?}] of api [{}] for specs [{:?}]",
+                            proposal.action, proposal.name, proposal.spec_ids
+                        );
+                    }
+                    for orphan in &reconciliation.orphans {
+                        warn!(
+                            "Specs [{:?}] of api [{}] are no longer in the catalog",
+                            orphan.missing_spec_ids, orphan.name
+                        );
+                    }
+                    if *mode == settings::Reconciliation::Register {
+                        match dao::repo_apis::register_proposals(
+                            &SETTINGS.database,
+                            &reconciliation,
+                        ) {
+                            Ok(applied) => info!(
+                                "Applied [{}] of [{}] reconciliation proposals",
+                                applied,
+                                reconciliation.proposals.len()
+                            ),
+                            Err(why) => error!("Unable to register proposals - reason [{}]", why),
+                        }
+                    }
+                    Some(reconciliation)
+                }
+                Err(why) => {
+                    error!("Unable to reconcile apis - reason [{}]", why);
+                    None
+                }
+            }
+        }
+    };
 
     let all_specs_paths: Vec<String> = all_specs.iter().map(|val| val.path.to_string()).collect();
     info!(
         "List of retrieved and parsed OpenAPI Specifications [{:?}]",
         all_specs_paths
     );
+    let all_async_specs_paths: Vec<String> = all_async_specs
+        .iter()
+        .map(|val| val.path.to_string())
+        .collect();
+    info!(
+        "List of retrieved and parsed AsyncAPI Specifications [{:?}]",
+        all_async_specs_paths
+    );
+
+    let specs: Vec<SpecParsing> = dao::catalog::get_parse_times(&all_specs, &all_async_specs)
+        .into_iter()
+        .map(|(id, parse_time)| SpecParsing {
+            id: id,
+            parse_time_ms: parse_time.as_millis() as u64,
+        })
+        .collect();
+    info!(
+        "Parsing time of the specifications [{}] ms - slowest [{:?}]",
+        specs.iter().map(|spec| spec.parse_time_ms).sum::<u64>(),
+        specs.iter().max_by_key(|spec| spec.parse_time_ms)
+    );
 
-    let len = &all_specs.len();
-    let metrics = get_metrics_endpoints_num(&all_specs);
+    let len = all_specs.len() + all_async_specs.len();
+    let metrics = get_metrics_endpoints_num(&all_specs, &all_async_specs);
     info!(
         "Parsed [{}] specifications and got a total of [{:?}] paths",
         len, &metrics
     );
-    dao::repo_metrics::save_metrics_endpoints_num(&SETTINGS.database, metrics.0, metrics.1)
-        .unwrap();
+    dao::repo_metrics::save_metrics_endpoints_num(&SETTINGS.database, metrics.0, metrics.1)?;
+
+    //a path with GET / POST / DELETE counts as 1 endpoint but 3 operations
+    let metrics = get_metrics_operations_num(&all_specs, &all_async_specs);
+    info!(
+        "Parsed [{}] specifications and got a total of [{:?}] operations",
+        len, &metrics
+    );
+    dao::repo_metrics::save_metrics_operations_num(&SETTINGS.database, metrics.0, metrics.1)?;
+
+    let metrics = get_metrics_messages_num(&all_async_specs);
+    info!(
+        "Parsed [{}] specifications and got a total of [{:?}] messages",
+        len, &metrics
+    );
+    dao::repo_metrics::save_metrics_messages_num(&SETTINGS.database, metrics.0, metrics.1)?;
 
     //save metrics zally_ignore
     let stats = dao::catalog::get_zally_ignore(&all_specs);
-    dao::repo_metrics::save_metrics_zally_ignore(&SETTINGS.database, Utc::now(), stats).unwrap();
+    dao::repo_metrics::save_metrics_zally_ignore(&SETTINGS.database, Utc::now(), stats)?;
+
+    //flag the x-zally-ignore not (or no longer) covered by a waiver
+    match dao::repo_waivers::list_all_waivers(&SETTINGS.database) {
+        Ok(waivers) => {
+            let checks = dao::catalog::check_zally_ignores(&all_specs, &SOURCES, &waivers);
+            for check in checks
+                .iter()
+                .filter(|check| check.status != zally::WaiverStatus::WAIVED)
+            {
+                warn!(
+                    "Suppression of rule [{}] on spec [{}] and path [{:?}] is [{:?}]",
+                    check.ignore.rule, check.spec, check.ignore.path, check.status
+                );
+            }
+        }
+        Err(why) => error!("Error while getting list_all_waivers [{}]", why),
+    }
+
+    //save metrics lint_violations, i.e. the violations not suppressed by a zally-ignore
+    let stats = dao::catalog::get_lint_violations(&all_specs);
+    dao::repo_metrics::save_metrics_lint_violations(&SETTINGS.database, Utc::now(), stats)?;
 
     //save metrics endpoints_num_per audience
     let stats = dao::catalog::get_endpoints_num_per_audience(&all_specs);
@@ -204,10 +440,169 @@ pub fn refresh_metrics() -> HttpResponse {
         &SETTINGS.database,
         Utc::now(),
         stats,
-    )
-    .unwrap();
+    )?;
+
+    //save metrics churn, i.e. commits and changed lines over the rolling windows
+    let stats = dao::catalog::get_churn(
+        &all_specs,
+        &all_async_specs,
+        &SOURCES,
+        Utc::now(),
+        &churn::CHURN_WINDOWS,
+    );
+    dao::repo_metrics::save_metrics_churn(&SETTINGS.database, Utc::now(), stats)?;
     //
-    HttpResponse::Ok().json(pull_requests.size)
+    Ok(HttpResponse::Ok().json(Refresh {
+        pull_requests: pull_requests.size,
+        sources: sources,
+        specs: specs,
+        reconciliation: reconciliation,
+    }))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BackfillPeriod {
+    pub from: NaiveDate,
+    //today if not set
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Backfill {
+    pub days: usize,
+    //days no source has a commit as of, before their history or when it cannot be walked, no point is inserted for them
+    pub skipped_days: usize,
+    //points inserted per metric, the days already having a point are skipped
+    pub endpoints_num: usize,
+    pub endpoints_num_per_audience: usize,
+    pub zally_violations: usize,
+}
+
+//the catalog is listed once per day, longer periods are to be backfilled in several calls
+const BACKFILL_MAX_DAYS: i64 = 366;
+
+/**
+ * Replay the git history of the catalog to compute, for each day of the period, the metrics as they were at its end
+ */
+#[post("/v1/metrics/backfill")]
+pub async fn backfill_metrics(
+    period: web::Query<BackfillPeriod>,
+) -> Result<HttpResponse, ApiError> {
+    info!("backfill metrics [{:?}]", period);
+    let today = Utc::now().date().naive_utc();
+    let from = period.from;
+    let to = period.to.unwrap_or(today).min(today);
+    Validator::new()
+        .check(
+            from <= to,
+            "from",
+            "must be before the end of the period and today",
+        )
+        .check(
+            (to - from).num_days() < BACKFILL_MAX_DAYS,
+            "to",
+            &format!("must be less than {} days after from", BACKFILL_MAX_DAYS),
+        )
+        .validate()?;
+
+    let backfill = dao::error::blocking(move || backfill_period(from, to)).await?;
+    info!("Backfilled metrics [{:?}]", backfill);
+
+    Ok(HttpResponse::Ok().json(backfill))
+}
+
+fn backfill_period(from: NaiveDate, to: NaiveDate) -> Result<Backfill, ApiError> {
+    let (known_endpoints_num, known_per_audience, known_zally_ignore) = get_known_days()?;
+
+    let days: Vec<NaiveDate> = (0..=(to - from).num_days())
+        .map(|offset| from + Duration::days(offset))
+        .collect();
+    let dates: Vec<DateTime<Utc>> = days
+        .iter()
+        .map(|day| Utc.from_utc_datetime(&day.and_hms(23, 59, 59)))
+        .collect();
+    //the history of each source is walked once for the whole period
+    let snapshots = dao::catalog::get_catalog_snapshots(&SOURCES, &dates);
+
+    let mut backfill = Backfill {
+        days: 0,
+        skipped_days: 0,
+        endpoints_num: 0,
+        endpoints_num_per_audience: 0,
+        zally_violations: 0,
+    };
+    for ((day, date_time), snapshot) in days.iter().zip(dates).zip(snapshots) {
+        backfill.days += 1;
+        if snapshot.is_empty() {
+            debug!("No source has a commit as of [{}], day skipped", day);
+            backfill.skipped_days += 1;
+            continue;
+        }
+        let (all_specs, all_async_specs) = dao::catalog::list_catalog_snapshot(&SOURCES, &snapshot);
+
+        if !known_endpoints_num.contains(day) {
+            let metrics = get_metrics_endpoints_num(&all_specs, &all_async_specs);
+            match dao::repo_metrics::save_metrics_endpoints_num(
+                &SETTINGS.database,
+                date_time,
+                metrics.1,
+            ) {
+                Ok(_) => backfill.endpoints_num += 1,
+                Err(why) => error!("Unable to backfill endpoints_num of [{}] [{}]", day, why),
+            }
+        }
+        if !known_per_audience.contains(day) {
+            let stats = dao::catalog::get_endpoints_num_per_audience(&all_specs);
+            match dao::repo_metrics::save_metrics_endpoints_num_per_audience(
+                &SETTINGS.database,
+                date_time,
+                stats,
+            ) {
+                Ok(_) => backfill.endpoints_num_per_audience += 1,
+                Err(why) => error!(
+                    "Unable to backfill endpoints_num_per_audience of [{}] [{}]",
+                    day, why
+                ),
+            }
+        }
+        if !known_zally_ignore.contains(day) {
+            let stats = dao::catalog::get_zally_ignore(&all_specs);
+            match dao::repo_metrics::save_metrics_zally_ignore(&SETTINGS.database, date_time, stats)
+            {
+                Ok(_) => backfill.zally_violations += 1,
+                Err(why) => error!("Unable to backfill zally_ignore of [{}] [{}]", day, why),
+            }
+        }
+    }
+
+    Ok(backfill)
+}
+
+type KnownDays = std::collections::HashSet<NaiveDate>;
+
+//days already having a point, per backfilled metric
+fn get_known_days() -> dao::error::Result<(KnownDays, KnownDays, KnownDays)> {
+    let endpoints_num = dao::repo_metrics::get_metrics_endpoints_number(&SETTINGS.database)?;
+    let per_audience = dao::repo_metrics::get_metrics_endpoints_per_audience(&SETTINGS.database)?;
+    let zally_ignore = dao::repo_metrics::get_metrics_zally_ignore(&SETTINGS.database)?;
+
+    Ok((
+        endpoints_num
+            .points
+            .iter()
+            .map(|(date, _)| date.date().naive_utc())
+            .collect(),
+        per_audience
+            .points
+            .iter()
+            .map(|(date, _)| date.date().naive_utc())
+            .collect(),
+        zally_ignore
+            .points
+            .iter()
+            .map(|(date, _)| date.date().naive_utc())
+            .collect(),
+    ))
 }
 
 fn get_metrics_pull_requests_number(pull_requests: &PullRequests) -> (DateTime<Utc>, i32) {
@@ -258,8 +653,11 @@ fn get_metrics_pull_requests_ages_stats(
 }
 
 //TODO move this method into catalog/mod.rs
-fn get_metrics_endpoints_num(all_specs: &Vec<SpecItem>) -> (DateTime<Utc>, i32) {
-    let endpoints_per_spec: Vec<_> = all_specs
+fn get_metrics_endpoints_num(
+    all_specs: &Vec<SpecItem>,
+    all_async_specs: &Vec<AsyncSpecItem>,
+) -> (DateTime<Utc>, i32) {
+    let mut endpoints_per_spec: Vec<_> = all_specs
         .iter()
         .map(|spec| {
             let num = spec.api_spec.paths.len();
@@ -268,6 +666,16 @@ fn get_metrics_endpoints_num(all_specs: &Vec<SpecItem>) -> (DateTime<Utc>, i32)
             num
         })
         .collect();
+    //channels are the asyncapi counterpart of paths
+    endpoints_per_spec.extend(all_async_specs.iter().map(|spec| {
+        let num = spec.api_spec.channels_num();
+        debug!(
+            "# of channels - spec [{:?}] got [{:?}] channels",
+            spec.path, num
+        );
+
+        num
+    }));
 
     let total: i32 = endpoints_per_spec.iter().sum::<usize>() as i32;
     info!(
@@ -278,6 +686,66 @@ fn get_metrics_endpoints_num(all_specs: &Vec<SpecItem>) -> (DateTime<Utc>, i32)
     (Utc::now(), total)
 }
 
+fn get_metrics_operations_num(
+    all_specs: &Vec<SpecItem>,
+    all_async_specs: &Vec<AsyncSpecItem>,
+) -> (DateTime<Utc>, i32) {
+    let mut operations_per_spec: Vec<_> = all_specs
+        .iter()
+        .map(|spec| {
+            let num = dao::catalog::operations::get_operations(&spec.api_spec).len();
+            debug!(
+                "# of operations - spec [{:?}] got [{:?}] operations",
+                spec.path, num
+            );
+
+            num
+        })
+        .collect();
+    //publish and subscribe operations of the channels
+    operations_per_spec.extend(all_async_specs.iter().map(|spec| {
+        let num = spec.api_spec.operations_num();
+        debug!(
+            "# of operations - spec [{:?}] got [{:?}] operations",
+            spec.path, num
+        );
+
+        num
+    }));
+
+    let total: i32 = operations_per_spec.iter().sum::<usize>() as i32;
+    info!(
+        "# of operations - per spec [{:?}] - and total # of operations [{}]",
+        &operations_per_spec, &total
+    );
+
+    (Utc::now(), total)
+}
+
+//an operation has one message, or several through oneOf
+fn get_metrics_messages_num(all_async_specs: &Vec<AsyncSpecItem>) -> (DateTime<Utc>, i32) {
+    let messages_per_spec: Vec<_> = all_async_specs
+        .iter()
+        .map(|spec| {
+            let num = spec.api_spec.messages_num();
+            debug!(
+                "# of messages - spec [{:?}] got [{:?}] messages",
+                spec.path, num
+            );
+
+            num
+        })
+        .collect();
+
+    let total: i32 = messages_per_spec.iter().sum::<usize>() as i32;
+    info!(
+        "# of messages - per spec [{:?}] - and total # of messages [{}]",
+        &messages_per_spec, &total
+    );
+
+    (Utc::now(), total)
+}
+
 #[cfg(test)]
 mod tests {
     use chrono::DateTime;
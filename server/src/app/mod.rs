@@ -4,3 +4,5 @@ pub mod domains;
 pub mod envs;
 pub mod metrics;
 pub mod tiers;
+pub mod waivers;
+pub mod zally;
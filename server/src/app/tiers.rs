@@ -5,9 +5,10 @@ use serde::{Deserialize, Serialize};
 
 #[path = "../dao/mod.rs"]
 mod dao;
+use dao::error::{ApiError, Validator};
 use dao::repo_apis::*;
 
-use log::{debug, error, info};
+use log::info;
 
 #[path = "../settings/mod.rs"]
 mod settings;
@@ -35,14 +36,9 @@ pub struct Tiers {
 }
 
 #[get("/v1/tiers")]
-pub fn get_tiers() -> HttpResponse {
+pub async fn get_tiers() -> Result<HttpResponse, ApiError> {
     info!("get tiers");
-    let mut all_tiers: Vec<TierItem> = match list_all_tiers(&SETTINGS.database) {
-        Ok(all_tiers) => all_tiers,
-        Err(why) => {
-            panic!("Unable to get domains: {}", why);
-        }
-    };
+    let mut all_tiers: Vec<TierItem> = list_all_tiers(&SETTINGS.database)?;
 
     let mut tiers = Vec::new();
 
@@ -56,14 +52,17 @@ pub fn get_tiers() -> HttpResponse {
 
     let tiers_obj = Tiers { tiers: tiers };
 
-    HttpResponse::Ok().json(tiers_obj)
+    Ok(HttpResponse::Ok().json(tiers_obj))
 }
 
 #[post("/v1/tiers")]
-pub fn create_tier(tier: Json<Tier>) -> HttpResponse {
-    let uuid = add_tier(&SETTINGS.database, &tier.name).unwrap();
+pub async fn create_tier(tier: Json<Tier>) -> Result<HttpResponse, ApiError> {
+    info!("create tier [{:?}]", tier);
+    Validator::new().not_blank(&tier.name, "name").validate()?;
 
-    HttpResponse::Created()
+    let uuid = add_tier(&SETTINGS.database, &tier.name)?;
+
+    Ok(HttpResponse::Created()
         .header("Location", format!("/v1/tiers/{}", uuid))
-        .finish()
+        .finish())
 }
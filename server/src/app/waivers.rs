@@ -0,0 +1,168 @@
+use actix_web::web::Json;
+use actix_web::{get, post};
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+
+#[path = "../dao/mod.rs"]
+mod dao;
+use dao::catalog::*;
+use dao::error::{ApiError, Validator};
+use dao::repo_waivers::*;
+
+use log::{error, info};
+
+#[path = "../settings/mod.rs"]
+mod settings;
+use settings::Settings;
+
+use chrono::{DateTime, Utc};
+
+use uuid::Uuid;
+
+lazy_static! {
+    static ref SETTINGS: settings::Settings = Settings::new().unwrap();
+    static ref SOURCES: Vec<settings::CatalogSource> = SETTINGS.get_sources();
+}
+
+/*
+ * Waivers (time-boxed exceptions to zally rules) related APIs
+ */
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub enum WaiverState {
+    ACTIVE,
+    EXPIRED,
+    REVOKED,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Waiver {
+    pub id: Uuid,
+    pub spec: String,
+    pub path: Option<String>,
+    pub rule: String,
+    pub approver: String,
+    pub expiry_date_time: DateTime<Utc>,
+    pub state: WaiverState,
+}
+
+//x-zally-ignore entry having no active waiver
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Suppression {
+    pub spec_id: String,
+    pub spec: String,
+    pub path: Option<String>,
+    pub method: Option<String>,
+    pub rule: String,
+    pub rationale: Option<String>,
+    pub status: zally::WaiverStatus,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Waivers {
+    pub waivers: Vec<Waiver>,
+    pub suppressions: Vec<Suppression>,
+}
+
+#[get("/v1/waivers")]
+pub async fn get_waivers() -> Result<HttpResponse, ApiError> {
+    info!("get waivers");
+    let all_waivers: Vec<WaiverItem> = list_all_waivers(&SETTINGS.database)?;
+
+    let (all_specs, _) = dao::error::blocking(|| list_catalog_at(&SOURCES, None)).await?;
+    let checks = check_zally_ignores(&all_specs, &SOURCES, &all_waivers);
+    let suppressions = checks
+        .into_iter()
+        .filter(|check| check.status != zally::WaiverStatus::WAIVED)
+        .map(|check| Suppression {
+            spec_id: check.spec_id,
+            spec: check.spec,
+            path: check.ignore.path,
+            method: check.ignore.method,
+            rule: check.ignore.rule,
+            rationale: check.ignore.rationale,
+            status: check.status,
+        })
+        .collect();
+
+    let now = Utc::now();
+    let waivers = all_waivers
+        .into_iter()
+        .map(|waiver| Waiver {
+            state: match (waiver.revoked_date_time, waiver.expiry_date_time > now) {
+                (Some(_), _) => WaiverState::REVOKED,
+                (None, true) => WaiverState::ACTIVE,
+                (None, false) => WaiverState::EXPIRED,
+            },
+            id: waiver.id,
+            spec: waiver.spec,
+            path: waiver.path,
+            rule: waiver.rule,
+            approver: waiver.approver,
+            expiry_date_time: waiver.expiry_date_time,
+        })
+        .collect();
+
+    Ok(HttpResponse::Ok().json(Waivers {
+        waivers: waivers,
+        suppressions: suppressions,
+    }))
+}
+
+#[post("/v1/waivers")]
+pub async fn create_waiver(waiver: Json<Waiver>) -> Result<HttpResponse, ApiError> {
+    info!("create waiver [{:?}]", waiver);
+    Validator::new()
+        .not_blank(&waiver.spec, "spec")
+        .not_blank(&waiver.rule, "rule")
+        .not_blank(&waiver.approver, "approver")
+        .check(
+            waiver.expiry_date_time > Utc::now(),
+            "expiry_date_time",
+            "must be in the future",
+        )
+        .validate()?;
+    let spec_id = get_stable_spec_id(&waiver.spec).await?;
+
+    let id = add_waiver(
+        &SETTINGS.database,
+        &spec_id,
+        waiver.path.as_deref(),
+        &waiver.rule,
+        &waiver.approver,
+        waiver.expiry_date_time,
+    )?;
+
+    Ok(HttpResponse::Ok().json(id))
+}
+
+//waivers are keyed on the stable id of the spec, the id of its current revision is accepted as well
+async fn get_stable_spec_id(spec_id: &str) -> Result<String, ApiError> {
+    let (all_specs, _) = dao::error::blocking(|| list_catalog_at(&SOURCES, None)).await?;
+    let stable_id = all_specs
+        .into_iter()
+        .find(|spec| spec.stable_id == spec_id || spec.id == spec_id)
+        .map(|spec| spec.stable_id);
+    Validator::new()
+        .check(
+            stable_id.is_some(),
+            "spec",
+            &format!("unknown spec [{}]", spec_id),
+        )
+        .validate()?;
+
+    Ok(stable_id.unwrap_or_default())
+}
+
+pub async fn delete_waiver(path: web::Path<(String,)>) -> Result<HttpResponse, ApiError> {
+    info!("revoking waiver for id [{:?}]", &path.0);
+    let id = dao::error::parse_uuid(&path.0, "id")?;
+
+    match revoke_waiver(&SETTINGS.database, id)? {
+        0 => {
+            error!("No active waiver [{}] to revoke", id);
+            Err(ApiError::not_found(format!("No active waiver [{}]", id)))
+        }
+        _ => Ok(HttpResponse::Ok().json("")),
+    }
+}
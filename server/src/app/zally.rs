@@ -0,0 +1,82 @@
+use actix_web::get;
+use actix_web::HttpResponse;
+use serde::{Deserialize, Serialize};
+
+#[path = "../dao/mod.rs"]
+mod dao;
+use dao::catalog::*;
+use dao::error::ApiError;
+
+use log::info;
+
+#[path = "../settings/mod.rs"]
+mod settings;
+use settings::Settings;
+
+lazy_static! {
+    static ref SETTINGS: settings::Settings = Settings::new().unwrap();
+    static ref SOURCES: Vec<settings::CatalogSource> = SETTINGS.get_sources();
+}
+
+/*
+ * Zally rules related APIs
+ */
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Rules {
+    pub severities: Vec<rules::SeverityStats>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ZallyIgnore {
+    pub spec_id: String,
+    pub spec: String,
+    pub path: Option<String>,
+    pub method: Option<String>,
+    pub rule: String,
+    pub rationale: Option<String>,
+    pub missing_rationale: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ZallyIgnores {
+    pub ignores: Vec<ZallyIgnore>,
+}
+
+#[get("/v1/rules")]
+pub async fn get_rules() -> Result<HttpResponse, ApiError> {
+    info!("get rules");
+    let all_rules = rules::get_rules(&SOURCES);
+    let (all_specs, _) = dao::error::blocking(|| list_catalog_at(&SOURCES, None)).await?;
+    let zally_ignore = get_zally_ignore(&all_specs);
+
+    let rules = Rules {
+        severities: rules::group_per_severity(&all_rules, &zally_ignore, true),
+    };
+
+    Ok(HttpResponse::Ok().json(rules))
+}
+
+#[get("/v1/zally/ignores")]
+pub async fn get_zally_ignores() -> Result<HttpResponse, ApiError> {
+    info!("get zally ignores");
+    let mut ignores = Vec::new();
+
+    let (all_specs, _) = dao::error::blocking(|| list_catalog_at(&SOURCES, None)).await?;
+    for spec in all_specs {
+        let spec_path = get_spec_short_path(&SOURCES, &spec);
+        for ignore in get_spec_zally_ignores(&spec) {
+            ignores.push(ZallyIgnore {
+                spec_id: spec.id.clone(),
+                spec: String::from(spec_path),
+                path: ignore.path,
+                method: ignore.method,
+                rule: ignore.rule,
+                missing_rationale: ignore.rationale.is_none(),
+                rationale: ignore.rationale,
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(ZallyIgnores { ignores: ignores }))
+}
@@ -0,0 +1,71 @@
+use openapiv3::Info;
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeMap;
+
+//the openapiv3 crate does not handle AsyncAPI, so only the subset of the 2.x model the catalog relies on is kept here
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct AsyncAPI {
+    pub asyncapi: String,
+    pub info: Info,
+    #[serde(default)]
+    pub servers: BTreeMap<String, Server>,
+    #[serde(default)]
+    pub channels: BTreeMap<String, ChannelItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Server {
+    pub url: String,
+    pub protocol: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ChannelItem {
+    pub description: Option<String>,
+    pub subscribe: Option<Operation>,
+    pub publish: Option<Operation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Operation {
+    #[serde(rename = "operationId")]
+    pub operation_id: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    //can be a single message, a $ref or a oneOf list
+    pub message: Option<serde_yaml::Value>,
+}
+
+impl AsyncAPI {
+    pub fn channels_num(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn operations_num(&self) -> usize {
+        self.operations().count()
+    }
+
+    pub fn messages_num(&self) -> usize {
+        self.operations()
+            .map(|operation| match &operation.message {
+                Some(message) => match message.get("oneOf").and_then(|val| val.as_sequence()) {
+                    Some(messages) => messages.len(),
+                    None => 1,
+                },
+                None => 0,
+            })
+            .sum()
+    }
+
+    fn operations(&self) -> impl Iterator<Item = &Operation> {
+        self.channels.values().flat_map(|channel| {
+            channel
+                .subscribe
+                .iter()
+                .chain(channel.publish.iter())
+                .collect::<Vec<&Operation>>()
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+
+use super::history::FileChange;
+
+//rolling windows (in days) the churn is computed over
+pub const CHURN_WINDOWS: [i64; 3] = [7, 30, 90];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Churn {
+    pub commits: usize,
+    //lines added plus lines deleted
+    pub lines: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChurnStats {
+    pub window_days: i64,
+    pub per_spec: HashMap<String, Churn>,
+    pub per_domain: HashMap<String, Churn>,
+    pub per_audience: HashMap<String, Churn>,
+}
+
+//a spec along with the changes of its file
+pub struct SpecChanges<'a> {
+    pub name: String,
+    pub domain: &'a str,
+    pub audience: &'a str,
+    pub changes: &'a [FileChange],
+}
+
+//a commit changing several specs of a domain counts once for the domain
+#[derive(Default)]
+struct ChurnBuilder<'a> {
+    commits: HashSet<&'a str>,
+    lines: usize,
+}
+
+impl<'a> ChurnBuilder<'a> {
+    fn add(&mut self, changes: &[&'a FileChange]) {
+        for change in changes {
+            self.commits.insert(change.commit_id.as_str());
+            self.lines += change.lines;
+        }
+    }
+
+    fn build(&self) -> Churn {
+        Churn {
+            commits: self.commits.len(),
+            lines: self.lines,
+        }
+    }
+}
+
+fn build_all(builders: HashMap<String, ChurnBuilder>) -> HashMap<String, Churn> {
+    builders
+        .into_iter()
+        .map(|(key, builder)| (key, builder.build()))
+        .collect()
+}
+
+/**
+ * Commits and changed lines over the window_days up to now. All the specs are listed,
+ * the ones not changed during the window with a zero churn.
+ */
+pub fn get_churn_stats<'a>(
+    specs: &[SpecChanges<'a>],
+    now: DateTime<Utc>,
+    window_days: i64,
+) -> ChurnStats {
+    let since = now - Duration::days(window_days);
+    let mut per_spec: HashMap<String, ChurnBuilder<'a>> = HashMap::new();
+    let mut per_domain: HashMap<String, ChurnBuilder<'a>> = HashMap::new();
+    let mut per_audience: HashMap<String, ChurnBuilder<'a>> = HashMap::new();
+
+    for spec in specs {
+        let changes: Vec<&'a FileChange> = spec
+            .changes
+            .iter()
+            .filter(|change| change.date >= since && change.date <= now)
+            .collect();
+
+        per_spec.entry(spec.name.clone()).or_default().add(&changes);
+        per_domain
+            .entry(spec.domain.to_string())
+            .or_default()
+            .add(&changes);
+        per_audience
+            .entry(spec.audience.to_string())
+            .or_default()
+            .add(&changes);
+    }
+
+    ChurnStats {
+        window_days: window_days,
+        per_spec: build_all(per_spec),
+        per_domain: build_all(per_domain),
+        per_audience: build_all(per_audience),
+    }
+}
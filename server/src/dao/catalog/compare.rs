@@ -0,0 +1,465 @@
+use openapiv3::OpenAPI;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use std::collections::HashSet;
+
+use super::operations;
+use super::pointer::escape;
+use super::resolver::get_pointer;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ChangeKind {
+    OperationRemoved,
+    OperationAdded,
+    RequiredParameterAdded,
+    ParameterAdded,
+    ParameterBecameRequired,
+    ResponseRemoved,
+    RequiredFieldAdded,
+    ResponseFieldRemoved,
+    FieldRemoved,
+    FieldAdded,
+    EnumNarrowed,
+    EnumWidened,
+    TypeChanged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    //json pointer of the changed item, in the new spec (in the old one if removed)
+    pub pointer: String,
+    pub breaking: bool,
+    pub description: String,
+}
+
+//schemas of requests are written by the clients whereas responses are read by them, so a same change
+//(e.g. a removed field) has not the same impact
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Request,
+    Response,
+}
+
+struct Comparator<'a> {
+    old: &'a Value,
+    new: &'a Value,
+    changes: Vec<Change>,
+    //(old ref, new ref, is request) already compared, recursive schemas would loop otherwise
+    visited: HashSet<(String, String, bool)>,
+}
+
+pub fn compare(old: &OpenAPI, new: &OpenAPI) -> Vec<Change> {
+    let old_doc = serde_yaml::to_value(old).unwrap_or(Value::Null);
+    let new_doc = serde_yaml::to_value(new).unwrap_or(Value::Null);
+
+    let mut comparator = Comparator {
+        old: &old_doc,
+        new: &new_doc,
+        changes: Vec::new(),
+        visited: HashSet::new(),
+    };
+
+    let old_operations = operations::get_operations(old);
+    let new_operations = operations::get_operations(new);
+
+    for operation in old_operations.iter() {
+        let pointer = get_operation_pointer(&operation.path, &operation.method);
+        match new_operations
+            .iter()
+            .find(|val| val.path == operation.path && val.method == operation.method)
+        {
+            Some(_) => comparator.compare_operation(&pointer),
+            None => comparator.push(
+                ChangeKind::OperationRemoved,
+                &pointer,
+                true,
+                format!("{} {} has been removed", operation.method, operation.path),
+            ),
+        }
+    }
+    for operation in new_operations.iter() {
+        if !old_operations
+            .iter()
+            .any(|val| val.path == operation.path && val.method == operation.method)
+        {
+            comparator.push(
+                ChangeKind::OperationAdded,
+                &get_operation_pointer(&operation.path, &operation.method),
+                false,
+                format!("{} {} has been added", operation.method, operation.path),
+            );
+        }
+    }
+
+    comparator.changes
+}
+
+pub fn is_breaking(changes: &[Change]) -> bool {
+    changes.iter().any(|change| change.breaking)
+}
+
+fn get_operation_pointer(path: &str, method: &str) -> String {
+    format!("/paths/{}/{}", escape(path), method.to_lowercase())
+}
+
+impl<'a> Comparator<'a> {
+    fn push(&mut self, kind: ChangeKind, pointer: &str, breaking: bool, description: String) {
+        self.changes.push(Change {
+            kind: kind,
+            pointer: String::from(pointer),
+            breaking: breaking,
+            description: description,
+        });
+    }
+
+    fn compare_operation(&mut self, pointer: &str) {
+        let old = get_pointer(self.old, pointer)
+            .cloned()
+            .unwrap_or(Value::Null);
+        let new = get_pointer(self.new, pointer)
+            .cloned()
+            .unwrap_or(Value::Null);
+        let path_pointer = &pointer[..pointer.rfind('/').unwrap_or(0)];
+
+        //parameters
+        let old_parameters = self.get_parameters(self.old, path_pointer, pointer);
+        let new_parameters = self.get_parameters(self.new, path_pointer, pointer);
+        for (key, parameter_pointer, new_parameter) in new_parameters.iter() {
+            let required = is_true(new_parameter.get("required"));
+            match old_parameters.iter().find(|(old_key, _, _)| old_key == key) {
+                Some((_, _, old_parameter)) => {
+                    if required && !is_true(old_parameter.get("required")) {
+                        self.push(
+                            ChangeKind::ParameterBecameRequired,
+                            parameter_pointer,
+                            true,
+                            format!("{} parameter [{}] is now required", key.0, key.1),
+                        );
+                    }
+                    if let (Some(old_schema), Some(new_schema)) =
+                        (old_parameter.get("schema"), new_parameter.get("schema"))
+                    {
+                        self.compare_schema(
+                            old_schema,
+                            new_schema,
+                            &format!("{}/schema", parameter_pointer),
+                            Direction::Request,
+                        );
+                    }
+                }
+                None => match required {
+                    true => self.push(
+                        ChangeKind::RequiredParameterAdded,
+                        parameter_pointer,
+                        true,
+                        format!("required {} parameter [{}] has been added", key.0, key.1),
+                    ),
+                    false => self.push(
+                        ChangeKind::ParameterAdded,
+                        parameter_pointer,
+                        false,
+                        format!("optional {} parameter [{}] has been added", key.0, key.1),
+                    ),
+                },
+            }
+        }
+
+        //request body
+        let old_body = self.resolve(self.old, old.get("requestBody"));
+        let new_body = self.resolve(self.new, new.get("requestBody"));
+        if let (Some(old_body), Some(new_body)) = (old_body, new_body) {
+            self.compare_content(
+                &old_body,
+                &new_body,
+                &format!("{}/requestBody", pointer),
+                Direction::Request,
+            );
+        }
+
+        //responses
+        let old_responses = get_entries(old.get("responses"));
+        let new_responses = get_entries(new.get("responses"));
+        for (code, old_response) in old_responses.iter() {
+            let response_pointer = format!("{}/responses/{}", pointer, escape(code));
+            match new_responses.iter().find(|(new_code, _)| new_code == code) {
+                Some((_, new_response)) => {
+                    let old_response = self.resolve(self.old, Some(old_response));
+                    let new_response = self.resolve(self.new, Some(new_response));
+                    if let (Some(old_response), Some(new_response)) = (old_response, new_response) {
+                        self.compare_content(
+                            &old_response,
+                            &new_response,
+                            &response_pointer,
+                            Direction::Response,
+                        );
+                    }
+                }
+                None => self.push(
+                    ChangeKind::ResponseRemoved,
+                    &response_pointer,
+                    code.starts_with('2'),
+                    format!("response [{}] has been removed", code),
+                ),
+            }
+        }
+    }
+
+    //(location, name) -> (pointer, parameter), path level ones being overridden by the operation ones
+    fn get_parameters(
+        &self,
+        doc: &Value,
+        path_pointer: &str,
+        operation_pointer: &str,
+    ) -> Vec<((String, String), String, Value)> {
+        let mut parameters: Vec<((String, String), String, Value)> = Vec::new();
+
+        for pointer in [path_pointer, operation_pointer].iter() {
+            let pointer = format!("{}/parameters", pointer);
+            let list = match get_pointer(doc, &pointer).and_then(|val| val.as_sequence()) {
+                Some(list) => list,
+                None => continue,
+            };
+            for (idx, parameter) in list.iter().enumerate() {
+                if let Some(parameter) = self.resolve(doc, Some(parameter)) {
+                    let key = (
+                        String::from(parameter["in"].as_str().unwrap_or_default()),
+                        String::from(parameter["name"].as_str().unwrap_or_default()),
+                    );
+                    parameters.retain(|(val, _, _)| *val != key);
+                    parameters.push((key, format!("{}/{}", pointer, idx), parameter));
+                }
+            }
+        }
+
+        parameters
+    }
+
+    fn compare_content(&mut self, old: &Value, new: &Value, pointer: &str, direction: Direction) {
+        for (media_type, old_media) in get_entries(old.get("content")).iter() {
+            let new_media = match new
+                .get("content")
+                .and_then(|val| val.get(media_type.as_str()))
+            {
+                Some(val) => val,
+                None => continue,
+            };
+            if let (Some(old_schema), Some(new_schema)) =
+                (old_media.get("schema"), new_media.get("schema"))
+            {
+                self.compare_schema(
+                    old_schema,
+                    new_schema,
+                    &format!("{}/content/{}/schema", pointer, escape(media_type)),
+                    direction,
+                );
+            }
+        }
+    }
+
+    fn compare_schema(&mut self, old: &Value, new: &Value, pointer: &str, direction: Direction) {
+        let refs = (get_ref(old), get_ref(new));
+        if let (Some(old_ref), Some(new_ref)) = &refs {
+            let key = (
+                old_ref.to_string(),
+                new_ref.to_string(),
+                direction == Direction::Request,
+            );
+            if !self.visited.insert(key) {
+                return;
+            }
+        }
+        let old = match self.resolve(self.old, Some(old)) {
+            Some(val) => val,
+            None => return,
+        };
+        let new = match self.resolve(self.new, Some(new)) {
+            Some(val) => val,
+            None => return,
+        };
+
+        //type
+        let old_type = old.get("type").and_then(|val| val.as_str());
+        let new_type = new.get("type").and_then(|val| val.as_str());
+        if let (Some(old_type), Some(new_type)) = (old_type, new_type) {
+            //an integer is still a number, the opposite is not true
+            let widened = old_type == "integer" && new_type == "number";
+            if old_type != new_type {
+                let breaking = !(widened && direction == Direction::Request);
+                self.push(
+                    ChangeKind::TypeChanged,
+                    pointer,
+                    breaking,
+                    format!("type changed from [{}] to [{}]", old_type, new_type),
+                );
+                return;
+            }
+        }
+
+        //enum
+        let old_enum = get_enum(&old);
+        let new_enum = get_enum(&new);
+        match (old_enum, new_enum) {
+            (Some(old_enum), Some(new_enum)) => {
+                let removed: Vec<&String> = old_enum
+                    .iter()
+                    .filter(|val| !new_enum.contains(val))
+                    .collect();
+                let added: Vec<&String> = new_enum
+                    .iter()
+                    .filter(|val| !old_enum.contains(val))
+                    .collect();
+                if !removed.is_empty() {
+                    self.push(
+                        ChangeKind::EnumNarrowed,
+                        pointer,
+                        direction == Direction::Request,
+                        format!("enum values {:?} have been removed", removed),
+                    );
+                }
+                if !added.is_empty() {
+                    self.push(
+                        ChangeKind::EnumWidened,
+                        pointer,
+                        direction == Direction::Response,
+                        format!("enum values {:?} have been added", added),
+                    );
+                }
+            }
+            (None, Some(new_enum)) => self.push(
+                ChangeKind::EnumNarrowed,
+                pointer,
+                direction == Direction::Request,
+                format!("values are now restricted to {:?}", new_enum),
+            ),
+            (Some(_), None) => self.push(
+                ChangeKind::EnumWidened,
+                pointer,
+                direction == Direction::Response,
+                String::from("values are no longer restricted"),
+            ),
+            (None, None) => {}
+        }
+
+        //properties
+        let old_properties = get_entries(old.get("properties"));
+        let new_properties = get_entries(new.get("properties"));
+        let old_required = get_required(&old);
+        let new_required = get_required(&new);
+        for (name, old_property) in old_properties.iter() {
+            let property_pointer = format!("{}/properties/{}", pointer, escape(name));
+            match new_properties.iter().find(|(new_name, _)| new_name == name) {
+                Some((_, new_property)) => {
+                    self.compare_schema(old_property, new_property, &property_pointer, direction)
+                }
+                None => match direction {
+                    Direction::Response => self.push(
+                        ChangeKind::ResponseFieldRemoved,
+                        &property_pointer,
+                        true,
+                        format!("response field [{}] has been removed", name),
+                    ),
+                    Direction::Request => self.push(
+                        ChangeKind::FieldRemoved,
+                        &property_pointer,
+                        false,
+                        format!("request field [{}] has been removed", name),
+                    ),
+                },
+            }
+        }
+        for (name, _) in new_properties.iter() {
+            if old_properties.iter().any(|(old_name, _)| old_name == name) {
+                if new_required.contains(name)
+                    && !old_required.contains(name)
+                    && direction == Direction::Request
+                {
+                    self.push(
+                        ChangeKind::RequiredFieldAdded,
+                        &format!("{}/properties/{}", pointer, escape(name)),
+                        true,
+                        format!("request field [{}] is now required", name),
+                    );
+                }
+                continue;
+            }
+            let required = new_required.contains(name) && direction == Direction::Request;
+            self.push(
+                match required {
+                    true => ChangeKind::RequiredFieldAdded,
+                    false => ChangeKind::FieldAdded,
+                },
+                &format!("{}/properties/{}", pointer, escape(name)),
+                required,
+                format!("field [{}] has been added", name),
+            );
+        }
+
+        //items of arrays
+        if let (Some(old_items), Some(new_items)) = (old.get("items"), new.get("items")) {
+            self.compare_schema(
+                old_items,
+                new_items,
+                &format!("{}/items", pointer),
+                direction,
+            );
+        }
+    }
+
+    //follow internal refs (external ones have been bundled while loading the spec)
+    fn resolve(&self, doc: &Value, val: Option<&Value>) -> Option<Value> {
+        let mut val = val?;
+        let mut depth = 0;
+        while let Some(reference) = get_ref(val) {
+            val = get_pointer(doc, reference.trim_start_matches('#'))?;
+            depth += 1;
+            if depth > 32 {
+                return None;
+            }
+        }
+
+        Some(val.clone())
+    }
+}
+
+fn get_ref(val: &Value) -> Option<&str> {
+    val.get("$ref").and_then(|val| val.as_str())
+}
+
+fn is_true(val: Option<&Value>) -> bool {
+    val.and_then(|val| val.as_bool()).unwrap_or(false)
+}
+
+fn get_entries(val: Option<&Value>) -> Vec<(String, Value)> {
+    match val.and_then(|val| val.as_mapping()) {
+        Some(mapping) => mapping
+            .iter()
+            .map(|(key, val)| (value_to_string(key), val.clone()))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+fn get_enum(schema: &Value) -> Option<Vec<String>> {
+    schema
+        .get("enum")
+        .and_then(|val| val.as_sequence())
+        .map(|values| values.iter().map(value_to_string).collect())
+}
+
+fn get_required(schema: &Value) -> Vec<String> {
+    match schema.get("required").and_then(|val| val.as_sequence()) {
+        Some(values) => values.iter().map(value_to_string).collect(),
+        None => Vec::new(),
+    }
+}
+
+fn value_to_string(val: &Value) -> String {
+    match val {
+        Value::String(val) => val.clone(),
+        Value::Number(val) => val.to_string(),
+        Value::Bool(val) => val.to_string(),
+        _ => String::from(""),
+    }
+}
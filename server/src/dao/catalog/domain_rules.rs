@@ -0,0 +1,234 @@
+use log::{debug, warn};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::extensions::SpecExtensions;
+
+const DEFAULT_RULES: &str = include_str!("domain-rules.yaml");
+pub const NO_DOMAIN: &str = "NA - no domain rule matched";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DomainRule {
+    ServerUrl { regex: RuleRegex },
+    Extension { name: String },
+    CatalogPath { regex: Option<RuleRegex> },
+    TitlePrefix { separator: String },
+}
+
+/**
+ * Regex of a rule, compiled when the rules are loaded so that an invalid one fails the load
+ */
+#[derive(Debug, Clone)]
+pub struct RuleRegex(Regex);
+
+impl RuleRegex {
+    pub fn new(regex: &str) -> Result<RuleRegex, String> {
+        Regex::new(regex)
+            .map(RuleRegex)
+            .map_err(|why| format!("invalid regex [{}] - {}", regex, why))
+    }
+}
+
+impl fmt::Display for RuleRegex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.as_str())
+    }
+}
+
+impl PartialEq for RuleRegex {
+    fn eq(&self, other: &RuleRegex) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Serialize for RuleRegex {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for RuleRegex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RuleRegex, D::Error> {
+        let regex = String::deserialize(deserializer)?;
+        RuleRegex::new(&regex).map_err(serde::de::Error::custom)
+    }
+}
+
+//what a domain can be derived from
+pub struct DomainSource<'a> {
+    pub server_url: Option<&'a str>,
+    pub extensions: &'a SpecExtensions,
+    //path of the spec relative to the catalog dir
+    pub catalog_path: &'a str,
+    pub title: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DomainMatch {
+    pub domain: String,
+    //position (starting at 1) of the matching rule, None if no rule matched
+    pub rule: Option<usize>,
+    pub explanation: String,
+}
+
+/**
+ * Name of the registered domain a derived one (e.g. /v1/settlement/operational-arrangement) belongs to, i.e. that it contains.
+ * The longest name wins, so that a domain is not mistaken for another one whose name is part of its own.
+ */
+pub fn find_domain<'a>(derived: &str, names: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    names
+        .into_iter()
+        .filter(|name| !name.is_empty() && derived.contains(name))
+        .max_by_key(|name| name.len())
+}
+
+pub fn get_default_rules() -> Vec<DomainRule> {
+    serde_yaml::from_str(DEFAULT_RULES).unwrap_or_else(|why| {
+        warn!(
+            "Unable to parse the default domain rules - reason [{}]",
+            why
+        );
+        Vec::new()
+    })
+}
+
+/**
+ * Rules from the file set in the settings, or the default (bundled) ones.
+ * Rules are loaded once per file, an invalid file (e.g. with an invalid regex) giving the default ones.
+ */
+pub fn get_rules(rules_path: Option<&str>) -> Arc<Vec<DomainRule>> {
+    lazy_static! {
+        static ref RULES: Mutex<HashMap<Option<String>, Arc<Vec<DomainRule>>>> =
+            Mutex::new(HashMap::new());
+    }
+    RULES
+        .lock()
+        .unwrap()
+        .entry(rules_path.map(String::from))
+        .or_insert_with(|| Arc::new(load_rules(rules_path)))
+        .clone()
+}
+
+fn load_rules(rules_path: Option<&str>) -> Vec<DomainRule> {
+    let rules_path = match rules_path {
+        Some(rules_path) => Path::new(rules_path),
+        None => return get_default_rules(),
+    };
+    let rules = std::fs::read_to_string(rules_path)
+        .map_err(|why| why.to_string())
+        .and_then(|content| serde_yaml::from_str(&content).map_err(|why| why.to_string()));
+
+    match rules {
+        Ok(rules) => {
+            debug!("Loaded domain rules from [{:?}]", rules_path);
+            rules
+        }
+        Err(why) => {
+            warn!(
+                "Unable to load domain rules [{:?}], using default ones - reason [{}]",
+                rules_path, why
+            );
+            get_default_rules()
+        }
+    }
+}
+
+pub fn resolve(rules: &[DomainRule], source: &DomainSource) -> DomainMatch {
+    let mut misses = Vec::new();
+    for (idx, rule) in rules.iter().enumerate() {
+        match apply(rule, source) {
+            Ok((domain, explanation)) => {
+                return DomainMatch {
+                    domain: domain,
+                    rule: Some(idx + 1),
+                    explanation: format!("rule #{} matched: {}", idx + 1, explanation),
+                }
+            }
+            Err(why) => misses.push(format!("rule #{}: {}", idx + 1, why)),
+        }
+    }
+
+    DomainMatch {
+        domain: String::from(NO_DOMAIN),
+        rule: None,
+        explanation: match misses.is_empty() {
+            true => String::from("no domain rule defined"),
+            false => misses.join(", "),
+        },
+    }
+}
+
+//the domain and how it has been derived, or why the rule does not apply
+fn apply(rule: &DomainRule, source: &DomainSource) -> Result<(String, String), String> {
+    match rule {
+        DomainRule::ServerUrl { regex } => {
+            let url = source.server_url.ok_or("servers attribute not specified")?;
+            let domain = capture(regex, url)?;
+            Ok((
+                domain,
+                format!("server url [{}] with regex [{}]", url, regex),
+            ))
+        }
+        DomainRule::Extension { name } => {
+            let val = source
+                .extensions
+                .info
+                .get(name)
+                .or_else(|| source.extensions.document.get(name))
+                .ok_or(format!("no extension [{}]", name))?;
+            match val.as_str().map(str::trim).filter(|val| !val.is_empty()) {
+                Some(domain) => Ok((String::from(domain), format!("extension [{}]", name))),
+                None => Err(format!("extension [{}] is not a string", name)),
+            }
+        }
+        DomainRule::CatalogPath { regex } => {
+            let dir = Path::new(source.catalog_path.trim_start_matches('/'))
+                .parent()
+                .map(|dir| dir.to_string_lossy().to_string())
+                .filter(|dir| !dir.is_empty())
+                .ok_or("spec at the root of the catalog")?;
+            let domain = match regex {
+                Some(regex) => capture(regex, &dir)?,
+                None => format!("/{}", dir),
+            };
+            Ok((domain, format!("catalog path [{}]", source.catalog_path)))
+        }
+        DomainRule::TitlePrefix { separator } => {
+            let prefix = source
+                .title
+                .split(separator.as_str())
+                .next()
+                .map(str::trim)
+                .filter(|prefix| !prefix.is_empty() && *prefix != source.title.trim())
+                .ok_or(format!("no separator [{}] in title", separator))?;
+            Ok((
+                String::from(prefix),
+                format!("prefix of title [{}]", source.title),
+            ))
+        }
+    }
+}
+
+//the group named domain, or the first group, or the whole match
+fn capture(regex: &RuleRegex, val: &str) -> Result<String, String> {
+    let caps = regex
+        .0
+        .captures(val)
+        .ok_or(format!("[{}] does not match [{}]", val, regex))?;
+    let domain = caps
+        .name("domain")
+        .or_else(|| caps.get(1))
+        .or_else(|| caps.get(0))
+        .map(|val| val.as_str().trim())
+        .filter(|val| !val.is_empty())
+        .ok_or(format!("no domain captured from [{}]", val))?;
+
+    Ok(String::from(domain))
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use std::collections::BTreeMap;
+
+use super::pointer::{json_to_string, METHODS};
+use super::to_json;
+
+//x-... name and value, e.g. x-audience: company-internal
+pub type Extensions = BTreeMap<String, serde_json::Value>;
+
+/**
+ * The vendor extensions (x-...) of a spec, at document, info, path and operation level.
+ * They are dropped by the OpenAPI crate, so they are read from the yaml document.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+pub struct SpecExtensions {
+    pub document: Extensions,
+    pub info: Extensions,
+    //all the paths of the spec, having extensions or not
+    pub paths: Vec<PathExtensions>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PathExtensions {
+    pub path: String,
+    pub extensions: Extensions,
+    pub operations: Vec<OperationExtensions>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OperationExtensions {
+    //upper case, as for the operations
+    pub method: String,
+    pub extensions: Extensions,
+}
+
+//asyncapi channels are read as paths, they have no http methods though
+pub fn get_extensions(doc: &Value) -> SpecExtensions {
+    let paths = doc
+        .get("paths")
+        .or_else(|| doc.get("channels"))
+        .and_then(|val| val.as_mapping());
+
+    SpecExtensions {
+        document: get_object_extensions(Some(doc)),
+        info: get_object_extensions(doc.get("info")),
+        paths: paths
+            .map(|paths| {
+                paths
+                    .iter()
+                    .map(|(path, item)| get_path_extensions(path, item))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn get_path_extensions(path: &Value, item: &Value) -> PathExtensions {
+    PathExtensions {
+        path: path.as_str().map(String::from).unwrap_or_default(),
+        extensions: get_object_extensions(Some(item)),
+        operations: METHODS
+            .iter()
+            .filter_map(|method| {
+                item.get(method).map(|operation| OperationExtensions {
+                    method: method.to_uppercase(),
+                    extensions: get_object_extensions(Some(operation)),
+                })
+            })
+            .collect(),
+    }
+}
+
+fn get_object_extensions(val: Option<&Value>) -> Extensions {
+    let mut extensions = Extensions::new();
+    if let Some(mapping) = val.and_then(|val| val.as_mapping()) {
+        for (key, val) in mapping.iter() {
+            if let Some(key) = key.as_str().filter(|key| key.starts_with("x-")) {
+                extensions.insert(String::from(key), to_json(val));
+            }
+        }
+    }
+
+    extensions
+}
+
+impl SpecExtensions {
+    pub fn get_audience(&self) -> Option<String> {
+        self.info.get("x-audience").and_then(json_to_string)
+    }
+
+    pub fn get_path(&self, path: &str) -> Option<&PathExtensions> {
+        self.paths.iter().find(|val| val.path == path)
+    }
+
+    pub fn get_operation(&self, path: &str, method: &str) -> Option<&OperationExtensions> {
+        self.get_path(path).and_then(|val| {
+            val.operations
+                .iter()
+                .find(|operation| operation.method.eq_ignore_ascii_case(method))
+        })
+    }
+
+    //permission required by an operation, set on the operation or on its path
+    pub fn get_has_authority(&self, path: &str, method: &str) -> Option<String> {
+        self.get_operation(path, method)
+            .and_then(|operation| operation.extensions.get("x-has-authority"))
+            .or_else(|| {
+                self.get_path(path)
+                    .and_then(|val| val.extensions.get("x-has-authority"))
+            })
+            .and_then(json_to_string)
+    }
+
+    //rule ids ignored for the whole spec
+    pub fn get_zally_ignore(&self) -> Vec<String> {
+        get_zally_ignore(&self.document)
+    }
+}
+
+impl PathExtensions {
+    pub fn get_zally_ignore(&self) -> Vec<String> {
+        get_zally_ignore(&self.extensions)
+    }
+}
+
+impl OperationExtensions {
+    pub fn get_zally_ignore(&self) -> Vec<String> {
+        get_zally_ignore(&self.extensions)
+    }
+}
+
+//rules are numbers for zalando guidelines but can be strings (e.g. M10) for others
+fn get_zally_ignore(extensions: &Extensions) -> Vec<String> {
+    match extensions.get("x-zally-ignore") {
+        Some(serde_json::Value::Array(rules)) => rules.iter().filter_map(json_to_string).collect(),
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,642 @@
+use log::debug;
+
+use serde_yaml::{Mapping, Value};
+
+use super::SpecType;
+
+//the internal model is the openapiv3 (3.0) one, so every other format is converted into it before being parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecFormat {
+    Swagger2,
+    OpenApi30,
+    OpenApi31,
+    AsyncApi2,
+}
+
+impl SpecFormat {
+    pub fn spec_type(&self) -> SpecType {
+        match *self {
+            SpecFormat::AsyncApi2 => SpecType::AsyncApi,
+            _ => SpecType::OpenApi,
+        }
+    }
+}
+
+/**
+ * Read the swagger / openapi / asyncapi version field and return the detected format with the original version
+ */
+pub fn get_spec_format(doc: &Value) -> Option<(SpecFormat, String)> {
+    if let Some(version) = doc.get("asyncapi").and_then(get_version) {
+        return Some((SpecFormat::AsyncApi2, version));
+    }
+    if let Some(version) = doc.get("swagger").and_then(get_version) {
+        if version.starts_with('2') {
+            return Some((SpecFormat::Swagger2, version));
+        }
+    }
+    if let Some(version) = doc.get("openapi").and_then(get_version) {
+        if version.starts_with("3.1") {
+            return Some((SpecFormat::OpenApi31, version));
+        } else if version.starts_with('3') {
+            return Some((SpecFormat::OpenApi30, version));
+        }
+    }
+
+    None
+}
+
+//version can be written unquoted (e.g. swagger: 2.0) and is then read as a number
+fn get_version(val: &Value) -> Option<String> {
+    match val {
+        Value::String(version) => Some(version.to_string()),
+        Value::Number(version) => Some(format!("{:?}", version.as_f64().unwrap_or_default())),
+        _ => None,
+    }
+}
+
+fn key(name: &str) -> Value {
+    Value::String(String::from(name))
+}
+
+/**
+ * Swagger 2.0 -> OpenAPI 3.0
+ * servers are built from schemes/host/basePath, definitions (and global parameters / responses) go to components
+ */
+pub fn convert_swagger_2(doc: &Value) -> Value {
+    let empty = Mapping::new();
+    let swagger = doc.as_mapping().unwrap_or(&empty);
+
+    let consumes = get_media_types(swagger.get(&key("consumes")));
+    let produces = get_media_types(swagger.get(&key("produces")));
+
+    let mut openapi = Mapping::new();
+    openapi.insert(key("openapi"), key("3.0.0"));
+    for (name, val) in swagger.iter() {
+        match name.as_str() {
+            Some("info") | Some("tags") | Some("security") | Some("externalDocs") => {
+                openapi.insert(name.clone(), val.clone());
+            }
+            Some(name) if name.starts_with("x-") => {
+                openapi.insert(key(name), val.clone());
+            }
+            _ => {}
+        }
+    }
+
+    //servers
+    let servers = get_servers(swagger);
+    if !servers.is_empty() {
+        openapi.insert(key("servers"), Value::Sequence(servers));
+    }
+
+    //components
+    let mut body_parameters: Vec<String> = Vec::new();
+    let mut components = Mapping::new();
+    if let Some(Value::Mapping(definitions)) = swagger.get(&key("definitions")) {
+        let mut schemas = Mapping::new();
+        for (name, schema) in definitions.iter() {
+            schemas.insert(name.clone(), convert_schema(schema));
+        }
+        components.insert(key("schemas"), Value::Mapping(schemas));
+    }
+    if let Some(Value::Mapping(parameters)) = swagger.get(&key("parameters")) {
+        let mut converted_parameters = Mapping::new();
+        let mut request_bodies = Mapping::new();
+        for (name, parameter) in parameters.iter() {
+            match parameter.get("in").and_then(|val| val.as_str()) {
+                Some("body") => {
+                    body_parameters.push(String::from(name.as_str().unwrap_or_default()));
+                    request_bodies
+                        .insert(name.clone(), convert_body_parameter(parameter, &consumes));
+                }
+                _ => {
+                    converted_parameters.insert(name.clone(), convert_parameter(parameter));
+                }
+            }
+        }
+        if !converted_parameters.is_empty() {
+            components.insert(key("parameters"), Value::Mapping(converted_parameters));
+        }
+        if !request_bodies.is_empty() {
+            components.insert(key("requestBodies"), Value::Mapping(request_bodies));
+        }
+    }
+    if let Some(Value::Mapping(responses)) = swagger.get(&key("responses")) {
+        let mut converted_responses = Mapping::new();
+        for (name, response) in responses.iter() {
+            converted_responses.insert(name.clone(), convert_response(response, &produces));
+        }
+        components.insert(key("responses"), Value::Mapping(converted_responses));
+    }
+    if let Some(Value::Mapping(security_definitions)) = swagger.get(&key("securityDefinitions")) {
+        let mut security_schemes = Mapping::new();
+        for (name, definition) in security_definitions.iter() {
+            security_schemes.insert(name.clone(), convert_security_definition(definition));
+        }
+        components.insert(key("securitySchemes"), Value::Mapping(security_schemes));
+    }
+    if !components.is_empty() {
+        openapi.insert(key("components"), Value::Mapping(components));
+    }
+
+    //paths
+    let mut paths = Mapping::new();
+    if let Some(Value::Mapping(swagger_paths)) = swagger.get(&key("paths")) {
+        for (path, path_item) in swagger_paths.iter() {
+            let mut converted_path_item = Mapping::new();
+            if let Value::Mapping(path_item) = path_item {
+                for (name, val) in path_item.iter() {
+                    match name.as_str() {
+                        Some("parameters") => {
+                            let (parameters, _) =
+                                convert_parameters(val, &consumes, &body_parameters);
+                            converted_path_item.insert(name.clone(), parameters);
+                        }
+                        Some("get") | Some("put") | Some("post") | Some("delete")
+                        | Some("options") | Some("head") | Some("patch") => {
+                            converted_path_item.insert(
+                                name.clone(),
+                                convert_operation(val, &consumes, &produces, &body_parameters),
+                            );
+                        }
+                        _ => {
+                            converted_path_item.insert(name.clone(), val.clone());
+                        }
+                    }
+                }
+            }
+            paths.insert(path.clone(), Value::Mapping(converted_path_item));
+        }
+    }
+    openapi.insert(key("paths"), Value::Mapping(paths));
+
+    let mut openapi = Value::Mapping(openapi);
+    rewrite_refs(&mut openapi);
+
+    debug!("Converted swagger 2.0 spec into [{:?}]", openapi);
+
+    openapi
+}
+
+fn get_media_types(val: Option<&Value>) -> Vec<String> {
+    let media_types: Vec<String> = match val.and_then(|val| val.as_sequence()) {
+        Some(media_types) => media_types
+            .iter()
+            .filter_map(|val| val.as_str())
+            .map(String::from)
+            .collect(),
+        None => Vec::new(),
+    };
+
+    if media_types.is_empty() {
+        vec![String::from("application/json")]
+    } else {
+        media_types
+    }
+}
+
+fn get_servers(swagger: &Mapping) -> Vec<Value> {
+    let host = swagger.get(&key("host")).and_then(|val| val.as_str());
+    let base_path = swagger
+        .get(&key("basePath"))
+        .and_then(|val| val.as_str())
+        .unwrap_or_default();
+
+    let mut servers = Vec::new();
+    match host {
+        Some(host) => {
+            let schemes: Vec<String> = match swagger.get(&key("schemes")) {
+                Some(Value::Sequence(schemes)) if !schemes.is_empty() => schemes
+                    .iter()
+                    .filter_map(|val| val.as_str())
+                    .map(String::from)
+                    .collect(),
+                _ => vec![String::from("https")],
+            };
+            for scheme in schemes {
+                let mut server = Mapping::new();
+                server.insert(
+                    key("url"),
+                    Value::String(format!("{}://{}{}", scheme, host, base_path)),
+                );
+                servers.push(Value::Mapping(server));
+            }
+        }
+        None => {
+            //no host, servers url is then relative to where the spec is served from
+            if !base_path.is_empty() {
+                let mut server = Mapping::new();
+                server.insert(key("url"), key(base_path));
+                servers.push(Value::Mapping(server));
+            }
+        }
+    }
+
+    servers
+}
+
+fn convert_operation(
+    operation: &Value,
+    consumes: &[String],
+    produces: &[String],
+    body_parameters: &[String],
+) -> Value {
+    let empty = Mapping::new();
+    let operation = operation.as_mapping().unwrap_or(&empty);
+
+    let consumes = match operation.get(&key("consumes")) {
+        Some(val) => get_media_types(Some(val)),
+        None => consumes.to_vec(),
+    };
+    let produces = match operation.get(&key("produces")) {
+        Some(val) => get_media_types(Some(val)),
+        None => produces.to_vec(),
+    };
+
+    let mut converted = Mapping::new();
+    for (name, val) in operation.iter() {
+        match name.as_str() {
+            Some("consumes") | Some("produces") | Some("schemes") => {}
+            Some("parameters") => {
+                let (parameters, request_body) =
+                    convert_parameters(val, &consumes, body_parameters);
+                if !parameters.as_sequence().map(Vec::is_empty).unwrap_or(true) {
+                    converted.insert(name.clone(), parameters);
+                }
+                if let Some(request_body) = request_body {
+                    converted.insert(key("requestBody"), request_body);
+                }
+            }
+            Some("responses") => {
+                let mut responses = Mapping::new();
+                if let Value::Mapping(val) = val {
+                    for (code, response) in val.iter() {
+                        responses.insert(code.clone(), convert_response(response, &produces));
+                    }
+                }
+                converted.insert(name.clone(), Value::Mapping(responses));
+            }
+            _ => {
+                converted.insert(name.clone(), val.clone());
+            }
+        }
+    }
+
+    Value::Mapping(converted)
+}
+
+//returns the converted (non body) parameters and the request body built from body / formData parameters
+fn convert_parameters(
+    parameters: &Value,
+    consumes: &[String],
+    body_parameters: &[String],
+) -> (Value, Option<Value>) {
+    let mut converted = Vec::new();
+    let mut request_body = None;
+    let mut form_properties = Mapping::new();
+    let mut form_required = Vec::new();
+    let mut has_file = false;
+
+    for parameter in parameters.as_sequence().unwrap_or(&Vec::new()) {
+        if let Some(reference) = parameter.get("$ref").and_then(|val| val.as_str()) {
+            let name = reference.trim_start_matches("#/parameters/");
+            if body_parameters.iter().any(|val| val == name) {
+                let mut body = Mapping::new();
+                body.insert(
+                    key("$ref"),
+                    Value::String(format!("#/components/requestBodies/{}", name)),
+                );
+                request_body = Some(Value::Mapping(body));
+            } else {
+                converted.push(parameter.clone());
+            }
+            continue;
+        }
+
+        match parameter.get("in").and_then(|val| val.as_str()) {
+            Some("body") => {
+                request_body = Some(convert_body_parameter(parameter, consumes));
+            }
+            Some("formData") => {
+                let name = parameter.get("name").cloned().unwrap_or(Value::Null);
+                if parameter.get("type").and_then(|val| val.as_str()) == Some("file") {
+                    has_file = true;
+                }
+                if parameter.get("required").and_then(|val| val.as_bool()) == Some(true) {
+                    form_required.push(name.clone());
+                }
+                form_properties.insert(name, get_parameter_schema(parameter));
+            }
+            _ => converted.push(convert_parameter(parameter)),
+        }
+    }
+
+    if !form_properties.is_empty() {
+        let media_type = if has_file || consumes.iter().any(|val| val == "multipart/form-data") {
+            "multipart/form-data"
+        } else {
+            "application/x-www-form-urlencoded"
+        };
+        let mut schema = Mapping::new();
+        schema.insert(key("type"), key("object"));
+        schema.insert(key("properties"), Value::Mapping(form_properties));
+        if !form_required.is_empty() {
+            schema.insert(key("required"), Value::Sequence(form_required));
+        }
+        request_body = Some(get_request_body(
+            Value::Mapping(schema),
+            &[String::from(media_type)],
+            None,
+            false,
+        ));
+    }
+
+    (Value::Sequence(converted), request_body)
+}
+
+fn convert_body_parameter(parameter: &Value, consumes: &[String]) -> Value {
+    get_request_body(
+        convert_schema(parameter.get("schema").unwrap_or(&Value::Null)),
+        consumes,
+        parameter.get("description"),
+        parameter.get("required").and_then(|val| val.as_bool()) == Some(true),
+    )
+}
+
+fn get_request_body(
+    schema: Value,
+    media_types: &[String],
+    description: Option<&Value>,
+    required: bool,
+) -> Value {
+    let mut request_body = Mapping::new();
+    if let Some(description) = description {
+        request_body.insert(key("description"), description.clone());
+    }
+    request_body.insert(key("content"), get_content(&schema, media_types));
+    if required {
+        request_body.insert(key("required"), Value::Bool(true));
+    }
+
+    Value::Mapping(request_body)
+}
+
+fn get_content(schema: &Value, media_types: &[String]) -> Value {
+    let mut content = Mapping::new();
+    for media_type in media_types {
+        let mut media = Mapping::new();
+        media.insert(key("schema"), schema.clone());
+        content.insert(key(media_type), Value::Mapping(media));
+    }
+
+    Value::Mapping(content)
+}
+
+const SCHEMA_FIELDS: [&str; 16] = [
+    "type",
+    "format",
+    "items",
+    "default",
+    "maximum",
+    "exclusiveMaximum",
+    "minimum",
+    "exclusiveMinimum",
+    "maxLength",
+    "minLength",
+    "pattern",
+    "maxItems",
+    "minItems",
+    "uniqueItems",
+    "enum",
+    "multipleOf",
+];
+
+//query, header, path parameters - type, format etc. move into schema
+fn convert_parameter(parameter: &Value) -> Value {
+    let empty = Mapping::new();
+    let mut converted = Mapping::new();
+    for (name, val) in parameter.as_mapping().unwrap_or(&empty).iter() {
+        let name_str = name.as_str().unwrap_or_default();
+        if !SCHEMA_FIELDS.contains(&name_str) && name_str != "collectionFormat" {
+            converted.insert(name.clone(), val.clone());
+        }
+    }
+    converted.insert(key("schema"), get_parameter_schema(parameter));
+
+    Value::Mapping(converted)
+}
+
+fn get_parameter_schema(parameter: &Value) -> Value {
+    let empty = Mapping::new();
+    let mut schema = Mapping::new();
+    for (name, val) in parameter.as_mapping().unwrap_or(&empty).iter() {
+        if SCHEMA_FIELDS.contains(&name.as_str().unwrap_or_default()) {
+            schema.insert(name.clone(), val.clone());
+        }
+    }
+
+    convert_schema(&Value::Mapping(schema))
+}
+
+fn convert_response(response: &Value, produces: &[String]) -> Value {
+    let empty = Mapping::new();
+    let mut converted = Mapping::new();
+    for (name, val) in response.as_mapping().unwrap_or(&empty).iter() {
+        match name.as_str() {
+            Some("schema") => {
+                converted.insert(key("content"), get_content(&convert_schema(val), produces));
+            }
+            Some("examples") => {}
+            Some("headers") => {
+                let mut headers = Mapping::new();
+                for (header_name, header) in val.as_mapping().unwrap_or(&empty).iter() {
+                    let mut converted_header = Mapping::new();
+                    if let Some(description) = header.get("description") {
+                        converted_header.insert(key("description"), description.clone());
+                    }
+                    converted_header.insert(key("schema"), get_parameter_schema(header));
+                    headers.insert(header_name.clone(), Value::Mapping(converted_header));
+                }
+                converted.insert(name.clone(), Value::Mapping(headers));
+            }
+            _ => {
+                converted.insert(name.clone(), val.clone());
+            }
+        }
+    }
+
+    Value::Mapping(converted)
+}
+
+//only the few places where swagger 2.0 and openapi 3.0 schemas differ
+fn convert_schema(schema: &Value) -> Value {
+    match schema {
+        Value::Mapping(schema) => {
+            let mut converted = Mapping::new();
+            for (name, val) in schema.iter() {
+                match (name.as_str(), val) {
+                    (Some("type"), Value::String(typ)) if typ == "file" => {
+                        converted.insert(key("type"), key("string"));
+                        converted.insert(key("format"), key("binary"));
+                    }
+                    (Some("x-nullable"), _) => {
+                        converted.insert(key("nullable"), val.clone());
+                    }
+                    (Some("discriminator"), Value::String(property_name)) => {
+                        let mut discriminator = Mapping::new();
+                        discriminator.insert(key("propertyName"), key(property_name));
+                        converted.insert(name.clone(), Value::Mapping(discriminator));
+                    }
+                    (Some("properties"), Value::Mapping(properties)) => {
+                        let mut converted_properties = Mapping::new();
+                        for (property, property_schema) in properties.iter() {
+                            converted_properties
+                                .insert(property.clone(), convert_schema(property_schema));
+                        }
+                        converted.insert(name.clone(), Value::Mapping(converted_properties));
+                    }
+                    (Some("items"), _) | (Some("additionalProperties"), _) => {
+                        converted.insert(name.clone(), convert_schema(val));
+                    }
+                    (Some("allOf"), Value::Sequence(schemas)) => {
+                        converted.insert(
+                            name.clone(),
+                            Value::Sequence(schemas.iter().map(convert_schema).collect()),
+                        );
+                    }
+                    _ => {
+                        converted.insert(name.clone(), val.clone());
+                    }
+                }
+            }
+            Value::Mapping(converted)
+        }
+        _ => schema.clone(),
+    }
+}
+
+fn convert_security_definition(definition: &Value) -> Value {
+    let mut converted = Mapping::new();
+    match definition.get("type").and_then(|val| val.as_str()) {
+        Some("basic") => {
+            converted.insert(key("type"), key("http"));
+            converted.insert(key("scheme"), key("basic"));
+        }
+        Some("oauth2") => {
+            let flow_name = match definition.get("flow").and_then(|val| val.as_str()) {
+                Some("application") => "clientCredentials",
+                Some("accessCode") => "authorizationCode",
+                Some("password") => "password",
+                _ => "implicit",
+            };
+            let mut flow = Mapping::new();
+            for field in &["authorizationUrl", "tokenUrl", "scopes"] {
+                if let Some(val) = definition.get(*field) {
+                    flow.insert(key(field), val.clone());
+                }
+            }
+            let mut flows = Mapping::new();
+            flows.insert(key(flow_name), Value::Mapping(flow));
+            converted.insert(key("type"), key("oauth2"));
+            converted.insert(key("flows"), Value::Mapping(flows));
+        }
+        _ => return definition.clone(),
+    }
+    if let Some(description) = definition.get("description") {
+        converted.insert(key("description"), description.clone());
+    }
+
+    Value::Mapping(converted)
+}
+
+fn rewrite_refs(val: &mut Value) {
+    match val {
+        Value::Mapping(mapping) => {
+            for (name, val) in mapping.iter_mut() {
+                match (name.as_str(), val) {
+                    (Some("$ref"), Value::String(reference)) => {
+                        *reference = reference
+                            .replacen("#/definitions/", "#/components/schemas/", 1)
+                            .replacen("#/parameters/", "#/components/parameters/", 1)
+                            .replacen("#/responses/", "#/components/responses/", 1);
+                    }
+                    (_, val) => rewrite_refs(val),
+                }
+            }
+        }
+        Value::Sequence(sequence) => sequence.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+/**
+ * OpenAPI 3.1 -> OpenAPI 3.0
+ * paths became optional and schemas are JSON Schema 2020-12, which the 3.0 model rejects on type arrays,
+ * numeric exclusiveMinimum / exclusiveMaximum, examples and const
+ */
+pub fn convert_openapi_3_1(doc: &Value) -> Value {
+    let mut openapi = doc.clone();
+    if let Value::Mapping(mapping) = &mut openapi {
+        if !mapping.contains_key(&key("paths")) {
+            mapping.insert(key("paths"), Value::Mapping(Mapping::new()));
+        }
+    }
+    downgrade_schemas(&mut openapi);
+
+    openapi
+}
+
+fn downgrade_schemas(val: &mut Value) {
+    match val {
+        Value::Mapping(mapping) => {
+            //type: [string, "null"] -> type: string + nullable: true
+            if let Some(Value::Sequence(types)) = mapping.get(&key("type")).cloned() {
+                let non_null_types: Vec<&Value> = types
+                    .iter()
+                    .filter(|val| val.as_str() != Some("null"))
+                    .collect();
+                if non_null_types.len() < types.len() {
+                    mapping.insert(key("nullable"), Value::Bool(true));
+                }
+                match non_null_types.as_slice() {
+                    [typ] => {
+                        mapping.insert(key("type"), (*typ).clone());
+                    }
+                    _ => {
+                        mapping.remove(&key("type"));
+                    }
+                }
+            }
+            //exclusiveMinimum: 5 -> minimum: 5 + exclusiveMinimum: true
+            for (exclusive, bound) in &[
+                ("exclusiveMinimum", "minimum"),
+                ("exclusiveMaximum", "maximum"),
+            ] {
+                if let Some(Value::Number(limit)) = mapping.get(&key(exclusive)).cloned() {
+                    mapping.insert(key(bound), Value::Number(limit));
+                    mapping.insert(key(exclusive), Value::Bool(true));
+                }
+            }
+            //examples: [a, b] -> example: a
+            if let Some(Value::Sequence(examples)) = mapping.get(&key("examples")).cloned() {
+                mapping.remove(&key("examples"));
+                if let Some(example) = examples.into_iter().next() {
+                    mapping.insert(key("example"), example);
+                }
+            }
+            //const: a -> enum: [a]
+            if let Some(constant) = mapping.get(&key("const")).cloned() {
+                if !constant.is_mapping() {
+                    mapping.remove(&key("const"));
+                    mapping.insert(key("enum"), Value::Sequence(vec![constant]));
+                }
+            }
+
+            for (_, val) in mapping.iter_mut() {
+                downgrade_schemas(val);
+            }
+        }
+        Value::Sequence(sequence) => sequence.iter_mut().for_each(downgrade_schemas),
+        _ => {}
+    }
+}
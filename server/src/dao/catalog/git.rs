@@ -0,0 +1,462 @@
+use log::{debug, info};
+
+use std::fmt;
+use std::path::Path;
+
+use git2::build::{CheckoutBuilder, RepoBuilder};
+use git2::{Cred, CredentialType, FetchOptions, Oid, RemoteCallbacks, Repository, StatusOptions};
+
+use super::super::super::settings::{CatalogSource, GitCredentials};
+
+#[derive(Debug)]
+pub enum GitError {
+    //the local clone is missing (or has no origin) and no url is set
+    NoRemote {
+        source: String,
+    },
+    Clone {
+        source: String,
+        url: String,
+        cause: git2::Error,
+    },
+    Open {
+        source: String,
+        cause: git2::Error,
+    },
+    //HEAD is detached and no branch is set
+    NoBranch {
+        source: String,
+    },
+    Fetch {
+        source: String,
+        branch: String,
+        cause: git2::Error,
+    },
+    //the local branch has commits that are not on the remote one
+    NotFastForward {
+        source: String,
+        branch: String,
+    },
+    //files of a checkout the server has not cloned have local changes
+    DirtyTree {
+        source: String,
+        paths: Vec<String>,
+    },
+    Update {
+        source: String,
+        branch: String,
+        cause: git2::Error,
+    },
+}
+
+impl fmt::Display for GitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GitError::NoRemote { source } => write!(
+                f,
+                "No url to clone or fetch the repo of source [{}]",
+                source
+            ),
+            GitError::Clone { source, url, cause } => write!(
+                f,
+                "Unable to clone [{}] for source [{}] - [{}]",
+                url, source, cause
+            ),
+            GitError::Open { source, cause } => write!(
+                f,
+                "Unable to open the repo of source [{}] - [{}]",
+                source, cause
+            ),
+            GitError::NoBranch { source } => write!(
+                f,
+                "HEAD of source [{}] is not on a branch and no branch is set",
+                source
+            ),
+            GitError::Fetch {
+                source,
+                branch,
+                cause,
+            } => write!(
+                f,
+                "Unable to fetch branch [{}] of source [{}] - [{}]",
+                branch, source, cause
+            ),
+            GitError::NotFastForward { source, branch } => write!(
+                f,
+                "Branch [{}] of source [{}] cannot be fast-forwarded",
+                branch, source
+            ),
+            GitError::DirtyTree { source, paths } => write!(
+                f,
+                "Working tree of source [{}] has local changes [{}]",
+                source,
+                paths.join(", ")
+            ),
+            GitError::Update {
+                source,
+                branch,
+                cause,
+            } => write!(
+                f,
+                "Unable to update branch [{}] of source [{}] - [{}]",
+                branch, source, cause
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GitError {}
+
+//set in the config of the repos the server has cloned, whose working tree is a read-only copy of the remote
+const MANAGED_KEY: &str = "apiscatalog.managed";
+
+/**
+ * Clone the repo of the source if its path is missing (or empty), otherwise fetch and fast-forward its branch.
+ * A clone made by the server is checked out whatever its local changes, any other checkout (e.g. the one of
+ * a developer) is only updated if its working tree is clean.
+ * Returns the commit the branch is on.
+ */
+pub fn refresh(source: &CatalogSource) -> Result<Oid, GitError> {
+    let path = Path::new(&source.path);
+    let is_empty = std::fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true);
+    if is_empty {
+        return clone(source);
+    }
+
+    let repo = Repository::open(path).map_err(|cause| GitError::Open {
+        source: source.name.clone(),
+        cause: cause,
+    })?;
+    fetch(source, &repo)
+}
+
+fn clone(source: &CatalogSource) -> Result<Oid, GitError> {
+    let url = source.url.as_ref().ok_or_else(|| GitError::NoRemote {
+        source: source.name.clone(),
+    })?;
+    let to_error = |cause| GitError::Clone {
+        source: source.name.clone(),
+        url: url.clone(),
+        cause: cause,
+    };
+
+    let mut builder = RepoBuilder::new();
+    builder.fetch_options(get_fetch_options(source.credentials.as_ref()));
+    if let Some(branch) = &source.branch {
+        builder.branch(branch);
+    }
+    let repo = builder
+        .clone(url, Path::new(&source.path))
+        .map_err(to_error)?;
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(to_error)?;
+    repo.config()
+        .and_then(|mut config| config.set_bool(MANAGED_KEY, true))
+        .map_err(to_error)?;
+    info!(
+        "Cloned [{}] for source [{}] at [{}]",
+        url,
+        source.name,
+        head.id()
+    );
+
+    Ok(head.id())
+}
+
+fn fetch(source: &CatalogSource, repo: &Repository) -> Result<Oid, GitError> {
+    let branch = match &source.branch {
+        Some(branch) => branch.clone(),
+        None => repo
+            .head()
+            .ok()
+            .filter(|head| head.is_branch())
+            .and_then(|head| head.shorthand().map(String::from))
+            .ok_or_else(|| GitError::NoBranch {
+                source: source.name.clone(),
+            })?,
+    };
+    let fetch_error = |cause| GitError::Fetch {
+        source: source.name.clone(),
+        branch: branch.clone(),
+        cause: cause,
+    };
+    let update_error = |cause| GitError::Update {
+        source: source.name.clone(),
+        branch: branch.clone(),
+        cause: cause,
+    };
+
+    let mut remote = match (repo.find_remote("origin"), &source.url) {
+        (_, Some(url)) => repo.remote_anonymous(url),
+        (Ok(remote), None) => Ok(remote),
+        (Err(_), None) => {
+            return Err(GitError::NoRemote {
+                source: source.name.clone(),
+            })
+        }
+    }
+    .map_err(fetch_error)?;
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+    let refspec = format!("+refs/heads/{}:{}", branch, remote_ref);
+    remote
+        .fetch(
+            &[refspec.as_str()],
+            Some(&mut get_fetch_options(source.credentials.as_ref())),
+            None,
+        )
+        .map_err(fetch_error)?;
+    let fetched = repo.refname_to_id(&remote_ref).map_err(fetch_error)?;
+
+    let local_ref = format!("refs/heads/{}", branch);
+    //working tree as checked out before the update of the branch
+    let head = repo.head().ok().and_then(|head| head.target());
+    let on_branch = repo
+        .head()
+        .ok()
+        .and_then(|head| head.name().map(String::from))
+        .is_some_and(|name| name == local_ref);
+    //the local commit the branch is fast-forwarded from, none if the branch is new
+    let (target, update) = match repo.refname_to_id(&local_ref) {
+        Ok(local) if local == fetched => (local, None),
+        //nothing new on the remote branch
+        Ok(local)
+            if repo
+                .graph_descendant_of(local, fetched)
+                .map_err(update_error)? =>
+        {
+            (local, None)
+        }
+        Ok(local)
+            if repo
+                .graph_descendant_of(fetched, local)
+                .map_err(update_error)? =>
+        {
+            (fetched, Some(Some(local)))
+        }
+        Ok(_) => {
+            return Err(GitError::NotFastForward {
+                source: source.name.clone(),
+                branch: branch,
+            })
+        }
+        //the branch is not known locally yet
+        Err(_) => (fetched, Some(None)),
+    };
+
+    //the working tree is updated before the branch, so that it is compared with the commit it has been checked out at
+    if !on_branch || head != Some(target) {
+        let managed = repo
+            .config()
+            .and_then(|config| config.get_bool(MANAGED_KEY))
+            .unwrap_or(false);
+        let mut checkout = CheckoutBuilder::new();
+        match managed {
+            true => checkout.force(),
+            false => {
+                let paths = get_changed_paths(repo).map_err(update_error)?;
+                if !paths.is_empty() {
+                    return Err(GitError::DirtyTree {
+                        source: source.name.clone(),
+                        paths: paths,
+                    });
+                }
+                checkout.safe()
+            }
+        };
+        let commit = repo.find_commit(target).map_err(update_error)?;
+        repo.checkout_tree(commit.as_object(), Some(&mut checkout))
+            .map_err(update_error)?;
+    }
+    match update {
+        Some(Some(local)) => {
+            repo.reference(&local_ref, fetched, true, "fast-forward")
+                .map_err(update_error)?;
+            info!(
+                "Fast-forwarded branch [{}] of source [{}] from [{}] to [{}]",
+                branch, source.name, local, fetched
+            );
+        }
+        Some(None) => {
+            repo.reference(&local_ref, fetched, false, "branch from remote")
+                .map_err(update_error)?;
+        }
+        None => {}
+    }
+    if !on_branch {
+        repo.set_head(&local_ref).map_err(update_error)?;
+    }
+    debug!(
+        "Branch [{}] of source [{}] is on [{}]",
+        branch, source.name, target
+    );
+
+    Ok(target)
+}
+
+//files of the working tree (or of the index) that differ from HEAD, untracked ones are left as is by a checkout
+fn get_changed_paths(repo: &Repository) -> Result<Vec<String>, git2::Error> {
+    let statuses = repo.statuses(Some(
+        StatusOptions::new()
+            .include_untracked(false)
+            .include_ignored(false),
+    ))?;
+
+    Ok(statuses
+        .iter()
+        .filter_map(|status| status.path().map(String::from))
+        .collect())
+}
+
+fn get_fetch_options(credentials: Option<&GitCredentials>) -> FetchOptions<'_> {
+    let mut callbacks = RemoteCallbacks::new();
+    if let Some(credentials) = credentials {
+        //libgit2 asks again as long as the credentials are rejected
+        let mut attempts = 0;
+        callbacks.credentials(move |_url, username_from_url, allowed_types| {
+            attempts += 1;
+            if attempts > 1 {
+                return Err(git2::Error::from_str("credentials have been rejected"));
+            }
+            get_credentials(credentials, username_from_url, allowed_types)
+        });
+    }
+
+    let mut options = FetchOptions::new();
+    options.remote_callbacks(callbacks);
+    options
+}
+
+fn get_credentials(
+    credentials: &GitCredentials,
+    username_from_url: Option<&str>,
+    allowed_types: CredentialType,
+) -> Result<Cred, git2::Error> {
+    match credentials {
+        GitCredentials::Token { username, token }
+            if allowed_types.contains(CredentialType::USER_PASS_PLAINTEXT) =>
+        {
+            let username = username.as_deref().or(username_from_url).unwrap_or("git");
+            Cred::userpass_plaintext(username, token)
+        }
+        GitCredentials::Ssh {
+            username,
+            private_key_path,
+            passphrase,
+        } if allowed_types.contains(CredentialType::SSH_KEY) => {
+            let username = username.as_deref().or(username_from_url).unwrap_or("git");
+            Cred::ssh_key(
+                username,
+                None,
+                Path::new(private_key_path),
+                passphrase.as_deref(),
+            )
+        }
+        _ => Err(git2::Error::from_str(&format!(
+            "credentials do not match the expected ones [{:?}]",
+            allowed_types
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit_file(repo: &Repository, path: &str, content: &str) -> Oid {
+        let parent = repo
+            .refname_to_id("refs/heads/master")
+            .ok()
+            .map(|oid| repo.find_commit(oid).unwrap());
+        let blob = repo.blob(content.as_bytes()).unwrap();
+        let mut builder = repo
+            .treebuilder(
+                parent
+                    .as_ref()
+                    .map(|parent| parent.tree().unwrap())
+                    .as_ref(),
+            )
+            .unwrap();
+        builder.insert(path, blob, 0o100644).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let signature = git2::Signature::now("test", "test@test.com").unwrap();
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("refs/heads/master"),
+            &signature,
+            &signature,
+            "update",
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    fn get_source(path: &Path, url: &Path) -> CatalogSource {
+        CatalogSource {
+            name: String::from("pets"),
+            path: path.to_string_lossy().to_string(),
+            dir: path.to_string_lossy().to_string(),
+            url: Some(url.to_string_lossy().to_string()),
+            credentials: None,
+            patterns: Vec::new(),
+            branch: Some(String::from("master")),
+            domain_prefix: None,
+            domain_rules_path: None,
+        }
+    }
+
+    #[test]
+    fn test_refresh_checkout() {
+        //the module is compiled several times, hence tests run concurrently
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-git-{}-{}",
+            module_path!().replace("::", "-"),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let remote_path = dir.join("remote.git");
+        let remote = Repository::init_bare(&remote_path).unwrap();
+        commit_file(&remote, "pets.yaml", "v1");
+
+        //a checkout the server has not cloned, e.g. the one of a developer
+        let checkout = RepoBuilder::new()
+            .branch("master")
+            .clone(&remote_path.to_string_lossy(), &dir.join("checkout"))
+            .unwrap();
+        let source = get_source(&dir.join("checkout"), &remote_path);
+        let file = dir.join("checkout").join("pets.yaml");
+        let second = commit_file(&remote, "pets.yaml", "v2");
+        assert_eq!(refresh(&source).unwrap(), second);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+
+        //local changes are kept
+        std::fs::write(&file, "local").unwrap();
+        commit_file(&remote, "pets.yaml", "v3");
+        match refresh(&source) {
+            Err(GitError::DirtyTree { paths, .. }) => assert_eq!(paths, vec!["pets.yaml"]),
+            other => panic!("unexpected refresh result {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "local");
+
+        //local and remote branches have diverged
+        commit_file(&checkout, "pets.yaml", "local");
+        match refresh(&source) {
+            Err(GitError::NotFastForward { branch, .. }) => assert_eq!(branch, "master"),
+            other => panic!("unexpected refresh result {:?}", other),
+        }
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "local");
+
+        //a clone of the server is a read-only copy of the remote
+        let source = get_source(&dir.join("clone"), &remote_path);
+        let file = dir.join("clone").join("pets.yaml");
+        refresh(&source).unwrap();
+        std::fs::write(&file, "local").unwrap();
+        let fourth = commit_file(&remote, "pets.yaml", "v4");
+        assert_eq!(refresh(&source).unwrap(), fourth);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v4");
+    }
+}
@@ -0,0 +1,355 @@
+use chrono::{DateTime, TimeZone, Utc};
+use git2::{Commit, Delta, Diff, DiffFindOptions, Oid, Patch, Repository, Sort};
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+
+use super::super::super::settings::CatalogSource;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SpecRevision {
+    pub commit_id: String,
+    pub author: String,
+    pub date: DateTime<Utc>,
+    //id of the spec at this revision, i.e. of its blob
+    pub spec_id: String,
+    //path of the spec at this revision, the file may have been renamed since
+    pub path: String,
+    //info.version of the spec at this revision
+    pub version: Option<String>,
+}
+
+//current path of a file -> path it has been added at
+pub type OriginPaths = HashMap<String, String>;
+
+//commit a history was on at a date, along with the origin paths of its files
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub commit: Oid,
+    pub origin_paths: Arc<OriginPaths>,
+}
+
+//a commit having changed a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileChange {
+    pub commit_id: String,
+    pub date: DateTime<Utc>,
+    //lines added plus lines deleted, 0 for a binary file or a pure rename
+    pub lines: usize,
+}
+
+//a file changed by a commit, compared to one of its parents (the first one unless stated otherwise)
+struct Change {
+    status: Delta,
+    old_path: Option<String>,
+    new_path: Option<String>,
+    new_id: Oid,
+}
+
+fn get_diff<'a>(repo: &'a Repository, commit: &Commit) -> Result<Diff<'a>, git2::Error> {
+    get_diff_from(repo, commit.parent(0).ok().as_ref(), commit)
+}
+
+//none for a root commit
+fn get_diff_from<'a>(
+    repo: &'a Repository,
+    parent: Option<&Commit>,
+    commit: &Commit,
+) -> Result<Diff<'a>, git2::Error> {
+    let tree = commit.tree()?;
+    let parent_tree = match parent {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+    Ok(diff)
+}
+
+fn get_changes(repo: &Repository, commit: &Commit) -> Result<Vec<Change>, git2::Error> {
+    get_changes_from(repo, commit.parent(0).ok().as_ref(), commit)
+}
+
+fn get_changes_from(
+    repo: &Repository,
+    parent: Option<&Commit>,
+    commit: &Commit,
+) -> Result<Vec<Change>, git2::Error> {
+    let diff = get_diff_from(repo, parent, commit)?;
+    let to_string =
+        |path: Option<&std::path::Path>| path.map(|path| path.to_string_lossy().to_string());
+    Ok(diff
+        .deltas()
+        .map(|delta| Change {
+            status: delta.status(),
+            old_path: to_string(delta.old_file().path()),
+            new_path: to_string(delta.new_file().path()),
+            new_id: delta.new_file().id(),
+        })
+        .collect())
+}
+
+//commits from the given one following the first parents, the most recent first
+fn get_first_parent_commits(repo: &Repository, from: Oid) -> Result<Vec<Oid>, git2::Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL);
+    revwalk.simplify_first_parent();
+    revwalk.push(from)?;
+
+    revwalk.collect()
+}
+
+/**
+ * Path each file of the commit had when it was added, following the renames along the history
+ */
+pub fn get_origin_paths(repo: &Repository, commit: Oid) -> Result<OriginPaths, git2::Error> {
+    let mut origins = OriginPaths::new();
+    for oid in get_first_parent_commits(repo, commit)?.iter().rev() {
+        follow_changes(repo, *oid, &mut origins)?;
+    }
+
+    Ok(origins)
+}
+
+//update the origin paths with the files added, renamed or deleted by the commit
+fn follow_changes(
+    repo: &Repository,
+    oid: Oid,
+    origins: &mut OriginPaths,
+) -> Result<(), git2::Error> {
+    for change in get_changes(repo, &repo.find_commit(oid)?)? {
+        match (change.status, change.old_path, change.new_path) {
+            (Delta::Added, _, Some(new_path)) | (Delta::Copied, _, Some(new_path)) => {
+                origins.insert(new_path.clone(), new_path);
+            }
+            (Delta::Renamed, Some(old_path), Some(new_path)) => {
+                let origin = origins.remove(&old_path).unwrap_or(old_path);
+                origins.insert(new_path, origin);
+            }
+            (Delta::Deleted, Some(old_path), _) => {
+                origins.remove(&old_path);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn get_blob_id(commit: &Commit, path: &str) -> Option<Oid> {
+    let entry = commit
+        .tree()
+        .ok()?
+        .get_path(std::path::Path::new(path))
+        .ok()?;
+    Some(entry.id())
+}
+
+/**
+ * Commits having touched the file at path, the most recent first, up to the one having added it (renames are followed).
+ * As git log does, a merge bringing the file of one of its parents is skipped and that parent alone is walked,
+ * a revision hence has the author of the commit that made the change rather than the one of the merge.
+ */
+pub fn get_history(
+    repo: &Repository,
+    source: &CatalogSource,
+    commit: Oid,
+    path: &str,
+) -> Result<Vec<SpecRevision>, git2::Error> {
+    //commits to walk, the most recent first, along with the path of the file in each of them
+    let mut pending: BinaryHeap<(i64, Oid, String)> = BinaryHeap::new();
+    let mut walked: HashSet<Oid> = HashSet::new();
+    let mut revisions = Vec::new();
+    pending.push((
+        repo.find_commit(commit)?.time().seconds(),
+        commit,
+        String::from(path),
+    ));
+    while let Some((_, oid, path)) = pending.pop() {
+        if !walked.insert(oid) {
+            continue;
+        }
+        let commit = repo.find_commit(oid)?;
+        let blob_id = match get_blob_id(&commit, &path) {
+            Some(blob_id) => blob_id,
+            None => continue,
+        };
+        let parents: Vec<Commit> = commit.parents().collect();
+        //same file as in a parent, the change has been made on the history of this parent
+        if let Some(parent) = parents
+            .iter()
+            .find(|parent| get_blob_id(parent, &path) == Some(blob_id))
+        {
+            pending.push((parent.time().seconds(), parent.id(), path));
+            continue;
+        }
+
+        //changed compared to every parent, none for a root commit
+        for parent in &parents {
+            let change = get_changes_from(repo, Some(parent), &commit)?
+                .into_iter()
+                .find(|change| {
+                    change.status != Delta::Deleted
+                        && change.new_path.as_deref() == Some(path.as_str())
+                });
+            //the history of the parent is not followed if the file has been added on top of it
+            let previous_path = match change.map(|change| (change.status, change.old_path)) {
+                Some((Delta::Renamed, Some(old_path))) => old_path,
+                Some((Delta::Added, _)) | Some((Delta::Copied, _)) | None => continue,
+                Some(_) => path.clone(),
+            };
+            pending.push((parent.time().seconds(), parent.id(), previous_path));
+        }
+
+        revisions.push(SpecRevision {
+            commit_id: oid.to_string(),
+            author: commit.author().name().unwrap_or_default().to_string(),
+            date: Utc.timestamp(commit.time().seconds(), 0),
+            spec_id: super::get_spec_id(source, blob_id),
+            path: path.clone(),
+            version: get_version(repo, blob_id),
+        });
+    }
+
+    Ok(revisions)
+}
+
+/**
+ * Changes made since the given date to the files of the commit, keyed by the path they have in the commit.
+ * Renames are followed, the changes of the files that are no longer in the commit are left out.
+ */
+pub fn get_file_changes_since(
+    repo: &Repository,
+    commit: Oid,
+    since: DateTime<Utc>,
+) -> Result<HashMap<String, Vec<FileChange>>, git2::Error> {
+    //path of the files at the commit being walked -> their path in the given commit
+    let mut paths: HashMap<String, String> =
+        super::get_tree_files(&repo.find_commit(commit)?.tree()?)
+            .into_iter()
+            .map(|(path, _)| (path.clone(), path))
+            .collect();
+    let mut changes: HashMap<String, Vec<FileChange>> = HashMap::new();
+    for oid in get_first_parent_commits(repo, commit)? {
+        let commit = repo.find_commit(oid)?;
+        let date = Utc.timestamp(commit.time().seconds(), 0);
+        //first parents are walked from the most recent, the older ones are out of the window
+        if date < since {
+            break;
+        }
+
+        let diff = get_diff(repo, &commit)?;
+        let mut renames = Vec::new();
+        for (idx, delta) in diff.deltas().enumerate() {
+            if delta.status() == Delta::Deleted {
+                continue;
+            }
+            let new_path = match delta.new_file().path() {
+                Some(new_path) => new_path.to_string_lossy().to_string(),
+                None => continue,
+            };
+            let path = match paths.get(&new_path) {
+                Some(path) => path.clone(),
+                None => continue,
+            };
+            let lines = match Patch::from_diff(&diff, idx)? {
+                Some(patch) => {
+                    let (_, additions, deletions) = patch.line_stats()?;
+                    additions + deletions
+                }
+                None => 0,
+            };
+            changes.entry(path.clone()).or_default().push(FileChange {
+                commit_id: oid.to_string(),
+                date: date,
+                lines: lines,
+            });
+
+            match delta.status() {
+                Delta::Renamed => {
+                    let old_path = delta
+                        .old_file()
+                        .path()
+                        .map(|old_path| old_path.to_string_lossy().to_string());
+                    renames.push((new_path, old_path, path));
+                }
+                Delta::Added | Delta::Copied => renames.push((new_path, None, path)),
+                _ => {}
+            }
+        }
+        //the renames are applied once all the changes of the commit are known
+        for (new_path, old_path, path) in renames {
+            paths.remove(&new_path);
+            if let Some(old_path) = old_path {
+                paths.insert(old_path, path);
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+/**
+ * Commit the first-parent history of the given one was on at each of the dates, none for the dates it started after.
+ * The history is walked once whatever the number of dates, the renames being followed along the way.
+ */
+pub fn get_commits_as_of(
+    repo: &Repository,
+    commit: Oid,
+    dates: &[DateTime<Utc>],
+) -> Result<Vec<Option<Snapshot>>, git2::Error> {
+    let commits = get_first_parent_commits(repo, commit)?;
+
+    //a date is on the most recent commit made before it, the dates still pending are the older ones
+    let mut pending: BTreeSet<(DateTime<Utc>, usize)> = dates
+        .iter()
+        .enumerate()
+        .map(|(idx, date)| (*date, idx))
+        .collect();
+    let mut as_of: Vec<Option<Oid>> = vec![None; dates.len()];
+    for oid in commits.iter() {
+        if pending.is_empty() {
+            break;
+        }
+        let date = Utc.timestamp(repo.find_commit(*oid)?.time().seconds(), 0);
+        for (_, idx) in pending.split_off(&(date, 0)) {
+            as_of[idx] = Some(*oid);
+        }
+    }
+
+    //origin paths are followed from the first commit up to the most recent one a date is on
+    let mut wanted: HashSet<Oid> = as_of.iter().flatten().cloned().collect();
+    let mut origins = OriginPaths::new();
+    let mut snapshots: HashMap<Oid, Arc<OriginPaths>> = HashMap::new();
+    for oid in commits.iter().rev() {
+        if wanted.is_empty() {
+            break;
+        }
+        follow_changes(repo, *oid, &mut origins)?;
+        if wanted.remove(oid) {
+            snapshots.insert(*oid, Arc::new(origins.clone()));
+        }
+    }
+
+    Ok(as_of
+        .into_iter()
+        .map(|oid| {
+            oid.map(|oid| Snapshot {
+                commit: oid,
+                origin_paths: snapshots[&oid].clone(),
+            })
+        })
+        .collect())
+}
+
+fn get_version(repo: &Repository, oid: Oid) -> Option<String> {
+    let blob = repo.find_blob(oid).ok()?;
+    let doc = super::load_spec(blob.content()).ok()?;
+    match doc.get("info")?.get("version")? {
+        serde_yaml::Value::String(version) => Some(version.clone()),
+        serde_yaml::Value::Number(version) => Some(version.to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,472 @@
+use log::{debug, warn};
+
+use git2::{Delta, Oid, Repository};
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::extensions::{self, SpecExtensions};
+use super::formats::{self, SpecFormat};
+use super::lint::{self, Violation};
+use super::resolver::{self, Resolver};
+use super::zally::{self, ZallyIgnoreItem};
+use super::{ParsedSpec, SpecType};
+
+/**
+ * Parsed content of a spec blob, i.e. what does not depend on the path the blob is at.
+ * Parsing is the costly part of listing the catalog, hence blobs are only parsed once they have changed.
+ */
+pub struct IndexedSpec {
+    pub spec: ParsedSpec,
+    //extensions of the bundled spec
+    pub extensions: SpecExtensions,
+    //read from the yaml text, as x-zally-ignore comments are dropped by the yaml parser
+    pub zally_ignores: Vec<ZallyIgnoreItem>,
+    //lint of the spec as written, i.e. without its external refs
+    pub lint_violations: Vec<Violation>,
+    //time it took to parse and analyse the blob
+    pub parse_time: Duration,
+}
+
+/**
+ * Why a blob matching the patterns of a source could not be loaded as a spec
+ */
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LoadError {
+    //not even a yaml (or json) document
+    Yaml {
+        message: String,
+        line: Option<usize>,
+        column: Option<usize>,
+    },
+    //a yaml document that does not match the model of its spec version
+    Schema {
+        message: String,
+    },
+    MissingSection {
+        section: String,
+    },
+    //blob not found in the repo
+    Git {
+        message: String,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Yaml {
+                message,
+                line: Some(line),
+                column: Some(column),
+            } => write!(
+                f,
+                "Invalid yaml at line [{}] column [{}] - [{}]",
+                line, column, message
+            ),
+            LoadError::Yaml { message, .. } => write!(f, "Invalid yaml - [{}]", message),
+            LoadError::Schema { message } => write!(f, "Invalid spec - [{}]", message),
+            LoadError::MissingSection { section } => {
+                write!(f, "Missing required section [{}]", section)
+            }
+            LoadError::Git { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+//sections without which a spec is not worth listing
+fn get_required_sections(format: &SpecFormat) -> [&'static str; 2] {
+    match format.spec_type() {
+        SpecType::OpenApi => ["info", "paths"],
+        SpecType::AsyncApi => ["info", "channels"],
+    }
+}
+
+//is_none_or would raise the minimum supported rust version of the crate
+#[allow(clippy::unnecessary_map_or)]
+fn check_required_sections(doc: &serde_yaml::Value, format: &SpecFormat) -> Result<(), LoadError> {
+    match get_required_sections(format)
+        .iter()
+        .find(|section| doc.get(**section).map_or(true, serde_yaml::Value::is_null))
+    {
+        Some(section) => Err(LoadError::MissingSection {
+            section: section.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn load_yaml(content: &[u8]) -> Result<serde_yaml::Value, LoadError> {
+    serde_yaml::from_slice(content).map_err(|why| {
+        let location = why.location();
+        LoadError::Yaml {
+            message: why.to_string(),
+            line: location.as_ref().map(|location| location.line()),
+            column: location.as_ref().map(|location| location.column()),
+        }
+    })
+}
+
+//path the spec has been bundled at, and the files (with their blob, none if missing) it refers to
+struct Refs {
+    spec_path: String,
+    files: Vec<(String, Option<Oid>)>,
+}
+
+struct Entry {
+    //none for a fragment, i.e. a yaml file without spec version
+    spec: Result<Option<Arc<IndexedSpec>>, LoadError>,
+    refs: Option<Refs>,
+}
+
+impl Entry {
+    //a spec with external refs is bundled again if it is at another path or if one of its files has changed
+    fn is_valid(&self, resolver: &Resolver, spec_path: &str) -> bool {
+        match &self.refs {
+            Some(refs) => {
+                refs.spec_path == spec_path
+                    && refs
+                        .files
+                        .iter()
+                        .all(|(file_path, oid)| resolver.get_file_id(file_path) == *oid)
+            }
+            None => true,
+        }
+    }
+}
+
+//blobs kept in the index, i.e. the specs of the sources along with the revisions looked up at other refs
+const CAPACITY: usize = 10_000;
+
+struct Index {
+    //entries along with the last time they have been used, see clock
+    entries: HashMap<Oid, (u64, Entry)>,
+    //commit each source (by path) has last been indexed at
+    commits: HashMap<String, Oid>,
+    //ticks on every lookup
+    clock: u64,
+    capacity: usize,
+}
+
+impl Index {
+    fn new(capacity: usize) -> Index {
+        Index {
+            entries: HashMap::new(),
+            commits: HashMap::new(),
+            clock: 0,
+            capacity: capacity,
+        }
+    }
+
+    fn get(&mut self, oid: &Oid) -> Option<&Entry> {
+        self.clock += 1;
+        let clock = self.clock;
+        self.entries.get_mut(oid).map(|(last_used, entry)| {
+            *last_used = clock;
+            &*entry
+        })
+    }
+
+    /**
+     * Beyond its capacity, the least recently used entries are dropped (e.g. the blobs of an old ref compared once).
+     * The index is then shrunk to 90% of its capacity, so that entries are not dropped one by one.
+     */
+    fn insert(&mut self, oid: Oid, entry: Entry) {
+        self.clock += 1;
+        self.entries.insert(oid, (self.clock, entry));
+        if self.entries.len() <= self.capacity {
+            return;
+        }
+
+        let dropped = self.entries.len() - self.capacity * 9 / 10;
+        let mut last_used: Vec<u64> = self
+            .entries
+            .values()
+            .map(|(last_used, _)| *last_used)
+            .collect();
+        //ticks are unique, hence exactly the dropped entries are at or below it
+        let (_, threshold, _) = last_used.select_nth_unstable(dropped - 1);
+        let threshold = *threshold;
+        self.entries
+            .retain(|_, (last_used, _)| *last_used > threshold);
+        debug!(
+            "Dropped [{}] least recently used entries from the index",
+            dropped
+        );
+    }
+
+    fn remove(&mut self, oid: &Oid) {
+        self.entries.remove(oid);
+    }
+}
+
+lazy_static! {
+    static ref INDEX: Mutex<Index> = Mutex::new(Index::new(CAPACITY));
+}
+
+/**
+ * The parsed spec of the blob at spec_path in the tree of the resolver, parsed and kept in the index if not known yet
+ */
+pub fn get(
+    repo: &Repository,
+    resolver: &mut Resolver,
+    spec_path: &str,
+    oid: Oid,
+) -> Result<Option<Arc<IndexedSpec>>, LoadError> {
+    if let Some(entry) = INDEX.lock().unwrap().get(&oid) {
+        if entry.is_valid(resolver, spec_path) {
+            return entry.spec.clone();
+        }
+    }
+
+    //the index is not locked while parsing
+    let entry = parse(repo, resolver, spec_path, oid);
+    let spec = entry.spec.clone();
+    //the blob may be read on the next lookup, unlike a spec that does not parse
+    if let Err(LoadError::Git { .. }) = spec {
+        return spec;
+    }
+    INDEX.lock().unwrap().insert(oid, entry);
+    spec
+}
+
+/**
+ * The parsed spec of the blob if it has already been indexed, whatever the path it has been found at
+ */
+pub fn get_indexed(oid: Oid) -> Option<Arc<IndexedSpec>> {
+    INDEX
+        .lock()
+        .unwrap()
+        .get(&oid)
+        .and_then(|entry| entry.spec.clone().ok().flatten())
+}
+
+/**
+ * Index the specs changed between the commit the source has last been indexed at (if any) and the given one,
+ * the blobs they replace being dropped. Returns the number of parsed blobs.
+ */
+pub fn update(
+    repo: &Repository,
+    source_path: &str,
+    commit: Oid,
+    is_spec: impl Fn(&str) -> bool,
+) -> Result<usize, git2::Error> {
+    let indexed = INDEX.lock().unwrap().commits.get(source_path).copied();
+    if indexed == Some(commit) {
+        return Ok(0);
+    }
+
+    let tree = repo.find_commit(commit)?.tree()?;
+    let old_tree = match indexed {
+        Some(indexed) => repo
+            .find_commit(indexed)
+            .and_then(|indexed| indexed.tree())
+            .ok(),
+        None => None,
+    };
+    let diff = repo.diff_tree_to_tree(old_tree.as_ref(), Some(&tree), None)?;
+    let mut dropped = Vec::new();
+    let mut changed = Vec::new();
+    for delta in diff.deltas() {
+        if let Delta::Modified | Delta::Deleted = delta.status() {
+            dropped.push(delta.old_file().id());
+        }
+        let new_path = match delta.new_file().path() {
+            Some(new_path) => new_path.to_string_lossy().to_string(),
+            None => continue,
+        };
+        if delta.status() != Delta::Deleted && is_spec(&new_path) {
+            changed.push((new_path, delta.new_file().id()));
+        }
+    }
+
+    {
+        let mut index = INDEX.lock().unwrap();
+        for oid in dropped {
+            index.remove(&oid);
+        }
+    }
+    let (tree_id, repo_path) = (tree.id(), repo.path().to_path_buf());
+    let indexed = super::parallel::map(
+        &changed,
+        || Repository::open(&repo_path),
+        |repo, (spec_path, oid)| {
+            let repo = repo.as_ref().map_err(git_error)?;
+            let tree = repo.find_tree(tree_id).map_err(|why| git_error(&why))?;
+            get(repo, &mut Resolver::new(repo, tree), spec_path, *oid)
+        },
+    );
+    for ((spec_path, _), indexed) in changed.iter().zip(indexed) {
+        if let Err(why) = indexed {
+            debug!("Unable to index [{}] - reason [{}]", spec_path, why);
+        }
+    }
+    INDEX
+        .lock()
+        .unwrap()
+        .commits
+        .insert(String::from(source_path), commit);
+
+    Ok(changed.len())
+}
+
+pub fn git_error(why: &git2::Error) -> LoadError {
+    LoadError::Git {
+        message: why.to_string(),
+    }
+}
+
+fn parse(repo: &Repository, resolver: &mut Resolver, spec_path: &str, oid: Oid) -> Entry {
+    let start = Instant::now();
+    let blob = match repo.find_blob(oid) {
+        Ok(blob) => blob,
+        Err(why) => {
+            return Entry {
+                spec: Err(LoadError::Git {
+                    message: format!("Unable to get Blob [{}] - [{}]", oid, why),
+                }),
+                refs: None,
+            }
+        }
+    };
+    let doc = match load_yaml(blob.content()) {
+        Ok(doc) => doc,
+        Err(why) => {
+            return Entry {
+                spec: Err(why),
+                refs: None,
+            }
+        }
+    };
+    //shared schemas, parameters... are referenced by the specs and are not specs by themselves
+    let format = match formats::get_spec_format(&doc) {
+        Some((format, _)) => format,
+        None => {
+            return Entry {
+                spec: Ok(None),
+                refs: None,
+            }
+        }
+    };
+
+    let zally_ignores = std::str::from_utf8(blob.content())
+        .map_err(|why| why.to_string())
+        .and_then(zally::get_ignores)
+        .unwrap_or_else(|why| {
+            warn!(
+                "Unable to get zally ignores of [{}] - reason [{}]",
+                spec_path, why
+            );
+            Vec::new()
+        });
+    let lint_violations = match super::convert_spec_doc(doc.clone()) {
+        Ok((format, _, doc)) if format.spec_type() == SpecType::OpenApi => lint::lint(&doc),
+        _ => Vec::new(),
+    };
+
+    let mut refs = None;
+    let doc = match resolver::has_external_refs(&doc) {
+        true => {
+            let (bundled, files) = resolver.bundle_files(spec_path, &doc);
+            refs = Some(Refs {
+                spec_path: String::from(spec_path),
+                files: files
+                    .into_iter()
+                    .map(|file_path| {
+                        let oid = resolver.get_file_id(&file_path);
+                        (file_path, oid)
+                    })
+                    .collect(),
+            });
+            match bundled {
+                Ok(bundled) => bundled,
+                Err(why) => {
+                    warn!(
+                        "Unable to resolve external refs of [{}] - reason [{}]",
+                        spec_path, why
+                    );
+                    doc
+                }
+            }
+        }
+        false => doc,
+    };
+    //extensions (x-audience...) are not handled by OpenAPI crate
+    let extensions = extensions::get_extensions(&doc);
+
+    let spec = check_required_sections(&doc, &format)
+        .and_then(|_| super::parse_spec_doc(doc).map_err(|why| LoadError::Schema { message: why }));
+    let parse_time = start.elapsed();
+    debug!(
+        "Parsed spec [{}] - Blob [{}] in [{}] ms",
+        spec_path,
+        oid,
+        parse_time.as_millis()
+    );
+
+    Entry {
+        spec: spec.map(|spec| {
+            Some(Arc::new(IndexedSpec {
+                spec: spec,
+                extensions: extensions,
+                zally_ignores: zally_ignores,
+                lint_violations: lint_violations,
+                parse_time: parse_time,
+            }))
+        }),
+        refs: refs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_capacity() {
+        let entry = || Entry {
+            spec: Ok(None),
+            refs: None,
+        };
+        let oid = |idx: u8| Oid::from_bytes(&[idx; 20]).unwrap();
+        let mut index = Index::new(10);
+        for idx in 0..10 {
+            index.insert(oid(idx), entry());
+        }
+        //the first one is used again, the next ones are the least recently used
+        assert!(index.get(&oid(0)).is_some());
+        index.insert(oid(10), entry());
+
+        assert_eq!(index.entries.len(), 9);
+        assert!(index.get(&oid(0)).is_some());
+        assert!(index.get(&oid(1)).is_none());
+        assert!(index.get(&oid(2)).is_none());
+        assert!(index.get(&oid(3)).is_some());
+        assert!(index.get(&oid(10)).is_some());
+    }
+
+    #[test]
+    fn test_index_git_failure() {
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-index-{}-{}",
+            module_path!().replace("::", "-"),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = Repository::init(&dir).unwrap();
+        let tree = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        //a blob the repo does not have
+        let oid =
+            Oid::hash_object(git2::ObjectType::Blob, dir.to_string_lossy().as_bytes()).unwrap();
+
+        let spec = get(&repo, &mut Resolver::new(&repo, tree), "pets.yaml", oid);
+        assert!(matches!(spec, Err(LoadError::Git { .. })));
+        assert!(!INDEX.lock().unwrap().entries.contains_key(&oid));
+    }
+}
@@ -0,0 +1,262 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use super::extensions::{self, SpecExtensions};
+use super::pointer::{escape, to_string, unescape, METHODS};
+use super::rules;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Violation {
+    pub rule: String,
+    pub title: String,
+    //json pointer of the item breaking the rule
+    pub pointer: String,
+}
+
+/**
+ * Evaluate a subset of the zalando guidelines, numbered as zally does so that x-zally-ignore are honoured.
+ * Rules are evaluated on an openapi 3.0 document (swagger 2.0 and openapi 3.1 being converted beforehand),
+ * violations suppressed by a x-zally-ignore (global, per path or per operation) are not returned
+ */
+pub fn lint(doc: &Value) -> Vec<Violation> {
+    let mut violations = Vec::new();
+
+    check_info(doc, &mut violations);
+    check_paths(doc, &mut violations);
+    check_properties(doc, "", &mut violations);
+
+    let extensions = extensions::get_extensions(doc);
+    violations.retain(|violation| !is_ignored(&extensions, violation));
+
+    violations
+}
+
+fn push(violations: &mut Vec<Violation>, rule: i64, pointer: String) {
+    lazy_static! {
+        static ref RULES: Vec<rules::Rule> = rules::get_default_rules();
+    }
+    let rule = rules::get_rule(&RULES, &rule.to_string());
+
+    violations.push(Violation {
+        rule: rule.id,
+        title: rule.title,
+        pointer: pointer,
+    });
+}
+
+fn check_info(doc: &Value, violations: &mut Vec<Violation>) {
+    lazy_static! {
+        static ref SEMVER: Regex = Regex::new(r"^\d+\.\d+\.\d+$").unwrap();
+    }
+    let info = doc.get("info").unwrap_or(&Value::Null);
+
+    for field in &["title", "version", "description", "contact"] {
+        if info.get(field).is_none() {
+            push(violations, 218, format!("/info/{}", field));
+        }
+    }
+    if let Some(contact) = info.get("contact") {
+        for field in &["name", "url", "email"] {
+            if contact.get(field).is_none() {
+                push(violations, 218, format!("/info/contact/{}", field));
+            }
+        }
+    }
+    if info.get("x-api-id").is_none() {
+        push(violations, 215, String::from("/info/x-api-id"));
+    }
+    if info.get("x-audience").is_none() {
+        push(violations, 219, String::from("/info/x-audience"));
+    }
+    if let Some(version) = info.get("version").and_then(|val| val.as_str()) {
+        if !SEMVER.is_match(version) {
+            push(violations, 116, String::from("/info/version"));
+        }
+    }
+}
+
+fn check_paths(doc: &Value, violations: &mut Vec<Violation>) {
+    lazy_static! {
+        static ref VERSION: Regex = Regex::new(r"^v\d+$").unwrap();
+        static ref KEBAB_CASE: Regex = Regex::new(r"^[a-z0-9]+(-[a-z0-9]+)*$").unwrap();
+        static ref SNAKE_CASE: Regex = Regex::new(r"^[a-z][a-z0-9]*(_[a-z0-9]+)*$").unwrap();
+    }
+    let paths = match doc.get("paths").and_then(|val| val.as_mapping()) {
+        Some(paths) => paths,
+        None => return,
+    };
+
+    for (path, item) in paths.iter() {
+        let path = path.as_str().unwrap_or_default();
+        let pointer = format!("/paths/{}", escape(path));
+
+        if path.len() > 1 && path.ends_with('/') {
+            push(violations, 136, pointer.clone());
+        }
+        let segments: Vec<&str> = path.split('/').filter(|val| !val.is_empty()).collect();
+        for (idx, segment) in segments.iter().enumerate() {
+            if segment.starts_with('{') {
+                continue;
+            }
+            if VERSION.is_match(segment) {
+                push(violations, 115, pointer.clone());
+            } else if !KEBAB_CASE.is_match(segment) {
+                push(violations, 129, pointer.clone());
+            } else if segments
+                .get(idx + 1)
+                .map(|next| next.starts_with('{'))
+                .unwrap_or(false)
+                && !segment.ends_with('s')
+            {
+                //a resource accessed by id is a collection
+                push(violations, 134, pointer.clone());
+            }
+        }
+
+        for method in METHODS.iter() {
+            let operation = match item.get(method) {
+                Some(operation) => operation,
+                None => continue,
+            };
+            let operation_pointer = format!("{}/{}", pointer, method);
+
+            if let Some(parameters) = operation
+                .get("parameters")
+                .and_then(|val| val.as_sequence())
+            {
+                for (idx, parameter) in parameters.iter().enumerate() {
+                    let is_query =
+                        parameter.get("in").and_then(|val| val.as_str()) == Some("query");
+                    let name = parameter.get("name").and_then(|val| val.as_str());
+                    if let (true, Some(name)) = (is_query, name) {
+                        if !SNAKE_CASE.is_match(name) {
+                            push(
+                                violations,
+                                130,
+                                format!("{}/parameters/{}", operation_pointer, idx),
+                            );
+                        }
+                    }
+                }
+            }
+
+            check_responses(operation, &operation_pointer, violations);
+        }
+    }
+}
+
+fn check_responses(operation: &Value, operation_pointer: &str, violations: &mut Vec<Violation>) {
+    let responses = match operation.get("responses").and_then(|val| val.as_mapping()) {
+        Some(responses) => responses,
+        None => return,
+    };
+
+    let codes: Vec<String> = responses.iter().map(|(code, _)| to_string(code)).collect();
+    let has_success = codes
+        .iter()
+        .any(|code| code.starts_with('2') || code.starts_with('3'));
+    let has_error = codes
+        .iter()
+        .any(|code| code.starts_with('4') || code.starts_with('5') || code == "default");
+    if !has_success || !has_error {
+        push(violations, 151, format!("{}/responses", operation_pointer));
+    }
+
+    for (code, response) in responses.iter() {
+        let code = to_string(code);
+        let is_error = code.starts_with('4') || code.starts_with('5') || code == "default";
+        let content = response.get("content").and_then(|val| val.as_mapping());
+        if let (true, Some(content)) = (is_error, content) {
+            if !content
+                .iter()
+                .any(|(media_type, _)| media_type.as_str() == Some("application/problem+json"))
+            {
+                push(
+                    violations,
+                    176,
+                    format!("{}/responses/{}/content", operation_pointer, escape(&code)),
+                );
+            }
+        }
+    }
+}
+
+//properties are checked wherever they are defined (components, inlined request / response schemas...)
+fn check_properties(val: &Value, pointer: &str, violations: &mut Vec<Violation>) {
+    lazy_static! {
+        static ref SNAKE_CASE: Regex = Regex::new(r"^[a-z][a-z0-9]*(_[a-z0-9]+)*$").unwrap();
+        static ref UPPER_SNAKE_CASE: Regex = Regex::new(r"^[A-Z][A-Z0-9]*(_[A-Z0-9]+)*$").unwrap();
+    }
+
+    match val {
+        Value::Mapping(mapping) => {
+            for (key, child) in mapping.iter() {
+                let key = to_string(key);
+                let child_pointer = format!("{}/{}", pointer, escape(&key));
+                match (key.as_str(), child) {
+                    //examples are data, not schemas
+                    ("example", _) | ("examples", _) => continue,
+                    ("properties", Value::Mapping(properties)) => {
+                        for (name, _) in properties.iter() {
+                            let name = to_string(name);
+                            if !SNAKE_CASE.is_match(&name) {
+                                push(
+                                    violations,
+                                    118,
+                                    format!("{}/{}", child_pointer, escape(&name)),
+                                );
+                            }
+                        }
+                    }
+                    ("enum", Value::Sequence(values)) => {
+                        let is_invalid = |val: &Value| match val.as_str() {
+                            Some(val) => !UPPER_SNAKE_CASE.is_match(val),
+                            None => false,
+                        };
+                        if values.iter().any(is_invalid) {
+                            push(violations, 240, child_pointer.clone());
+                        }
+                    }
+                    _ => {}
+                }
+                check_properties(child, &child_pointer, violations);
+            }
+        }
+        Value::Sequence(sequence) => {
+            for (idx, child) in sequence.iter().enumerate() {
+                check_properties(child, &format!("{}/{}", pointer, idx), violations);
+            }
+        }
+        _ => {}
+    }
+}
+
+//x-zally-ignore can be set at the root of the spec, on a path or on an operation
+fn is_ignored(extensions: &SpecExtensions, violation: &Violation) -> bool {
+    if extensions.get_zally_ignore().contains(&violation.rule) {
+        return true;
+    }
+    if !violation.pointer.starts_with("/paths/") {
+        return false;
+    }
+
+    let segments: Vec<String> = violation
+        .pointer
+        .trim_start_matches("/paths/")
+        .split('/')
+        .map(unescape)
+        .collect();
+    let path = segments.first().cloned().unwrap_or_default();
+    let is_path_ignored = extensions
+        .get_path(&path)
+        .map(|item| item.get_zally_ignore().contains(&violation.rule))
+        .unwrap_or(false);
+    let is_operation_ignored = segments
+        .get(1)
+        .and_then(|method| extensions.get_operation(&path, method))
+        .map(|operation| operation.get_zally_ignore().contains(&violation.rule))
+        .unwrap_or(false);
+
+    is_path_ignored || is_operation_ignored
+}
@@ -1,651 +1,3669 @@
 extern crate glob;
-use glob::glob;
+use glob::{MatchOptions, Pattern};
 use log::{debug, info, warn};
 
-extern crate yaml_rust;
-use yaml_rust::{Yaml, YamlLoader};
-
 use std::collections::HashMap;
 
+use std::fmt;
 use std::path::Path;
+use std::sync::{Arc, Mutex};
 use std::vec::Vec;
 
 extern crate git2;
-use git2::{Blob, Oid, Repository};
+use git2::{Blob, Commit, ObjectType, Oid, Repository, Tree, TreeWalkMode, TreeWalkResult};
 
 use openapiv3::OpenAPI;
+use serde::{Deserialize, Serialize};
 use serde_yaml;
 
-use cmd_lib::run_cmd;
+use uuid::Uuid;
+
+use super::super::settings::{CatalogSource, DEFAULT_SOURCE};
+
+mod asyncapi;
+pub use asyncapi::AsyncAPI;
+
+mod formats;
+use formats::SpecFormat;
+
+mod resolver;
+use resolver::Resolver;
+
+mod pointer;
+
+pub mod operations;
+
+pub mod compare;
+
+pub mod review;
+
+pub mod lint;
+
+pub mod rules;
+
+pub mod zally;
+
+pub mod extensions;
+
+pub mod domain_rules;
+
+pub mod git;
+
+pub mod history;
 
-extern crate regex;
-use regex::Regex;
+pub mod churn;
 
-use std::fs;
+pub mod reconciliation;
+
+mod index;
+pub use index::LoadError;
+
+mod parallel;
 
 //
 #[derive(Debug, Clone)]
 pub struct SpecItem {
     pub path: std::string::String,
     pub id: std::string::String,
+    //same for all the revisions of the spec, see get_stable_id
+    pub stable_id: std::string::String,
+    //name of the catalog source the spec comes from
+    pub source: std::string::String,
     pub api_spec: OpenAPI,
     pub audience: std::string::String,
-    pub domain: std::string::String,
+    //domain of the spec and the rule it comes from, see get_domain
+    pub domain_match: domain_rules::DomainMatch,
+    //version as written in the file (swagger 2.0 and openapi 3.1 are converted into the 3.0 model)
+    pub spec_version: std::string::String,
+    pub extensions: extensions::SpecExtensions,
 }
 
-pub fn list_specs(path: &str) -> Vec<SpecItem> {
-    let mut specs = Vec::new();
-    //get connection to git repo (should be cloned as prerequisite)
-    if let Ok(repo) = get_git_repo(path) {
-        let pattern = format!("{}{}", path, "/**/*.yaml"); //TODO fragile
-        for entry in glob(&pattern).unwrap().filter_map(Result::ok) {
-            let path = entry.display().to_string();
-            let file_path = Path::new(&path);
-            let oid: Oid = match repo.blob_path(file_path) {
-                Ok(oid) => oid,
-                Err(why) => {
-                    panic!("Unable to get File: {}", why);
-                }
-            };
-            //generate the OpenAPI
-            let blob: Blob = match repo.find_blob(oid) {
-                Ok(blob) => blob,
-                Err(why) => {
-                    panic!("Unable to get Blob: {}", why);
-                }
-            };
+#[derive(Debug, Clone)]
+pub struct AsyncSpecItem {
+    pub path: std::string::String,
+    pub id: std::string::String,
+    pub stable_id: std::string::String,
+    pub source: std::string::String,
+    pub api_spec: AsyncAPI,
+    pub audience: std::string::String,
+    pub domain_match: domain_rules::DomainMatch,
+    pub extensions: extensions::SpecExtensions,
+}
 
-            match serde_yaml::from_reader(blob.content()) {
-                Ok(openapi) => {
-                    //audience is defiend as x-audience and extensions are not handled by OpenAPI crate
-                    //TODO this whole thing has to be reworked
-                    let audience = match get_audience_from_spec(&file_path) {
-                        Some(aud) => aud,
-                        None => String::from("N/A"),
-                    };
-                    let domain = get_domain_from_spec(&openapi);
-                    //create the API Item and add it to the returned value
-                    let spec = SpecItem {
-                        path: path,
-                        id: format!("{:?}", oid),
-                        api_spec: openapi.clone(),
-                        audience: audience,
-                        domain: domain.to_string(),
-                    };
-                    specs.push(spec);
-                }
-                Err(why) => {
-                    warn!("Unable to parse file [{:?}] - reason [{:?}]", path, why);
-                }
-            }
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpecType {
+    OpenApi,
+    AsyncApi,
+}
+
+impl SpecType {
+    pub fn as_str(&self) -> String {
+        match *self {
+            SpecType::OpenApi => String::from("openapi"),
+            SpecType::AsyncApi => String::from("asyncapi"),
         }
-    } else {
-        warn!("Unable to get git repo from path [{}]", path);
     }
+}
 
-    specs
+//a file matching the patterns of a source that could not be loaded as a spec
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SpecLoadError {
+    pub source: std::string::String,
+    pub path: std::string::String,
+    pub id: std::string::String,
+    pub error: LoadError,
 }
 
-fn get_audience_from_spec(spec: &Path) -> Option<String> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"(x-audience)(\s*:)(.+)").unwrap();
-    }
-    let spec_content = fs::read_to_string(spec).unwrap_or_default();
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    pub specs: Vec<SpecItem>,
+    pub async_specs: Vec<AsyncSpecItem>,
+    //files that are listed neither as specs nor as fragments
+    pub errors: Vec<SpecLoadError>,
+}
 
-    if let Some(cap) = RE.captures(spec_content.as_str()) {
-        debug!(
-            "found x-audience [{}] in spec [{:?}]",
-            cap[3].to_string(),
-            spec
-        );
-        Some(cap[3].to_string())
-    } else {
-        debug!("Unable to x-audience from [{:?}]", spec);
-        None
+impl Catalog {
+    fn extend(&mut self, other: Catalog) {
+        self.specs.extend(other.specs);
+        self.async_specs.extend(other.async_specs);
+        self.errors.extend(other.errors);
     }
 }
 
-//
-pub fn get_spec(path: &str, id: &str) -> Vec<SpecItem> {
-    let mut specs = Vec::new();
-    //get connection to git repo (should be cloned as prerequisite)
-    if let Ok(repo) = get_git_repo(path) {
-        //generate the OpenAPI
-        if let Ok(oid) = Oid::from_str(id) {
-            let blob: Blob = match repo.find_blob(oid) {
-                Ok(blob) => blob,
-                Err(why) => {
-                    panic!("Unable to get Blob: {}", why);
-                }
-            };
+/**
+ * Errors of the lookups in the catalog, the HTTP API converts them into an ApiError
+ */
+#[derive(Debug)]
+pub enum CatalogError {
+    //unknown spec, source or ref, file missing at a ref...
+    NotFound { message: String },
+    //the repo of a source cannot be opened or read
+    Git { message: String },
+    //the spec is found but cannot be loaded
+    Spec { id: String, message: String },
+    //e.g. linting an asyncapi spec
+    Unsupported { message: String },
+}
 
-            if let Ok(openapi) = serde_yaml::from_reader(blob.content()) {
-                //audience is defiend as x-audience and extensions are not handled by OpenAPI crate
-                //TODO this whole thing has to be reworked
-                let audience = match get_audience_from_spec(Path::new(path)) {
-                    Some(aud) => aud,
-                    None => String::from("N/A"),
-                };
-                let domain = get_domain_from_spec(&openapi);
-                //create the API Item and add it to the returned value
-                let spec = SpecItem {
-                    path: path.to_string(),
-                    id: format!("{:?}", oid),
-                    api_spec: openapi.clone(),
-                    audience: audience,
-                    domain: domain.to_string(),
-                };
-                specs.push(spec);
-            } else {
-                warn!("Unable to parse file [{}]", path);
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CatalogError::NotFound { message } => write!(f, "{}", message),
+            CatalogError::Git { message } => write!(f, "{}", message),
+            CatalogError::Spec { id, message } => {
+                write!(f, "Unable to load spec [{}] - [{}]", id, message)
             }
+            CatalogError::Unsupported { message } => write!(f, "{}", message),
         }
-    } else {
-        warn!("Unable to get git repo from path [{}]", path);
     }
+}
 
-    specs
+impl std::error::Error for CatalogError {}
+
+fn not_found(message: String) -> CatalogError {
+    CatalogError::NotFound { message: message }
 }
 
-pub fn get_spec_short_path(catalog_dir_srt: String, spec: &SpecItem) -> &str {
-    let short_path = &spec.path[catalog_dir_srt.as_str().len()..spec.path.len()];
+fn git_failure(message: String) -> CatalogError {
+    CatalogError::Git { message: message }
+}
 
-    short_path
+fn spec_failure(id: &str, message: String) -> CatalogError {
+    CatalogError::Spec {
+        id: String::from(id),
+        message: message,
+    }
 }
 
-//
-fn get_git_repo(path: &str) -> Result<Repository, git2::Error> {
-    let repo: Repository = Repository::open(path)?;
-    info!(
-        "Parsing yaml files from Git Repo [{}]",
-        path.to_string() + &"**/*.yaml".to_string()
-    );
+pub fn list_specs(sources: &[CatalogSource]) -> Vec<SpecItem> {
+    list_catalog(sources).0
+}
 
-    Ok(repo)
+pub fn list_async_specs(sources: &[CatalogSource]) -> Vec<AsyncSpecItem> {
+    list_catalog(sources).1
 }
 
-pub fn refresh_git_repo(path: &str) {
-    //TODO maybe a cleaner way https://github.com/rust-lang/git2-rs/commit/f3b87baed1e33d6c2d94fe1fa6aa6503a071d837
-    //TODO be more proper on error management here.typical case: credentials to git pull are no longer working...
-    run_cmd!("cd {}; git pull", path).unwrap();
-    info!("Refresh Git Repo with result [{:?}]", "result");
+pub fn list_catalog(sources: &[CatalogSource]) -> (Vec<SpecItem>, Vec<AsyncSpecItem>) {
+    list_catalog_at(sources, None).unwrap_or_default()
 }
 
-pub fn get_zally_ignore(all_specs: &Vec<SpecItem>) -> std::collections::HashMap<i64, usize> {
-    let mut merged_stats = std::collections::HashMap::new();
+/**
+ * Specs of all the sources as of the given branch, tag or commit (HEAD if not set), read from the git trees.
+ * A source being listed once if its name is used several times, and skipped if it does not have the ref.
+ * Fails if none of the sources has the ref.
+ */
+pub fn list_catalog_at(
+    sources: &[CatalogSource],
+    git_ref: Option<&str>,
+) -> Result<(Vec<SpecItem>, Vec<AsyncSpecItem>), CatalogError> {
+    load_catalog(sources, git_ref).map(|catalog| (catalog.specs, catalog.async_specs))
+}
 
-    // let specs = list_specs(path);
-    for spec in all_specs.iter() {
-        //need to load the yaml file as OpenAPI crate will remove the x-zally-ignore...
-        let yaml_spec_as_string = std::fs::read_to_string(spec.path.as_str()).unwrap();
-        let stats = get_zally_ignore_metrics(yaml_spec_as_string.as_str(), spec.path.as_str());
+/**
+ * Same as list_catalog_at, along with the files that could not be loaded as specs
+ */
+pub fn load_catalog(
+    sources: &[CatalogSource],
+    git_ref: Option<&str>,
+) -> Result<Catalog, CatalogError> {
+    let mut catalog = Catalog::default();
+    let mut names = std::collections::HashSet::new();
+    let mut errors = Vec::new();
+    for source in sources {
+        if !names.insert(source.name.as_str()) {
+            warn!(
+                "Catalog source [{}] is declared more than once, only the first one is listed",
+                source.name
+            );
+            continue;
+        }
+        match list_source_catalog(source, git_ref) {
+            Ok(source_catalog) => catalog.extend(source_catalog),
+            Err(why) => {
+                warn!(
+                    "Unable to list specs of source [{}] - reason [{}]",
+                    source.name, why
+                );
+                errors.push(why);
+            }
+        }
+    }
 
-        //some the maps
-        for (key, val) in stats.iter() {
-            match merged_stats.get(key) {
-                Some(known_val) => {
-                    merged_stats.insert(*key, val + known_val);
-                }
-                None => {
-                    merged_stats.insert(*key, *val);
+    match git_ref {
+        Some(_) if !errors.is_empty() && errors.len() == names.len() => {
+            let message = errors
+                .iter()
+                .map(|why| why.to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            //the ref is unknown, unless a repo could not be read
+            match errors
+                .iter()
+                .all(|why| matches!(why, CatalogError::NotFound { .. }))
+            {
+                true => Err(not_found(message)),
+                false => Err(git_failure(message)),
+            }
+        }
+        _ => Ok(catalog),
+    }
+}
+
+//commits the sources were on at a date, along with their index in the sources
+pub type CatalogSnapshot = Vec<(usize, history::Snapshot)>;
+
+/**
+ * Commit of each source as of each of the dates, i.e. the last commit of its branch made before it.
+ * Sources whose history starts after a date are left out of its snapshot, the history of a source is walked once.
+ */
+pub fn get_catalog_snapshots(
+    sources: &[CatalogSource],
+    dates: &[chrono::DateTime<chrono::Utc>],
+) -> Vec<CatalogSnapshot> {
+    let mut snapshots = vec![CatalogSnapshot::new(); dates.len()];
+    let mut names = std::collections::HashSet::new();
+    for (idx, source) in sources.iter().enumerate() {
+        if !names.insert(source.name.as_str()) {
+            continue;
+        }
+        let as_of = get_git_repo(&source.path).and_then(|repo| {
+            let head = repo.head()?.peel_to_commit()?.id();
+            history::get_commits_as_of(&repo, head, dates)
+        });
+        match as_of {
+            Ok(as_of) => {
+                for (snapshot, commit) in snapshots.iter_mut().zip(as_of) {
+                    if let Some(commit) = commit {
+                        snapshot.push((idx, commit));
+                    }
                 }
             }
+            Err(why) => warn!(
+                "Unable to walk the history of source [{}] - reason [{}]",
+                source.name, why
+            ),
         }
     }
-    merged_stats
+
+    snapshots
 }
 
-fn get_zally_ignore_metrics(spec: &str, spec_name: &str) -> std::collections::HashMap<i64, usize> {
-    debug!(
-        "get_zally_ignore_metrics is called for spec {:?}",
-        spec_name
-    );
+/**
+ * Specs of the sources as they were at the commits of the snapshot
+ */
+pub fn list_catalog_snapshot(
+    sources: &[CatalogSource],
+    snapshot: &CatalogSnapshot,
+) -> (Vec<SpecItem>, Vec<AsyncSpecItem>) {
+    let mut specs = Vec::new();
+    let mut async_specs = Vec::new();
+    for (idx, as_of) in snapshot {
+        let source = &sources[*idx];
+        let repo = match get_git_repo(&source.path) {
+            Ok(repo) => repo,
+            Err(why) => {
+                warn!(
+                    "Unable to get git repo of source [{}] - reason [{}]",
+                    source.name, why
+                );
+                continue;
+            }
+        };
+        let listed = repo
+            .find_commit(as_of.commit)
+            .map_err(|why| git_failure(why.to_string()))
+            .and_then(|commit| list_commit_catalog(source, &commit, &as_of.origin_paths));
+        match listed {
+            Ok(source_catalog) => {
+                specs.extend(source_catalog.specs);
+                async_specs.extend(source_catalog.async_specs);
+            }
+            Err(why) => warn!(
+                "Unable to list specs of source [{}] at [{}] - reason [{}]",
+                source.name, as_of.commit, why
+            ),
+        }
+    }
 
-    let docs = match YamlLoader::load_from_str(spec) {
-        Ok(docs) => docs,
-        Err(why) => {
-            panic!("Error while parsing spec {} - :{:?}", spec, why);
+    (specs, async_specs)
+}
+
+fn list_source_catalog(
+    source: &CatalogSource,
+    git_ref: Option<&str>,
+) -> Result<Catalog, CatalogError> {
+    let path = source.path.as_str();
+    //get connection to git repo (should be cloned as prerequisite)
+    let repo = get_git_repo(path).map_err(|why| {
+        git_failure(format!(
+            "Unable to get git repo of source [{}] from path [{}] - [{}]",
+            source.name, path, why
+        ))
+    })?;
+    if git_ref.is_none() {
+        check_branch(&repo, source);
+    }
+    let commit = get_commit(&repo, git_ref)?;
+    let origin_paths = get_origin_paths(&repo, source, commit.id());
+
+    list_commit_catalog(source, &commit, &origin_paths)
+}
+
+fn list_commit_catalog(
+    source: &CatalogSource,
+    commit: &Commit,
+    origin_paths: &history::OriginPaths,
+) -> Result<Catalog, CatalogError> {
+    let path = source.path.as_str();
+    let mut catalog = Catalog::default();
+    let tree = commit.tree().map_err(|why| {
+        git_failure(format!(
+            "Unable to get tree of [{}] - [{}]",
+            commit.id(),
+            why
+        ))
+    })?;
+    let files = get_tree_files(&tree);
+
+    //files matching the patterns, in the order of the patterns
+    let mut listed = std::collections::HashSet::new();
+    let mut entries = Vec::new();
+    for pattern in get_patterns(source) {
+        for (spec_path, oid) in files.iter() {
+            if pattern.matches_with(spec_path, PATTERN_OPTIONS) && listed.insert(spec_path) {
+                entries.push((spec_path.as_str(), *oid));
+            }
         }
-    }; // Result<Vec<Yaml>, ScanError>
-    let doc = docs[0].as_hash().unwrap(); //Option<&Hash> et LinkedHashMap<Yaml, Yaml>;
+    }
 
-    // let iter = doc.iter();
-    // for item in iter {
-    //     println!("---------");
-    //     println!("{:?}", &item);
-    //     println!("---------");
-    // }
-    let mut stats = std::collections::HashMap::new();
-    //get global zally-ignore
-    {
-        match doc.get(&Yaml::String(String::from("x-zally-ignore"))) {
-            Some(val) => {
-                //println!("x-zally-ignore {:?}", val);
+    //a repo cannot be shared between threads, each worker opens its own
+    let tree_id = tree.id();
+    let indexed_specs = parallel::map(
+        &entries,
+        || Repository::open(path),
+        |repo, (spec_path, oid)| {
+            let repo = repo.as_ref().map_err(index::git_error)?;
+            let tree = repo
+                .find_tree(tree_id)
+                .map_err(|why| index::git_error(&why))?;
+            //external $ref are resolved against the files of the same tree
+            index::get(repo, &mut Resolver::new(repo, tree), spec_path, *oid)
+        },
+    );
 
-                let paths = doc
-                    .get(&Yaml::String(String::from("paths")))
-                    .unwrap()
-                    .as_hash()
-                    .unwrap();
-
-                for elt in val.as_vec().unwrap() {
-                    stats.insert(elt.as_i64().unwrap(), paths.len());
-                    // println!(
-                    //     "x-zally-ignore {:?} {:?}",
-                    //     elt.as_i64(),
-                    //     elt.as_i64().unwrap()
-                    // );
-                    // println!("path len {:?}", paths.len());
-                }
+    for ((spec_path, oid), indexed) in entries.into_iter().zip(indexed_specs) {
+        let path = format!("{}/{}", path.trim_end_matches('/'), spec_path);
+        let indexed = match indexed {
+            Ok(Some(indexed)) => indexed,
+            Ok(None) => {
+                debug!(
+                    "No spec version in file [{:?}], considered as a fragment",
+                    path
+                );
+                continue;
+            }
+            Err(why) => {
+                warn!("Unable to parse file [{:?}] - reason [{}]", path, why);
+                catalog.errors.push(SpecLoadError {
+                    source: source.name.clone(),
+                    path: path,
+                    id: get_spec_id(source, oid),
+                    error: why,
+                });
+                continue;
             }
-            None => info!("no global zally-ignore for spec {:?}", spec_name),
         };
+        let extensions = indexed.extensions.clone();
+        let audience = extensions
+            .get_audience()
+            .unwrap_or_else(|| String::from("N/A"));
+        let stable_id = get_stable_id(
+            source,
+            origin_paths
+                .get(spec_path)
+                .map_or(spec_path, String::as_str),
+        );
+        let domain_match = get_domain(source, &indexed.spec, &extensions, &path);
+
+        match &indexed.spec {
+            ParsedSpec::OpenApi(openapi, spec_version) => {
+                //create the API Item and add it to the returned value
+                let spec = SpecItem {
+                    path: path,
+                    id: get_spec_id(source, oid),
+                    stable_id: stable_id,
+                    source: source.name.clone(),
+                    api_spec: *openapi.clone(),
+                    audience: audience,
+                    domain_match: domain_match,
+                    spec_version: spec_version.clone(),
+                    extensions: extensions,
+                };
+                catalog.specs.push(spec);
+            }
+            ParsedSpec::AsyncApi(asyncapi) => {
+                let spec = AsyncSpecItem {
+                    path: path,
+                    id: get_spec_id(source, oid),
+                    stable_id: stable_id,
+                    source: source.name.clone(),
+                    api_spec: *asyncapi.clone(),
+                    audience: audience,
+                    domain_match: domain_match,
+                    extensions: extensions,
+                };
+                catalog.async_specs.push(spec);
+            }
+        }
     }
 
-    //get zally-ignore per path
-    let mut stats_per_path: HashMap<i64, usize> = std::collections::HashMap::new();
-    {
-        let paths = doc
-            .get(&Yaml::String(String::from("paths")))
-            .unwrap()
-            .as_hash()
-            .unwrap();
+    Ok(catalog)
+}
 
-        for path in paths.iter() {
-            // println!("{:?}", path.0);
-            // println!("{:?}", path.1);
-            let zally = path
-                .1
-                .as_hash()
-                .unwrap()
-                .get(&Yaml::String(String::from("x-zally-ignore")));
-
-            match zally {
-                Some(val) => {
-                    for elt in val.as_vec().unwrap() {
-                        let elt = match elt.as_i64() {
-                            Some(val) => val,
-                            None => {
-                                warn!("Got zally-ignore [{:?}]", elt);
-                                -1
-                            }
-                        };
-                        let stat = stats_per_path.get(&elt).cloned();
-                        match stat {
-                            Some(val) => {
-                                stats_per_path.insert(elt, val + 1);
-                            }
-                            None => {
-                                stats_per_path.insert(elt, 1);
-                            }
-                        }
-                    }
-                }
-                None => {
-                    info!(
-                        "no zally-ignore on paths for spec {:?} and path {:?}",
-                        spec_name, path.0
+const PATTERN_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+//patterns of the files of the source that are specs, in their order of declaration
+fn get_patterns(source: &CatalogSource) -> Vec<Pattern> {
+    source
+        .patterns
+        .iter()
+        .filter_map(
+            |pattern| match Pattern::new(pattern.trim_start_matches('/')) {
+                Ok(pattern) => Some(pattern),
+                Err(why) => {
+                    warn!(
+                        "Invalid pattern [{}] of source [{}] - reason [{}]",
+                        pattern, source.name, why
                     );
+                    None
                 }
-            }
+            },
+        )
+        .collect()
+}
+
+//commit of a branch, tag or commit, HEAD if not set. Branches are looked for among the remote ones too
+fn get_commit<'a>(repo: &'a Repository, git_ref: Option<&str>) -> Result<Commit<'a>, CatalogError> {
+    let git_ref = match git_ref {
+        Some(git_ref) => git_ref,
+        None => {
+            return repo
+                .head()
+                .and_then(|head| head.peel_to_commit())
+                .map_err(|why| git_failure(format!("Unable to get HEAD commit - [{}]", why)))
+        }
+    };
+    repo.revparse_single(git_ref)
+        .or_else(|_| repo.revparse_single(&format!("origin/{}", git_ref)))
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|why| not_found(format!("Unable to resolve ref [{}] - [{}]", git_ref, why)))
+}
+
+fn get_tree<'a>(repo: &'a Repository, git_ref: Option<&str>) -> Result<Tree<'a>, CatalogError> {
+    get_commit(repo, git_ref)?
+        .tree()
+        .map_err(|why| git_failure(why.to_string()))
+}
+
+/**
+ * Stable ids are the same for all the revisions of a spec, they are derived from its source and from
+ * the path it has been added at, so that they survive the renames of the file.
+ */
+fn get_stable_id(source: &CatalogSource, origin_path: &str) -> String {
+    let name = format!("{}:{}", source.name, origin_path);
+    Uuid::new_v5(&Uuid::NAMESPACE_URL, name.as_bytes()).to_string()
+}
+
+//walking the whole history is costly, hence the origin paths of the last commit listed per repo are kept
+fn get_origin_paths(
+    repo: &Repository,
+    source: &CatalogSource,
+    commit: Oid,
+) -> history::OriginPaths {
+    lazy_static! {
+        static ref ORIGIN_PATHS: Mutex<HashMap<String, (Oid, history::OriginPaths)>> =
+            Mutex::new(HashMap::new());
+    }
+    let mut cache = ORIGIN_PATHS.lock().unwrap();
+    if let Some((cached, origin_paths)) = cache.get(&source.path) {
+        if *cached == commit {
+            return origin_paths.clone();
         }
-        // println!("stats_per_path {:?}", stats_per_path);
-        // println!("len {:?}", paths.len());
     }
 
-    //merge both maps
-    for stat in stats_per_path.iter() {
-        //check if stat already exist in global, if not add it to stats
-        if stats.contains_key(stat.0) {
-            debug!("stats {:?} already in global stats", stat.0);
-        } else {
-            stats.insert(*stat.0, *stat.1);
+    let origin_paths = history::get_origin_paths(repo, commit).unwrap_or_else(|why| {
+        warn!(
+            "Unable to follow the renames of source [{}] - reason [{}]",
+            source.name, why
+        );
+        history::OriginPaths::new()
+    });
+    cache.insert(source.path.clone(), (commit, origin_paths.clone()));
+    origin_paths
+}
+
+//source, repo and path at HEAD of the spec with the given stable id
+fn find_stable_id<'a>(
+    sources: &'a [CatalogSource],
+    id: &str,
+) -> Option<(&'a CatalogSource, Repository, String)> {
+    for source in sources {
+        let repo = match get_git_repo(&source.path) {
+            Ok(repo) => repo,
+            Err(_) => continue,
+        };
+        let head = match get_commit(&repo, None) {
+            Ok(head) => head.id(),
+            Err(_) => continue,
+        };
+        let path = get_origin_paths(&repo, source, head)
+            .into_iter()
+            .find(|(_, origin_path)| get_stable_id(source, origin_path) == id)
+            .map(|(path, _)| path);
+        if let Some(path) = path {
+            return Some((source, repo, path));
         }
     }
 
-    stats
+    None
 }
 
-pub fn get_endpoints_num_per_audience(
-    all_specs: &Vec<SpecItem>,
-) -> std::collections::HashMap<String, usize> {
-    let mut merged_stats = std::collections::HashMap::new();
+//path (from the root of the tree) and blob id of all the files of the tree
+fn get_tree_files(tree: &Tree) -> Vec<(String, Oid)> {
+    let mut files = Vec::new();
+    let _ = tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if let (Some(ObjectType::Blob), Some(name)) = (entry.kind(), entry.name()) {
+            files.push((format!("{}{}", root, name), entry.id()));
+        }
+        TreeWalkResult::Ok
+    });
 
-    for spec in all_specs.iter() {
-        //need to load the yaml file as OpenAPI crate will remove the x-zally-ignore...
-        let yaml_spec_as_string = std::fs::read_to_string(spec.path.as_str()).unwrap();
-        let stats = get_endpoints_num_per_audience_metrics(
-            yaml_spec_as_string.as_str(),
-            spec.path.as_str(),
-        );
+    files
+}
 
-        //sum the maps
-        for (key, val) in stats.iter() {
-            match merged_stats.get(key) {
-                Some(known_val) => {
-                    merged_stats.insert(key.clone(), val + known_val);
-                }
-                None => {
-                    merged_stats.insert(key.clone(), *val);
-                }
-            }
+//specs are listed from the checked out branch, which may not be the one of the source
+fn check_branch(repo: &Repository, source: &CatalogSource) {
+    if let Some(branch) = &source.branch {
+        let head = repo
+            .head()
+            .ok()
+            .and_then(|head| head.shorthand().map(String::from));
+        if head.as_deref() != Some(branch.as_str()) {
+            warn!(
+                "Source [{}] is on [{:?}] instead of branch [{}]",
+                source.name, head, branch
+            );
         }
     }
-    merged_stats
 }
 
-fn get_endpoints_num_per_audience_metrics(
-    spec: &str,
-    spec_name: &str,
-) -> std::collections::HashMap<String, usize> {
-    debug!(
-        "get_endpoints_num_per_audience_metrics is called for spec {:?}",
-        spec_name
-    );
+/**
+ * Ids of the specs are their blob ids, prefixed by the name of their source so that they are unique across repos.
+ * Specs of the default source keep the bare blob id.
+ */
+fn get_spec_id(source: &CatalogSource, oid: Oid) -> String {
+    match source.name.as_str() {
+        DEFAULT_SOURCE => format!("{:?}", oid),
+        name => format!("{}:{:?}", name, oid),
+    }
+}
 
-    let docs = match YamlLoader::load_from_str(spec) {
-        Ok(docs) => docs,
-        Err(why) => {
-            panic!("Error while parsing spec {} - :{:?}", spec, why);
-        }
-    }; // Result<Vec<Yaml>, ScanError>
-    let doc = docs[0].as_hash().unwrap(); //Option<&Hash> et LinkedHashMap<Yaml, Yaml>;
-    let doc_info_tag = doc
-        .get(&Yaml::String(String::from("info")))
-        .unwrap()
-        .as_hash()
-        .unwrap(); //Option<&Hash> et LinkedHashMap<Yaml, Yaml>;
+//blob (or commit) id part of an id
+fn get_oid(id: &str) -> Result<Oid, CatalogError> {
+    let oid = id.rsplit(':').next().unwrap_or(id);
+    Oid::from_str(oid).map_err(|why| not_found(format!("Unknown id [{}] - [{}]", id, why)))
+}
 
-    let paths = doc
-        .get(&Yaml::String(String::from("paths")))
-        .unwrap()
-        .as_hash()
-        .unwrap();
-    let num_of_endpoints = paths.len();
+pub fn get_source<'a>(sources: &'a [CatalogSource], name: &str) -> Option<&'a CatalogSource> {
+    sources.iter().find(|source| source.name == name)
+}
 
-    let mut stats = std::collections::HashMap::new();
-    {
-        match doc_info_tag.get(&Yaml::String(String::from("x-audience"))) {
-            Some(val) => {
-                info!("found audience [{:?}] for spec [{:?}]", val, spec_name);
-                let audience_name = String::from(val.as_str().unwrap());
-                match stats.get(&audience_name) {
-                    Some(val) => {
-                        stats.insert(audience_name, val + num_of_endpoints);
-                    }
-                    None => {
-                        stats.insert(audience_name, num_of_endpoints);
-                    }
-                };
-            }
+/**
+ * Source, repo and blob id of a spec id (or of a stable id), a bare blob id being looked for in all the sources
+ */
+fn resolve_spec_id<'a>(
+    sources: &'a [CatalogSource],
+    id: &str,
+) -> Result<(&'a CatalogSource, Repository, Oid), CatalogError> {
+    //stable ids give the current revision of the spec
+    if Uuid::parse_str(id).is_ok() {
+        let (source, repo, path) = find_stable_id(sources, id)
+            .ok_or_else(|| not_found(format!("Unknown spec [{}]", id)))?;
+        let oid = get_head_tree(&repo)?
+            .get_path(Path::new(&path))
+            .map(|entry| entry.id())
+            .map_err(|why| git_failure(format!("Unable to get [{}] in HEAD - [{}]", path, why)))?;
+        return Ok((source, repo, oid));
+    }
+    let oid = get_oid(id)?;
+    let candidates: Vec<&CatalogSource> = match id.rfind(':') {
+        Some(idx) => match get_source(sources, &id[..idx]) {
+            Some(source) => vec![source],
             None => {
-                info!("no audience for spec [{:?}]", spec_name);
-                stats.insert(String::from("no audience"), num_of_endpoints);
+                return Err(not_found(format!(
+                    "Unknown source [{}] of spec [{}]",
+                    &id[..idx],
+                    id
+                )))
             }
-        };
+        },
+        None => sources.iter().collect(),
     };
+    for source in candidates {
+        match get_git_repo(&source.path) {
+            Ok(repo) => {
+                if repo.find_blob(oid).is_ok() {
+                    return Ok((source, repo, oid));
+                }
+            }
+            Err(why) => warn!(
+                "Unable to get git repo of source [{}] - reason [{}]",
+                source.name, why
+            ),
+        }
+    }
 
-    stats
+    Err(not_found(format!("Unable to get Blob [{}]", id)))
 }
 
-pub fn get_endpoints_num_per_subdomain(all_specs: &Vec<SpecItem>) -> HashMap<String, usize> {
-    let mut data: HashMap<String, usize> = HashMap::new();
-    for spec in all_specs {
-        debug!(
-            "get_endpoints_num_per_subdomain - parsing spec [{:?}]",
-            spec.path
-        );
-        let base_url = get_domain_from_spec(&spec.api_spec);
-        let num = spec.api_spec.paths.len();
+enum ParsedSpec {
+    OpenApi(Box<OpenAPI>, String),
+    AsyncApi(Box<AsyncAPI>),
+}
+
+fn parse_spec(content: &[u8]) -> Result<ParsedSpec, String> {
+    parse_spec_doc(load_spec(content)?)
+}
+
+fn load_spec(content: &[u8]) -> Result<serde_yaml::Value, String> {
+    serde_yaml::from_slice(content).map_err(|why| why.to_string())
+}
 
-        *data.entry(base_url.to_string()).or_insert(0) += num;
+fn parse_spec_doc(doc: serde_yaml::Value) -> Result<ParsedSpec, String> {
+    let (format, version, doc) = convert_spec_doc(doc)?;
+
+    match format.spec_type() {
+        SpecType::OpenApi => serde_yaml::from_value(doc)
+            .map(|openapi| ParsedSpec::OpenApi(Box::new(openapi), version))
+            .map_err(|why| why.to_string()),
+        SpecType::AsyncApi => serde_yaml::from_value(doc)
+            .map(|asyncapi| ParsedSpec::AsyncApi(Box::new(asyncapi)))
+            .map_err(|why| why.to_string()),
     }
+}
 
-    debug!("endpoints per subdomain [{:?}]", data);
+//swagger 2.0 and openapi 3.1 are converted into openapi 3.0, extensions (x-...) are kept
+fn convert_spec_doc(
+    doc: serde_yaml::Value,
+) -> Result<(SpecFormat, String, serde_yaml::Value), String> {
+    let (format, version) = match formats::get_spec_format(&doc) {
+        Some(val) => val,
+        None => {
+            return Err(String::from(
+                "neither a swagger, an openapi nor an asyncapi specification",
+            ))
+        }
+    };
+    debug!("Got spec format [{:?}] - version [{}]", format, version);
 
-    data
+    let doc = match format {
+        SpecFormat::Swagger2 => formats::convert_swagger_2(&doc),
+        SpecFormat::OpenApi31 => formats::convert_openapi_3_1(&doc),
+        SpecFormat::OpenApi30 | SpecFormat::AsyncApi2 => doc,
+    };
+
+    Ok((format, version, doc))
 }
 
-fn get_domain_from_spec(spec: &OpenAPI) -> &str {
-    let base_url = match &spec.servers.is_empty() {
-        true => "NA - servers attribute not specified",
-        false => {
-            //TODO can do better
-            //base_url could have the following form http://baseurl/v1/xva-management/xva
-            //will extract http://baseurl and keep the rest
-            lazy_static! {
-                static ref RE: Regex = Regex::new(r"(http[s]?://[a-z]*)(.*)").unwrap();
-            }
+fn parse_blob(repo: &Repository, blob: &Blob) -> Result<ParsedSpec, String> {
+    let doc = load_spec(blob.content())?;
+    if !resolver::has_external_refs(&doc) {
+        return parse_spec_doc(doc);
+    }
 
-            if let Some(cap) = RE.captures(&spec.servers[0].url) {
-                cap.get(2).unwrap().as_str()
-            } else {
-                &spec.servers[0].url
-            }
+    let tree = get_head_tree(repo).map_err(|why| why.to_string())?;
+    parse_blob_in_tree(repo, blob, tree)
+}
+
+//external refs are resolved against the given tree, e.g. the one of the commit the blob has been found in
+fn parse_blob_in_tree(repo: &Repository, blob: &Blob, tree: Tree) -> Result<ParsedSpec, String> {
+    let doc = load_spec(blob.content())?;
+    if !resolver::has_external_refs(&doc) {
+        return parse_spec_doc(doc);
+    }
+
+    //refs are relative to the spec file, so its location in the tree is needed
+    let doc = match find_blob_path(&tree, blob.id()) {
+        Some(spec_path) => resolve_external_refs(&mut Resolver::new(repo, tree), &spec_path, doc),
+        None => {
+            warn!(
+                "Unable to find Blob [{}] in tree [{}], external refs are not resolved",
+                blob.id(),
+                tree.id()
+            );
+            doc
         }
     };
 
-    base_url
+    parse_spec_doc(doc)
 }
 
-#[cfg(test)]
-mod tests {
+fn resolve_external_refs(
+    resolver: &mut Resolver,
+    spec_path: &str,
+    doc: serde_yaml::Value,
+) -> serde_yaml::Value {
+    if !resolver::has_external_refs(&doc) {
+        return doc;
+    }
 
-    #[test]
-    fn test_get_endpoints_num_per_subdomain_1() {
-        let mut specs = Vec::new();
-        let spec = "
-        openapi: 3.0.0
+    match resolver.bundle(spec_path, &doc) {
+        Ok(bundled) => bundled,
+        Err(why) => {
+            warn!(
+                "Unable to resolve external refs of [{}] - reason [{}]",
+                spec_path, why
+            );
+            doc
+        }
+    }
+}
+
+/**
+ * Get the spec with all its external refs resolved, as a single document (in its original format)
+ */
+pub fn get_bundled_spec(
+    sources: &[CatalogSource],
+    id: &str,
+) -> Result<serde_yaml::Value, CatalogError> {
+    let (_, repo, oid) = resolve_spec_id(sources, id)?;
+    let blob = find_blob(&repo, id, oid)?;
+    let doc = load_spec(blob.content()).map_err(|why| spec_failure(id, why))?;
+
+    let tree = get_head_tree(&repo)?;
+    let spec_path = match find_blob_path(&tree, oid) {
+        Some(spec_path) => spec_path,
+        None => return Err(not_found(format!("Unable to find Blob [{}] in HEAD", id))),
+    };
+
+    let bundled = Resolver::new(&repo, tree).bundle(&spec_path, &doc);
+    bundled.map_err(|why| spec_failure(id, why))
+}
+
+fn find_blob<'a>(repo: &'a Repository, id: &str, oid: Oid) -> Result<Blob<'a>, CatalogError> {
+    repo.find_blob(oid)
+        .map_err(|why| not_found(format!("Unable to get Blob [{}] - [{}]", id, why)))
+}
+
+//serde_json does not accept non string keys (e.g. response codes), whereas yaml does
+pub fn to_json(val: &serde_yaml::Value) -> serde_json::Value {
+    match val {
+        serde_yaml::Value::Null => serde_json::Value::Null,
+        serde_yaml::Value::Bool(val) => serde_json::Value::Bool(*val),
+        serde_yaml::Value::Number(val) => {
+            serde_json::to_value(val).unwrap_or(serde_json::Value::Null)
+        }
+        serde_yaml::Value::String(val) => serde_json::Value::String(val.clone()),
+        serde_yaml::Value::Sequence(val) => {
+            serde_json::Value::Array(val.iter().map(to_json).collect())
+        }
+        serde_yaml::Value::Mapping(val) => serde_json::Value::Object(
+            val.iter()
+                .map(|(key, val)| {
+                    let key = match key {
+                        serde_yaml::Value::String(key) => key.clone(),
+                        key => serde_yaml::to_string(key)
+                            .map(|key| key.trim_start_matches("---").trim().to_string())
+                            .unwrap_or_default(),
+                    };
+                    (key, to_json(val))
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn get_head_tree(repo: &Repository) -> Result<Tree<'_>, CatalogError> {
+    repo.head()
+        .and_then(|head| head.peel_to_tree())
+        .map_err(|why| git_failure(format!("Unable to get HEAD tree - [{}]", why)))
+}
+
+fn find_blob_path(tree: &Tree, oid: Oid) -> Option<String> {
+    let mut blob_path = None;
+    //walk is stopped as soon as the blob is found, the returned error is then expected
+    let _ = tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if entry.id() == oid {
+            blob_path = Some(format!("{}{}", root, entry.name().unwrap_or_default()));
+            TreeWalkResult::Abort
+        } else {
+            TreeWalkResult::Ok
+        }
+    });
+
+    blob_path
+}
+
+//blob of a spec, along with the file it is in and the tree to resolve its external refs against
+struct SpecBlob<'a> {
+    blob: Blob<'a>,
+    //path of the file in the catalog as listed by list_catalog, the repo path if the blob is not in HEAD
+    file_path: String,
+    //the id of the blob if it is not in HEAD
+    stable_id: String,
+    //path of the file in the tree, none if the blob is not in HEAD
+    spec_path: Option<String>,
+    tree: Option<Tree<'a>>,
+}
+
+impl<'a> SpecBlob<'a> {
+    fn index(self, repo: &Repository) -> Result<Arc<index::IndexedSpec>, CatalogError> {
+        let oid = self.blob.id();
+        let tree = match self.tree {
+            Some(tree) => tree,
+            None => get_head_tree(repo)?,
+        };
+        //a blob that is not in HEAD has its external refs resolved from the root of the tree
+        let spec_path = self.spec_path.unwrap_or_else(|| oid.to_string());
+        index::get(repo, &mut Resolver::new(repo, tree), &spec_path, oid)
+            .map_err(|why| spec_failure(&oid.to_string(), why.to_string()))?
+            .ok_or_else(|| spec_failure(&oid.to_string(), String::from("not a spec")))
+    }
+}
+
+/**
+ * Blob of the spec as of the given branch, tag or commit, i.e. the file the blob is in at HEAD taken from the ref
+ */
+fn get_spec_blob<'a>(
+    repo: &'a Repository,
+    source: &CatalogSource,
+    oid: Oid,
+    git_ref: Option<&str>,
+) -> Result<SpecBlob<'a>, CatalogError> {
+    let path = source.path.as_str();
+    let head_tree = get_head_tree(repo);
+    let spec_path = head_tree
+        .as_ref()
+        .ok()
+        .and_then(|tree| find_blob_path(tree, oid));
+    let stable_id = match (&spec_path, get_commit(repo, None)) {
+        (Some(spec_path), Ok(head)) => {
+            let origin_paths = get_origin_paths(repo, source, head.id());
+            get_stable_id(source, origin_paths.get(spec_path).unwrap_or(spec_path))
+        }
+        _ => get_spec_id(source, oid),
+    };
+    let to_file_path = |spec_path: &str| format!("{}/{}", path.trim_end_matches('/'), spec_path);
+
+    match git_ref {
+        None => Ok(SpecBlob {
+            blob: find_blob(repo, &oid.to_string(), oid)?,
+            file_path: spec_path
+                .as_deref()
+                .map(to_file_path)
+                .unwrap_or_else(|| path.to_string()),
+            stable_id: stable_id,
+            spec_path: spec_path,
+            tree: head_tree.ok(),
+        }),
+        Some(git_ref) => {
+            let spec_path = spec_path
+                .ok_or_else(|| not_found(format!("Unable to find Blob [{}] in HEAD", oid)))?;
+            let tree = get_tree(repo, Some(git_ref))?;
+            let blob = tree
+                .get_path(Path::new(&spec_path))
+                .and_then(|entry| entry.to_object(repo))
+                .and_then(|object| object.peel_to_blob())
+                .map_err(|why| {
+                    not_found(format!(
+                        "Unable to get [{}] at ref [{}] - [{}]",
+                        spec_path, git_ref, why
+                    ))
+                })?;
+            Ok(SpecBlob {
+                blob: blob,
+                file_path: to_file_path(&spec_path),
+                stable_id: stable_id,
+                spec_path: Some(spec_path),
+                tree: Some(tree),
+            })
+        }
+    }
+}
+
+//
+pub fn get_spec(sources: &[CatalogSource], id: &str) -> Vec<SpecItem> {
+    get_spec_at(sources, id, None)
+}
+
+/**
+ * The spec as of the given branch, tag or commit (its current revision if not set)
+ */
+pub fn get_spec_at(sources: &[CatalogSource], id: &str, git_ref: Option<&str>) -> Vec<SpecItem> {
+    let mut specs = Vec::new();
+    //get connection to git repo (should be cloned as prerequisite)
+    let spec_blob = resolve_spec_id(sources, id).and_then(|(source, repo, oid)| {
+        let spec_blob = get_spec_blob(&repo, source, oid, git_ref)?;
+        let (blob_id, file_path) = (spec_blob.blob.id(), spec_blob.file_path.clone());
+        let stable_id = spec_blob.stable_id.clone();
+        let indexed = spec_blob.index(&repo);
+        Ok((source, blob_id, file_path, stable_id, indexed))
+    });
+    match spec_blob {
+        Ok((source, oid, file_path, stable_id, indexed)) => {
+            //generate the OpenAPI
+            let indexed = indexed
+                .as_ref()
+                .map(|indexed| (&indexed.spec, &indexed.extensions));
+            if let Ok((spec @ ParsedSpec::OpenApi(openapi, spec_version), extensions)) = indexed {
+                let domain_match = get_domain(source, spec, extensions, &file_path);
+                //create the API Item and add it to the returned value
+                let spec = SpecItem {
+                    path: file_path,
+                    id: get_spec_id(source, oid),
+                    stable_id: stable_id,
+                    source: source.name.clone(),
+                    api_spec: *openapi.clone(),
+                    audience: extensions
+                        .get_audience()
+                        .unwrap_or_else(|| String::from("N/A")),
+                    domain_match: domain_match,
+                    spec_version: spec_version.clone(),
+                    extensions: extensions.clone(),
+                };
+                specs.push(spec);
+            } else {
+                warn!("Unable to parse spec [{}] of source [{}]", id, source.name);
+            }
+        }
+        Err(why) => warn!("Unable to get spec [{}] - reason [{}]", id, why),
+    }
+
+    specs
+}
+
+pub fn get_async_spec(sources: &[CatalogSource], id: &str) -> Vec<AsyncSpecItem> {
+    get_async_spec_at(sources, id, None)
+}
+
+pub fn get_async_spec_at(
+    sources: &[CatalogSource],
+    id: &str,
+    git_ref: Option<&str>,
+) -> Vec<AsyncSpecItem> {
+    let mut specs = Vec::new();
+    //get connection to git repo (should be cloned as prerequisite)
+    let spec_blob = resolve_spec_id(sources, id).and_then(|(source, repo, oid)| {
+        let spec_blob = get_spec_blob(&repo, source, oid, git_ref)?;
+        let (blob_id, file_path) = (spec_blob.blob.id(), spec_blob.file_path.clone());
+        let stable_id = spec_blob.stable_id.clone();
+        let indexed = spec_blob.index(&repo);
+        Ok((source, blob_id, file_path, stable_id, indexed))
+    });
+    match spec_blob {
+        Ok((source, oid, file_path, stable_id, indexed)) => {
+            let indexed = indexed
+                .as_ref()
+                .map(|indexed| (&indexed.spec, &indexed.extensions));
+            if let Ok((spec @ ParsedSpec::AsyncApi(asyncapi), extensions)) = indexed {
+                let domain_match = get_domain(source, spec, extensions, &file_path);
+                let spec = AsyncSpecItem {
+                    path: file_path,
+                    id: get_spec_id(source, oid),
+                    stable_id: stable_id,
+                    source: source.name.clone(),
+                    api_spec: *asyncapi.clone(),
+                    audience: extensions
+                        .get_audience()
+                        .unwrap_or_else(|| String::from("N/A")),
+                    domain_match: domain_match,
+                    extensions: extensions.clone(),
+                };
+                specs.push(spec);
+            } else {
+                warn!(
+                    "Unable to parse spec [{}] of source [{}] as asyncapi",
+                    id, source.name
+                );
+            }
+        }
+        Err(why) => warn!("Unable to get async spec [{}] - reason [{}]", id, why),
+    }
+
+    specs
+}
+
+/**
+ * Revisions of the spec with the given id (or stable id), the most recent first
+ */
+pub fn get_spec_history(
+    sources: &[CatalogSource],
+    id: &str,
+) -> Result<Vec<history::SpecRevision>, CatalogError> {
+    let (source, repo, path) = match find_stable_id(sources, id) {
+        Some(found) => found,
+        None => {
+            let (source, repo, oid) = resolve_spec_id(sources, id)?;
+            let path = find_blob_path(&get_head_tree(&repo)?, oid)
+                .ok_or_else(|| not_found(format!("Unable to find Blob [{}] in HEAD", id)))?;
+            (source, repo, path)
+        }
+    };
+    let head = get_commit(&repo, None)?.id();
+
+    history::get_history(&repo, source, head, &path).map_err(|why| {
+        git_failure(format!(
+            "Unable to get history of [{}] of source [{}] - [{}]",
+            path, source.name, why
+        ))
+    })
+}
+
+pub fn compare_spec_items(old: &SpecItem, new: &SpecItem) -> Vec<compare::Change> {
+    compare::compare(&old.api_spec, &new.api_spec)
+}
+
+/**
+ * Compare the spec with the given blob id to another revision, other_id being either the blob id of the other revision
+ * or the id of a commit, the same file being then taken from this commit.
+ */
+pub fn compare_specs(
+    sources: &[CatalogSource],
+    id: &str,
+    other_id: &str,
+) -> Result<Vec<compare::Change>, CatalogError> {
+    //both revisions are in the repo of the spec
+    let (_, repo, oid) = resolve_spec_id(sources, id)?;
+    let other_oid = get_oid(other_id)?;
+
+    let blob = find_blob(&repo, id, oid)?;
+    let old = parse_blob(&repo, &blob).map_err(|why| spec_failure(id, why))?;
+
+    let new = match repo.find_blob(other_oid) {
+        Ok(other_blob) => {
+            parse_blob(&repo, &other_blob).map_err(|why| spec_failure(other_id, why))?
+        }
+        Err(_) => {
+            let commit = repo.find_commit(other_oid).map_err(|why| {
+                not_found(format!(
+                    "[{}] is neither a Blob nor a Commit - [{}]",
+                    other_id, why
+                ))
+            })?;
+            let spec_path = match find_blob_path(&get_head_tree(&repo)?, oid) {
+                Some(spec_path) => spec_path,
+                None => return Err(not_found(format!("Unable to find Blob [{}] in HEAD", id))),
+            };
+            let tree = commit.tree().map_err(|why| git_failure(why.to_string()))?;
+            let other_blob = tree
+                .get_path(Path::new(&spec_path))
+                .and_then(|entry| entry.to_object(&repo))
+                .and_then(|object| object.peel_to_blob())
+                .map_err(|why| {
+                    not_found(format!(
+                        "Unable to get [{}] from Commit [{}] - [{}]",
+                        spec_path, other_id, why
+                    ))
+                })?;
+            parse_blob_in_tree(&repo, &other_blob, tree)
+                .map_err(|why| spec_failure(other_id, why))?
+        }
+    };
+
+    match (old, new) {
+        (ParsedSpec::OpenApi(old, _), ParsedSpec::OpenApi(new, _)) => {
+            let changes = compare::compare(&old, &new);
+            info!(
+                "Compared [{}] to [{}] - got [{}] changes, breaking [{}]",
+                id,
+                other_id,
+                changes.len(),
+                compare::is_breaking(&changes)
+            );
+            Ok(changes)
+        }
+        _ => Err(CatalogError::Unsupported {
+            message: String::from("Only openapi specifications can be compared"),
+        }),
+    }
+}
+
+/**
+ * Review items and breaking verdict between 2 revisions of a file, None if the file has been added or deleted.
+ * The verdict is None if the file is not an openapi spec (e.g. a fragment).
+ */
+pub fn review_spec_contents(
+    before: Option<&[u8]>,
+    after: Option<&[u8]>,
+) -> (Vec<review::ReviewItem>, Option<bool>) {
+    let before_doc = before.and_then(|content| load_spec(content).ok());
+    let after_doc = after.and_then(|content| load_spec(content).ok());
+    let items = review::diff_specs(before_doc.as_ref(), after_doc.as_ref());
+
+    let is_spec = |doc: &Option<serde_yaml::Value>| match doc {
+        Some(doc) => formats::get_spec_format(doc).is_some(),
+        None => false,
+    };
+    let breaking = match (&before_doc, &after_doc) {
+        (Some(before), Some(after)) => match (
+            parse_spec_doc(before.clone()),
+            parse_spec_doc(after.clone()),
+        ) {
+            (Ok(ParsedSpec::OpenApi(before, _)), Ok(ParsedSpec::OpenApi(after, _))) => {
+                Some(compare::is_breaking(&compare::compare(&before, &after)))
+            }
+            _ => None,
+        },
+        //a deleted spec removes all its operations
+        (Some(_), None) if is_spec(&before_doc) => Some(true),
+        (None, Some(_)) if is_spec(&after_doc) => Some(false),
+        _ => None,
+    };
+
+    (items, breaking)
+}
+
+//path of the spec relative to the dir of its source
+pub fn get_spec_short_path<'a>(sources: &[CatalogSource], spec: &'a SpecItem) -> &'a str {
+    get_short_path(get_source_dir(sources, &spec.source), spec.path.as_str())
+}
+
+pub fn get_async_spec_short_path<'a>(
+    sources: &[CatalogSource],
+    spec: &'a AsyncSpecItem,
+) -> &'a str {
+    get_short_path(get_source_dir(sources, &spec.source), spec.path.as_str())
+}
+
+fn get_source_dir<'a>(sources: &'a [CatalogSource], name: &str) -> &'a str {
+    get_source(sources, name)
+        .map(|source| source.dir.as_str())
+        .unwrap_or_default()
+}
+
+fn get_short_path<'a>(catalog_dir: &str, path: &'a str) -> &'a str {
+    path.strip_prefix(catalog_dir).unwrap_or(path)
+}
+
+//
+fn get_git_repo(path: &str) -> Result<Repository, git2::Error> {
+    let repo: Repository = Repository::open(path)?;
+    info!(
+        "Parsing yaml files from Git Repo [{}]",
+        path.to_string() + &"**/*.yaml".to_string()
+    );
+
+    Ok(repo)
+}
+
+/**
+ * Clone (if missing) or fast-forward the local repo of the source, returning the commit it is on
+ */
+pub fn refresh_git_repo(source: &CatalogSource) -> Result<Oid, git::GitError> {
+    let commit = git::refresh(source)?;
+    index_source(source, commit);
+
+    Ok(commit)
+}
+
+//parse the specs changed since the source has last been indexed, so that listing it only parses new blobs
+fn index_source(source: &CatalogSource, commit: Oid) {
+    let patterns = get_patterns(source);
+    let is_spec = |spec_path: &str| {
+        patterns
+            .iter()
+            .any(|pattern| pattern.matches_with(spec_path, PATTERN_OPTIONS))
+    };
+    match get_git_repo(&source.path)
+        .and_then(|repo| index::update(&repo, &source.path, commit, is_spec))
+    {
+        Ok(parsed) => info!(
+            "Indexed source [{}] at [{}] - [{}] changed specs",
+            source.name, commit, parsed
+        ),
+        Err(why) => warn!(
+            "Unable to index source [{}] - reason [{}]",
+            source.name, why
+        ),
+    }
+}
+
+/**
+ * Churn of the listed specs, i.e. commits and changed lines per spec, domain and audience, over each of the
+ * windows (in days) up to now. Specs of named sources are prefixed by their source, as their ids are.
+ */
+pub fn get_churn(
+    all_specs: &[SpecItem],
+    all_async_specs: &[AsyncSpecItem],
+    sources: &[CatalogSource],
+    now: chrono::DateTime<chrono::Utc>,
+    windows: &[i64],
+) -> Vec<churn::ChurnStats> {
+    let since = now - chrono::Duration::days(windows.iter().copied().max().unwrap_or_default());
+    let mut changes_per_source: HashMap<&str, HashMap<String, Vec<history::FileChange>>> =
+        HashMap::new();
+    for source in sources {
+        let changes = get_git_repo(&source.path)
+            .and_then(|repo| {
+                let head = repo.head()?.peel_to_commit()?.id();
+                history::get_file_changes_since(&repo, head, since)
+            })
+            .unwrap_or_else(|why| {
+                warn!(
+                    "Unable to get the churn of source [{}] - reason [{}]",
+                    source.name, why
+                );
+                HashMap::new()
+            });
+        changes_per_source
+            .entry(source.name.as_str())
+            .or_insert(changes);
+    }
+
+    let no_changes = Vec::new();
+    let mut specs: Vec<(&str, &str, &str, &str, &str)> = all_specs
+        .iter()
+        .map(|spec| {
+            (
+                spec.source.as_str(),
+                spec.path.as_str(),
+                get_spec_short_path(sources, spec),
+                spec.domain_match.domain.as_str(),
+                spec.audience.as_str(),
+            )
+        })
+        .collect();
+    specs.extend(all_async_specs.iter().map(|spec| {
+        (
+            spec.source.as_str(),
+            spec.path.as_str(),
+            get_async_spec_short_path(sources, spec),
+            spec.domain_match.domain.as_str(),
+            spec.audience.as_str(),
+        )
+    }));
+    let specs: Vec<churn::SpecChanges> = specs
+        .into_iter()
+        .map(|(source_name, path, short_path, domain, audience)| {
+            let source_path = get_source(sources, source_name)
+                .map(|source| source.path.as_str())
+                .unwrap_or_default();
+            let changes = changes_per_source
+                .get(source_name)
+                .and_then(|changes| changes.get(get_relative_path(source_path, path)))
+                .unwrap_or(&no_changes);
+            churn::SpecChanges {
+                name: match source_name {
+                    DEFAULT_SOURCE => String::from(short_path),
+                    _ => format!("{}:{}", source_name, short_path),
+                },
+                domain: domain,
+                audience: audience,
+                changes: changes,
+            }
+        })
+        .collect();
+
+    windows
+        .iter()
+        .map(|window_days| churn::get_churn_stats(&specs, now, *window_days))
+        .collect()
+}
+
+pub fn get_zally_ignore(all_specs: &Vec<SpecItem>) -> std::collections::HashMap<String, usize> {
+    let mut merged_stats = std::collections::HashMap::new();
+
+    for spec in all_specs.iter() {
+        let stats = get_zally_ignore_metrics(&spec.extensions, spec.path.as_str());
+
+        //some the maps
+        for (key, val) in stats.iter() {
+            match merged_stats.get(key) {
+                Some(known_val) => {
+                    merged_stats.insert(key.clone(), val + known_val);
+                }
+                None => {
+                    merged_stats.insert(key.clone(), *val);
+                }
+            }
+        }
+    }
+    merged_stats
+}
+
+fn get_zally_ignore_metrics(
+    extensions: &extensions::SpecExtensions,
+    spec_name: &str,
+) -> std::collections::HashMap<String, usize> {
+    debug!(
+        "get_zally_ignore_metrics is called for spec {:?}",
+        spec_name
+    );
+
+    let mut stats = std::collections::HashMap::new();
+    //get global zally-ignore, applying to all the paths
+    {
+        let rules = extensions.get_zally_ignore();
+        if rules.is_empty() {
+            info!("no global zally-ignore for spec {:?}", spec_name);
+        }
+        for rule in rules {
+            stats.insert(rule, extensions.paths.len());
+        }
+    }
+
+    //get zally-ignore per path
+    let mut stats_per_path: HashMap<String, usize> = std::collections::HashMap::new();
+    for path in extensions.paths.iter() {
+        let rules = path.get_zally_ignore();
+        if rules.is_empty() {
+            info!(
+                "no zally-ignore on paths for spec {:?} and path {:?}",
+                spec_name, path.path
+            );
+        }
+        for rule in rules {
+            *stats_per_path.entry(rule).or_insert(0) += 1;
+        }
+    }
+
+    //merge both maps
+    for stat in stats_per_path.iter() {
+        //check if stat already exist in global, if not add it to stats
+        if stats.contains_key(stat.0) {
+            debug!("stats {:?} already in global stats", stat.0);
+        } else {
+            stats.insert(stat.0.clone(), *stat.1);
+        }
+    }
+
+    stats
+}
+
+pub fn get_spec_zally_ignores(spec: &SpecItem) -> Vec<zally::ZallyIgnoreItem> {
+    //x-zally-ignore are read from the yaml text when the spec is indexed
+    match get_indexed_spec(spec) {
+        Some(indexed) => indexed.zally_ignores.clone(),
+        None => Vec::new(),
+    }
+}
+
+/**
+ * Time it took to parse each spec when it has been indexed, by spec id and in the order of the specs
+ */
+pub fn get_parse_times(
+    all_specs: &[SpecItem],
+    all_async_specs: &[AsyncSpecItem],
+) -> Vec<(String, std::time::Duration)> {
+    all_specs
+        .iter()
+        .map(|spec| &spec.id)
+        .chain(all_async_specs.iter().map(|spec| &spec.id))
+        .filter_map(|id| {
+            let indexed = get_oid(id).ok().and_then(index::get_indexed)?;
+            Some((id.clone(), indexed.parse_time))
+        })
+        .collect()
+}
+
+//specs are indexed as they are listed
+fn get_indexed_spec(spec: &SpecItem) -> Option<Arc<index::IndexedSpec>> {
+    let indexed = get_oid(&spec.id).ok().and_then(index::get_indexed);
+    if indexed.is_none() {
+        warn!("Spec [{}] - [{}] is not indexed", spec.id, spec.path);
+    }
+    indexed
+}
+
+pub fn check_zally_ignores(
+    all_specs: &Vec<SpecItem>,
+    sources: &[CatalogSource],
+    waivers: &[super::repo_waivers::WaiverItem],
+) -> Vec<zally::IgnoreCheck> {
+    let now = chrono::Utc::now();
+    all_specs
+        .iter()
+        .flat_map(|spec| {
+            let spec_path = get_spec_short_path(sources, spec);
+            get_spec_zally_ignores(spec).into_iter().map(move |ignore| {
+                zally::check_ignore(&spec.stable_id, spec_path, ignore, waivers, now)
+            })
+        })
+        .collect()
+}
+
+pub fn lint_spec(spec: &SpecItem) -> Vec<lint::Violation> {
+    match get_indexed_spec(spec) {
+        Some(indexed) => {
+            debug!(
+                "Got [{}] lint violations for spec [{:?}]",
+                indexed.lint_violations.len(),
+                spec.path
+            );
+            indexed.lint_violations.clone()
+        }
+        None => Vec::new(),
+    }
+}
+
+pub fn lint_spec_by_id(
+    sources: &[CatalogSource],
+    id: &str,
+) -> Result<Vec<lint::Violation>, CatalogError> {
+    let (_, repo, oid) = resolve_spec_id(sources, id)?;
+    let blob = find_blob(&repo, id, oid)?;
+
+    let (format, _, doc) = load_spec(blob.content())
+        .and_then(convert_spec_doc)
+        .map_err(|why| spec_failure(id, why))?;
+    match format.spec_type() {
+        SpecType::OpenApi => Ok(lint::lint(&doc)),
+        SpecType::AsyncApi => Err(CatalogError::Unsupported {
+            message: String::from("Only openapi specifications can be linted"),
+        }),
+    }
+}
+
+//# of violations per rule
+pub fn get_lint_violations(all_specs: &Vec<SpecItem>) -> HashMap<String, usize> {
+    let mut stats: HashMap<String, usize> = HashMap::new();
+    for spec in all_specs.iter() {
+        for violation in lint_spec(spec) {
+            *stats.entry(violation.rule).or_insert(0) += 1;
+        }
+    }
+    debug!("lint violations per rule [{:?}]", stats);
+
+    stats
+}
+
+pub fn get_endpoints_num_per_audience(
+    all_specs: &Vec<SpecItem>,
+) -> std::collections::HashMap<String, usize> {
+    let mut merged_stats = std::collections::HashMap::new();
+
+    for spec in all_specs.iter() {
+        let stats = get_endpoints_num_per_audience_metrics(&spec.extensions, spec.path.as_str());
+
+        //sum the maps
+        for (key, val) in stats.iter() {
+            match merged_stats.get(key) {
+                Some(known_val) => {
+                    merged_stats.insert(key.clone(), val + known_val);
+                }
+                None => {
+                    merged_stats.insert(key.clone(), *val);
+                }
+            }
+        }
+    }
+    merged_stats
+}
+
+fn get_endpoints_num_per_audience_metrics(
+    extensions: &extensions::SpecExtensions,
+    spec_name: &str,
+) -> std::collections::HashMap<String, usize> {
+    debug!(
+        "get_endpoints_num_per_audience_metrics is called for spec {:?}",
+        spec_name
+    );
+
+    let num_of_endpoints = extensions.paths.len();
+
+    let mut stats = std::collections::HashMap::new();
+    match extensions.get_audience() {
+        Some(audience_name) => {
+            info!(
+                "found audience [{:?}] for spec [{:?}]",
+                audience_name, spec_name
+            );
+            stats.insert(audience_name, num_of_endpoints);
+        }
+        None => {
+            info!("no audience for spec [{:?}]", spec_name);
+            stats.insert(String::from("no audience"), num_of_endpoints);
+        }
+    };
+
+    stats
+}
+
+pub fn get_endpoints_num_per_subdomain(
+    all_specs: &[SpecItem],
+    all_async_specs: &[AsyncSpecItem],
+) -> HashMap<String, usize> {
+    let mut data: HashMap<String, usize> = HashMap::new();
+    for spec in all_specs {
+        debug!(
+            "get_endpoints_num_per_subdomain - parsing spec [{:?}]",
+            spec.path
+        );
+        let num = spec.api_spec.paths.len();
+
+        *data.entry(spec.domain_match.domain.clone()).or_insert(0) += num;
+    }
+    //channels are the asyncapi counterpart of paths
+    for spec in all_async_specs {
+        debug!(
+            "get_endpoints_num_per_subdomain - parsing asyncapi spec [{:?}]",
+            spec.path
+        );
+        let num = spec.api_spec.channels_num();
+
+        *data.entry(spec.domain_match.domain.clone()).or_insert(0) += num;
+    }
+
+    debug!("endpoints per subdomain [{:?}]", data);
+
+    data
+}
+
+/**
+ * Domain of the spec according to the rules of its source, along with the rule that matched.
+ * Catalog paths are relative to the dir of the source, domains being prefixed by the one of the source.
+ */
+fn get_domain(
+    source: &CatalogSource,
+    spec: &ParsedSpec,
+    extensions: &extensions::SpecExtensions,
+    file_path: &str,
+) -> domain_rules::DomainMatch {
+    let (server_url, title) = match spec {
+        ParsedSpec::OpenApi(openapi, _) => (
+            openapi.servers.first().map(|server| server.url.as_str()),
+            openapi.info.title.as_str(),
+        ),
+        ParsedSpec::AsyncApi(asyncapi) => (
+            asyncapi
+                .servers
+                .values()
+                .next()
+                .map(|server| server.url.as_str()),
+            asyncapi.info.title.as_str(),
+        ),
+    };
+    let domain_source = domain_rules::DomainSource {
+        server_url: server_url,
+        extensions: extensions,
+        catalog_path: get_relative_path(&source.dir, file_path),
+        title: title,
+    };
+    let rules = domain_rules::get_rules(source.domain_rules_path.as_deref());
+
+    prefix_domain_match(source, domain_rules::resolve(&rules, &domain_source))
+}
+
+//specs without domain are left as is so that they can still be told apart
+fn prefix_domain_match(
+    source: &CatalogSource,
+    domain_match: domain_rules::DomainMatch,
+) -> domain_rules::DomainMatch {
+    match &source.domain_prefix {
+        Some(prefix) if domain_match.rule.is_some() => domain_rules::DomainMatch {
+            domain: format!("{}{}", prefix, domain_match.domain),
+            explanation: format!("{}, prefixed by [{}]", domain_match.explanation, prefix),
+            ..domain_match
+        },
+        _ => domain_match,
+    }
+}
+
+//path of a file relative to a dir, the path as is if not in the dir
+fn get_relative_path<'a>(dir: &str, path: &'a str) -> &'a str {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .trim_start_matches('/')
+}
+
+#[cfg(test)]
+mod tests {
+
+    fn get_extensions(spec: &str) -> super::extensions::SpecExtensions {
+        super::extensions::get_extensions(&serde_yaml::from_str(spec).unwrap())
+    }
+
+    //domain of a spec of the default source, derived with the default rules
+    fn get_domain_match(spec: &str) -> super::domain_rules::DomainMatch {
+        let source = super::CatalogSource {
+            name: String::from(super::DEFAULT_SOURCE),
+            path: String::new(),
+            url: None,
+            credentials: None,
+            dir: String::new(),
+            patterns: Vec::new(),
+            branch: None,
+            domain_prefix: None,
+            domain_rules_path: None,
+        };
+        let extensions = get_extensions(spec);
+        let spec = super::parse_spec(spec.as_bytes()).unwrap();
+
+        super::get_domain(&source, &spec, &extensions, "spec.yaml")
+    }
+
+    #[test]
+    fn test_get_endpoints_num_per_subdomain_1() {
+        let mut specs = Vec::new();
+        let spec = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        servers: 
+          - url: /v1/a/b
+        paths:
+          /resource_1:
+            get:
+              responses:
+                '206':
+                  description: Partial Content
+        ";
+
+        let spec_item = super::SpecItem {
+            path: String::from("std::string::String"),
+            id: String::from("std::string::String"),
+            stable_id: String::from("std::string::String"),
+            source: String::from(super::DEFAULT_SOURCE),
+            api_spec: serde_yaml::from_str(spec).unwrap(),
+            audience: String::from("std::string::String"),
+            domain_match: get_domain_match(spec),
+            spec_version: String::from("3.0.0"),
+            extensions: super::extensions::SpecExtensions::default(),
+        };
+
+        specs.push(spec_item);
+
+        let spec = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        servers: 
+          - url: /v1/a/c
+        paths:
+          /resource_1:
+            get:
+              responses:
+                '206':
+                  description: Partial Content
+                  /resource_1:
+            post:
+              responses:
+                '206':
+                  description: Partial Content
+        ";
+
+        let spec_item = super::SpecItem {
+            path: String::from("std::string::String"),
+            id: String::from("std::string::String"),
+            stable_id: String::from("std::string::String"),
+            source: String::from(super::DEFAULT_SOURCE),
+            api_spec: serde_yaml::from_str(spec).unwrap(),
+            audience: String::from("std::string::String"),
+            domain_match: get_domain_match(spec),
+            spec_version: String::from("3.0.0"),
+            extensions: super::extensions::SpecExtensions::default(),
+        };
+
+        specs.push(spec_item);
+
+        let spec = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        paths:
+          /resource_1:
+            get:
+              responses:
+                '206':
+                  description: Partial Content
+        ";
+
+        let spec_item = super::SpecItem {
+            path: String::from("std::string::String"),
+            id: String::from("std::string::String"),
+            stable_id: String::from("std::string::String"),
+            source: String::from(super::DEFAULT_SOURCE),
+            api_spec: serde_yaml::from_str(spec).unwrap(),
+            audience: String::from("std::string::String"),
+            domain_match: get_domain_match(spec),
+            spec_version: String::from("3.0.0"),
+            extensions: super::extensions::SpecExtensions::default(),
+        };
+
+        specs.push(spec_item);
+
+        let data = super::get_endpoints_num_per_subdomain(&specs, &Vec::new());
+
+        assert_eq!(data.get("/v1/a/c").unwrap(), &1usize);
+        assert_eq!(data.get("/v1/a/b").unwrap(), &1usize);
+        assert_eq!(data.get(super::domain_rules::NO_DOMAIN).unwrap(), &1usize);
+    }
+
+    #[test]
+    fn test_get_zally_ignore_metrics_1() {
+        let spec = "
+        openapi: \"3.0.0\"
+        info:
+          version: 1.0.0
+          title: an API ...
+        x-zally-ignore:
+          - 134
+          - 120 # Rest maturity evolving
+        
+        paths:
+          /v1/a/b:
+            get:
+              description: get ...
+              responses:
+                '200':
+                  description: returns...
+
+          /v2/a/b:
+            x-zally-ignore:
+              - 164
+            get:
+              description: get ...
+              responses:
+                '200':
+                  description: returns...
+                    
+          /a/b:
+            x-zally-ignore:
+              - 164 # Rest maturity evolving
+              - 134
+            post:
+              parameters:
+                - name: chunk
+                  in: query
+                  required: true
+                  schema:
+                    type: integer
+                    format: int32
+                    minimum: 1
+              responses:
+                200:
+                  description: ...     
+        ";
+
+        let results = super::get_zally_ignore_metrics(&get_extensions(spec), "name");
+
+        println!("*** results : {:?}", results);
+
+        assert_eq!(results.get("134").unwrap(), &3usize);
+        assert_eq!(results.get("120").unwrap(), &3usize);
+        assert_eq!(results.get("164").unwrap(), &2usize);
+    }
+
+    #[test]
+    fn test_get_zally_ignores_rationale() {
+        let spec = "
+        openapi: \"3.0.0\"
+        info:
+          version: 1.0.0
+          title: an API ...
+        x-zally-ignore:
+          - 134
+          - 120 # Rest maturity evolving
+        paths:
+          /a/b:
+            x-zally-ignore: [164, 'M10'] # legacy # clients
+            get:
+              x-zally-ignore:
+                - \"215 # not a comment\"
+              responses:
+                '200':
+                  description: returns...
+        ";
+
+        let results = super::zally::get_ignores(spec).unwrap();
+
+        let rationales: Vec<(&str, Option<&str>)> = results
+            .iter()
+            .map(|val| (val.rule.as_str(), val.rationale.as_deref()))
+            .collect();
+        assert_eq!(
+            rationales,
+            vec![
+                ("134", None),
+                ("120", Some("Rest maturity evolving")),
+                ("164", Some("legacy # clients")),
+                ("M10", Some("legacy # clients")),
+                ("215 # not a comment", None),
+            ]
+        );
+        assert_eq!(results[1].path, None);
+        assert_eq!(results[2].path, Some(String::from("/a/b")));
+        assert_eq!(results[2].method, None);
+        assert_eq!(results[4].method, Some(String::from("GET")));
+    }
+
+    #[test]
+    fn test_check_zally_ignores_waivers() {
+        let spec = "
+        openapi: \"3.0.0\"
+        info:
+          version: 1.0.0
+          title: an API ...
+        x-zally-ignore:
+          - 134
+        paths:
+          /a/b:
+            x-zally-ignore:
+              - 120
+              - 164
+              - 215
+        ";
+        let now = chrono::Utc::now();
+        let waiver =
+            |path: Option<&str>, rule: &str, days: i64| super::super::repo_waivers::WaiverItem {
+                id: uuid::Uuid::new_v4(),
+                spec: String::from("spec-id"),
+                path: path.map(String::from),
+                rule: String::from(rule),
+                approver: String::from("owner"),
+                expiry_date_time: now + chrono::Duration::days(days),
+                start_date_time: now,
+                revoked_date_time: None,
+            };
+        let mut revoked = waiver(None, "215", 10);
+        revoked.revoked_date_time = Some(now);
+        //same path, in another source
+        let mut other_spec = waiver(None, "164", 10);
+        other_spec.spec = String::from("other-spec-id");
+        let waivers = vec![
+            waiver(None, "134", 10),
+            waiver(Some("/a/b"), "120", -1),
+            waiver(Some("/c"), "164", 10),
+            other_spec,
+            revoked,
+        ];
+
+        let statuses: Vec<super::zally::WaiverStatus> = super::zally::get_ignores(spec)
+            .unwrap()
+            .into_iter()
+            .map(|ignore| {
+                super::zally::check_ignore("spec-id", "domain/spec.yaml", ignore, &waivers, now)
+                    .status
+            })
+            .collect();
+
+        assert_eq!(
+            statuses,
+            vec![
+                super::zally::WaiverStatus::WAIVED,
+                super::zally::WaiverStatus::EXPIRED,
+                super::zally::WaiverStatus::UNREGISTERED,
+                super::zally::WaiverStatus::UNREGISTERED,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_extensions() {
+        let spec = "
+        openapi: 3.0.0
+        x-zally-ignore: [134, M10]
+        info:
+          version: 1.0.0
+          title: sample
+          x-audience: company-internal
+          x-api-id: d0184f38-b98d-11e7-9c56-68f728c1ba70
+        paths:
+          /pets:
+            x-has-authority: read:pets
+            get:
+              x-custom:
+                owner: team-a
+              responses:
+                '200':
+                  description: ok
+            delete:
+              x-has-authority: delete:pets
+              responses:
+                '204':
+                  description: deleted
+          /stores:
+            get:
+              responses:
+                '200':
+                  description: ok
+        ";
+
+        let extensions = get_extensions(spec);
+
+        assert_eq!(
+            extensions.get_audience(),
+            Some(String::from("company-internal"))
+        );
+        assert_eq!(extensions.get_zally_ignore(), vec!["134", "M10"]);
+        assert_eq!(extensions.paths.len(), 2);
+        assert_eq!(
+            extensions.get_has_authority("/pets", "GET"),
+            Some(String::from("read:pets"))
+        );
+        assert_eq!(
+            extensions.get_has_authority("/pets", "DELETE"),
+            Some(String::from("delete:pets"))
+        );
+        assert_eq!(extensions.get_has_authority("/stores", "GET"), None);
+        assert_eq!(
+            extensions
+                .get_operation("/pets", "get")
+                .and_then(|operation| operation.extensions.get("x-custom")),
+            Some(&serde_json::json!({"owner": "team-a"}))
+        );
+        assert!(extensions.info.contains_key("x-api-id"));
+        assert!(!extensions.info.contains_key("title"));
+    }
+
+    #[test]
+    fn test_domain_rules() {
+        let rules: Vec<super::domain_rules::DomainRule> = serde_yaml::from_str(
+            "
+            - type: extension
+              name: x-domain
+            - type: server_url
+              regex: \"^(?:[a-z]+://[^/]*)?(?P<domain>/[^?#]*)\"
+            - type: catalog_path
+            - type: title_prefix
+              separator: \" - \"
+            ",
+        )
+        .unwrap();
+        let extensions = get_extensions(
+            "
+            info:
+              x-domain: /settlement/payments
+            ",
+        );
+        let no_extensions = super::extensions::SpecExtensions::default();
+        let resolve = |server_url, extensions, catalog_path, title| {
+            let source = super::domain_rules::DomainSource {
+                server_url: server_url,
+                extensions: extensions,
+                catalog_path: catalog_path,
+                title: title,
+            };
+            super::domain_rules::resolve(&rules, &source)
+        };
+
+        let domain = resolve(Some("https://api.example.com"), &extensions, "a.yaml", "");
+        assert_eq!(domain.domain, "/settlement/payments");
+        assert_eq!(domain.rule, Some(1));
+
+        //dots, port and digits in the host
+        let domain = resolve(
+            Some("https://api.v2.example.com:8443/v1/market-risk/scenarios"),
+            &no_extensions,
+            "a.yaml",
+            "",
+        );
+        assert_eq!(domain.domain, "/v1/market-risk/scenarios");
+        assert_eq!(domain.rule, Some(2));
+
+        let domain = resolve(None, &no_extensions, "trading/orders/spec.yaml", "");
+        assert_eq!(domain.domain, "/trading/orders");
+        assert_eq!(domain.rule, Some(3));
+
+        let domain = resolve(None, &no_extensions, "spec.yaml", "Trading - Orders API");
+        assert_eq!(domain.domain, "Trading");
+        assert_eq!(domain.rule, Some(4));
+
+        let domain = resolve(None, &no_extensions, "spec.yaml", "Orders API");
+        assert_eq!(domain.domain, super::domain_rules::NO_DOMAIN);
+        assert_eq!(domain.rule, None);
+        assert!(domain
+            .explanation
+            .contains("servers attribute not specified"));
+
+        //regexes are compiled when the rules are loaded
+        let invalid: Result<Vec<super::domain_rules::DomainRule>, _> =
+            serde_yaml::from_str("- type: server_url\n  regex: \"(?P<domain>/[^?#]*\"");
+        assert!(invalid.unwrap_err().to_string().contains("invalid regex"));
+    }
+
+    #[test]
+    fn test_get_zally_ignore_metrics_2() {
+        let spec = "
+        openapi: \"3.0.0\"
+        info:
+          version: 1.0.0
+          title: an API ...
+        
+        paths:
+          /v1/a/b:
+            get:
+              description: get ...
+              responses:
+                '200':
+                  description: returns...
+                    
+          /a/b:
+            x-zally-ignore:
+              - M10
+              - 164 
+            post:
+              parameters:
+                - name: chunk
+                  in: query
+                  required: true
+                  schema:
+                    type: integer
+                    format: int32
+                    minimum: 1
+              responses:
+                200:
+                  description: ...       
+        ";
+
+        let results = super::get_zally_ignore_metrics(&get_extensions(spec), "name");
+
+        println!("*** results : {:?}", results);
+
+        assert_eq!(results.get("164").unwrap(), &1usize);
+        assert_eq!(results.get("M10").unwrap(), &1usize);
+    }
+
+    #[test]
+    fn test_get_endpoints_num_per_audience_ignore_metrics_1() {
+        let spec = "
+        openapi: \"3.0.0\"
+        info:
+          version: 1.0.0
+          title: an API ...
+          x-audience: an audience
+        
+        paths:
+          /v1/a/b:
+            get:
+              description: get ...
+              responses:
+                '200':
+                  description: returns...
+
+          /v2/a/b:
+            get:
+              description: get ...
+              responses:
+                '200':
+                  description: returns...  
+        ";
+
+        let results = super::get_endpoints_num_per_audience_metrics(&get_extensions(spec), "name");
+
+        assert_eq!(results.get("an audience").unwrap(), &2usize);
+    }
+
+    #[test]
+    fn test_get_async_spec_endpoints_num_1() {
+        let spec = "
+        asyncapi: 2.0.0
+        info:
+          version: 1.0.0
+          title: sample events
+        servers:
+          production:
+            url: https://broker/v1/a/d
+            protocol: kafka
+        channels:
+          user/signedup:
+            subscribe:
+              message:
+                oneOf:
+                  - name: UserSignedUp
+                  - name: UserSignedUpV2
+          user/deleted:
+            publish:
+              message:
+                name: UserDeleted
+            subscribe:
+              message:
+                $ref: '#/components/messages/UserDeleted'
+        ";
+
+        let domain_match = get_domain_match(spec);
+        let spec = match super::parse_spec(spec.as_bytes()) {
+            Ok(super::ParsedSpec::AsyncApi(spec)) => spec,
+            _ => panic!("spec should have been parsed as asyncapi"),
+        };
+
+        assert_eq!(spec.channels_num(), 2usize);
+        assert_eq!(spec.operations_num(), 3usize);
+        assert_eq!(spec.messages_num(), 4usize);
+
+        let spec_item = super::AsyncSpecItem {
+            path: String::from("std::string::String"),
+            id: String::from("std::string::String"),
+            stable_id: String::from("std::string::String"),
+            source: String::from(super::DEFAULT_SOURCE),
+            api_spec: *spec,
+            audience: String::from("std::string::String"),
+            domain_match: domain_match,
+            extensions: super::extensions::SpecExtensions::default(),
+        };
+
+        let data = super::get_endpoints_num_per_subdomain(&Vec::new(), &[spec_item]);
+
+        assert_eq!(data.get("/v1/a/d").unwrap(), &2usize);
+    }
+
+    #[test]
+    fn test_parse_spec_type_1() {
+        let spec = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        paths: {}
+        ";
+        assert!(matches!(
+            super::parse_spec(spec.as_bytes()),
+            Ok(super::ParsedSpec::OpenApi(_, _))
+        ));
+
+        let not_a_spec = "
+        common:
+          errors: []
+        ";
+        assert!(super::parse_spec(not_a_spec.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_parse_spec_swagger_2() {
+        let spec = "
+        swagger: '2.0'
+        info:
+          version: 1.0.0
+          title: sample
+        host: petstore.swagger.io
+        basePath: /v1/a/b
+        schemes:
+          - https
+        paths:
+          /pets:
+            post:
+              operationId: createPet
+              parameters:
+                - name: pet
+                  in: body
+                  required: true
+                  schema:
+                    $ref: '#/definitions/Pet'
+                - name: dry-run
+                  in: query
+                  type: boolean
+              responses:
+                '201':
+                  description: created
+                  schema:
+                    $ref: '#/definitions/Pet'
+        definitions:
+          Pet:
+            type: object
+            discriminator: petType
+            properties:
+              petType:
+                type: string
+        ";
+
+        let (openapi, spec_version) = match super::parse_spec(spec.as_bytes()) {
+            Ok(super::ParsedSpec::OpenApi(openapi, spec_version)) => (openapi, spec_version),
+            _ => panic!("spec should have been converted into openapi"),
+        };
+
+        assert_eq!(spec_version, "2.0");
+        assert_eq!(openapi.servers[0].url, "https://petstore.swagger.io/v1/a/b");
+        assert!(openapi.components.unwrap().schemas.contains_key("Pet"));
+
+        let operation = match openapi.paths.get("/pets").unwrap() {
+            openapiv3::ReferenceOr::Item(path) => path.post.clone().unwrap(),
+            _ => panic!("path should be an item"),
+        };
+        assert_eq!(operation.parameters.len(), 1);
+        assert!(operation.request_body.is_some());
+    }
+
+    #[test]
+    fn test_parse_spec_openapi_3_1() {
+        let spec = "
+        openapi: 3.1.0
+        info:
+          version: 1.0.0
+          title: sample
+        webhooks: {}
+        components:
+          schemas:
+            Pet:
+              type: object
+              properties:
+                name:
+                  type: [string, 'null']
+                age:
+                  type: integer
+                  exclusiveMinimum: 0
+                  examples: [1, 2]
+        ";
+
+        let (openapi, spec_version) = match super::parse_spec(spec.as_bytes()) {
+            Ok(super::ParsedSpec::OpenApi(openapi, spec_version)) => (openapi, spec_version),
+            _ => panic!("spec should have been converted into openapi"),
+        };
+
+        assert_eq!(spec_version, "3.1.0");
+        assert!(openapi.paths.is_empty());
+        assert!(openapi.components.unwrap().schemas.contains_key("Pet"));
+    }
+
+    //commit the given files in a fresh repo, as refs are resolved against the HEAD tree
+    fn init_git_repo(name: &str, files: &[(&str, &str)]) -> git2::Repository {
+        //the module is compiled several times, hence tests run concurrently
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-{}-{}-{}",
+            name,
+            module_path!().replace("::", "-"),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = git2::Repository::init(&dir).unwrap();
+        for (path, content) in files {
+            let file = dir.join(path);
+            std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+            std::fs::write(&file, content).unwrap();
+        }
+        {
+            let mut index = repo.index().unwrap();
+            index
+                .add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None)
+                .unwrap();
+            let tree_id = index.write_tree().unwrap();
+            let tree = repo.find_tree(tree_id).unwrap();
+            let signature = git2::Signature::now("test", "test@test.com").unwrap();
+            repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+                .unwrap();
+        }
+
+        repo
+    }
+
+    #[test]
+    fn test_bundle_external_refs_1() {
+        let spec = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        paths:
+          /pets:
+            get:
+              parameters:
+                - $ref: '../common/parameters.yaml#/limit'
+              responses:
+                '200':
+                  description: ok
+                  content:
+                    application/json:
+                      schema:
+                        $ref: '#/components/schemas/Pet'
+                default:
+                  description: error
+                  content:
+                    application/json:
+                      schema:
+                        $ref: '../common/errors.yaml#/components/schemas/Problem'
+        components:
+          schemas:
+            Pet:
+              type: object
+        ";
+        let errors = "
+        components:
+          schemas:
+            Problem:
+              type: object
+              properties:
+                cause:
+                  $ref: '#/components/schemas/Problem'
+        ";
+        let parameters = "
+        limit:
+          name: limit
+          in: query
+          schema:
+            type: integer
+        ";
+        let repo = init_git_repo(
+            "bundle",
+            &[
+                ("domain/pets.yaml", spec),
+                ("common/errors.yaml", errors),
+                ("common/parameters.yaml", parameters),
+            ],
+        );
+
+        let doc: serde_yaml::Value = serde_yaml::from_str(spec).unwrap();
+        assert!(super::resolver::has_external_refs(&doc));
+
+        let tree = super::get_head_tree(&repo).unwrap();
+        let bundled = super::Resolver::new(&repo, tree)
+            .bundle("domain/pets.yaml", &doc)
+            .unwrap();
+        assert!(!super::resolver::has_external_refs(&bundled));
+
+        //named component is hoisted, including its recursive ref
+        let problem =
+            super::resolver::get_pointer(&bundled, "/components/schemas/Problem").unwrap();
+        assert_eq!(
+            problem["properties"]["cause"]["$ref"].as_str(),
+            Some("#/components/schemas/Problem")
+        );
+        //anything else is inlined
+        let parameter =
+            super::resolver::get_pointer(&bundled, "/paths/~1pets/get/parameters/0").unwrap();
+        assert_eq!(parameter["name"].as_str(), Some("limit"));
+
+        match super::parse_spec_doc(bundled) {
+            Ok(super::ParsedSpec::OpenApi(openapi, _)) => {
+                assert_eq!(openapi.components.unwrap().schemas.len(), 2)
+            }
+            _ => panic!("bundled spec should be an openapi"),
+        }
+    }
+
+    #[test]
+    fn test_bundle_external_refs_cycle() {
+        let spec = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        paths:
+          /pets:
+            $ref: 'paths.yaml#/pets'
+        ";
+        let paths = "
+        pets:
+          $ref: '#/others'
+        others:
+          $ref: '#/pets'
+        ";
+        let repo = init_git_repo("cycle", &[("pets.yaml", spec), ("paths.yaml", paths)]);
+
+        let doc: serde_yaml::Value = serde_yaml::from_str(spec).unwrap();
+        let tree = super::get_head_tree(&repo).unwrap();
+        let bundled = super::Resolver::new(&repo, tree).bundle("pets.yaml", &doc);
+
+        assert!(bundled.unwrap_err().starts_with("Cycle detected"));
+    }
+
+    fn get_source(name: &str, repo: &git2::Repository) -> super::CatalogSource {
+        let path = repo.workdir().unwrap().to_string_lossy().to_string();
+        super::CatalogSource {
+            name: String::from(name),
+            dir: format!("{}catalog/", path),
+            path: path,
+            url: None,
+            credentials: None,
+            patterns: vec![String::from("catalog/**/*.yaml")],
+            branch: None,
+            domain_prefix: None,
+            domain_rules_path: None,
+        }
+    }
+
+    #[test]
+    fn test_list_catalog_sources() {
+        let spec = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        servers:
+          - url: /v1/pets
+        paths:
+          /pets:
+            get:
+              responses:
+                '200':
+                  description: ok
+        ";
+        //same file in both repos, hence the same blob id
+        let default_repo = init_git_repo("source-default", &[("catalog/pets.yaml", spec)]);
+        let trading_repo = init_git_repo(
+            "source-trading",
+            &[("catalog/pets.yaml", spec), ("other/pets.yaml", spec)],
+        );
+        let mut trading = get_source("trading", &trading_repo);
+        trading.domain_prefix = Some(String::from("/trading"));
+        //rules of the source, rather than the default ones
+        let rules_path = trading_repo.workdir().unwrap().join("domain-rules.yaml");
+        std::fs::write(
+            &rules_path,
+            "- type: server_url\n  regex: \"^/v1(?P<domain>/.*)\"\n",
+        )
+        .unwrap();
+        trading.domain_rules_path = Some(rules_path.to_string_lossy().to_string());
+        let sources = vec![get_source(super::DEFAULT_SOURCE, &default_repo), trading];
+
+        let specs = super::list_specs(&sources);
+        assert_eq!(specs.len(), 2);
+        let oid = specs[0].id.clone();
+        assert_eq!(specs[0].source, super::DEFAULT_SOURCE);
+        assert_eq!(specs[0].domain_match.domain, "/v1/pets");
+        assert_eq!(specs[1].source, "trading");
+        assert_eq!(specs[1].id, format!("trading:{}", oid));
+        assert_eq!(specs[1].domain_match.domain, "/trading/pets");
+        assert!(specs[1]
+            .domain_match
+            .explanation
+            .ends_with("prefixed by [/trading]"));
+        assert_eq!(super::get_spec_short_path(&sources, &specs[1]), "pets.yaml");
+
+        //ids are resolved against their source, bare ones against the first source having the blob
+        let spec = super::get_spec(&sources, &specs[1].id).pop().unwrap();
+        assert_eq!(spec.source, "trading");
+        assert!(spec.path.starts_with(&sources[1].path));
+        let spec = super::get_spec(&sources, &oid).pop().unwrap();
+        assert_eq!(spec.source, super::DEFAULT_SOURCE);
+        assert!(super::get_spec(&sources, &format!("unknown:{}", oid)).is_empty());
+        assert_eq!(spec.domain_match, specs[0].domain_match);
+    }
+
+    //commit a file on master of a repo, on top of the current master if any
+    fn commit_file(repo: &git2::Repository, path: &str, content: &str) -> git2::Oid {
+        commit_file_at(repo, path, content, chrono::Utc::now())
+    }
+
+    fn commit_file_at(
+        repo: &git2::Repository,
+        path: &str,
+        content: &str,
+        date: chrono::DateTime<chrono::Utc>,
+    ) -> git2::Oid {
+        let parent = repo
+            .refname_to_id("refs/heads/master")
+            .ok()
+            .map(|oid| repo.find_commit(oid).unwrap());
+        let blob = repo.blob(content.as_bytes()).unwrap();
+        let mut builder = repo
+            .treebuilder(
+                parent
+                    .as_ref()
+                    .map(|parent| parent.tree().unwrap())
+                    .as_ref(),
+            )
+            .unwrap();
+        builder.insert(path, blob, 0o100644).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let signature = git2::Signature::new(
+            "test",
+            "test@test.com",
+            &git2::Time::new(date.timestamp(), 0),
+        )
+        .unwrap();
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(
+            Some("refs/heads/master"),
+            &signature,
+            &signature,
+            "update",
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_refresh_git_repo() {
+        //the module is compiled several times, hence tests run concurrently
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-refresh-{}-{}",
+            module_path!().replace("::", "-"),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let remote = git2::Repository::init_bare(dir.join("remote.git")).unwrap();
+        let first = commit_file(&remote, "pets.yaml", "v1");
+
+        let mut source = super::CatalogSource {
+            name: String::from("pets"),
+            path: dir.join("clone").to_string_lossy().to_string(),
+            dir: dir.join("clone").to_string_lossy().to_string(),
+            url: None,
+            credentials: None,
+            patterns: Vec::new(),
+            branch: Some(String::from("master")),
+            domain_prefix: None,
+            domain_rules_path: None,
+        };
+        //nothing to clone from
+        match super::refresh_git_repo(&source) {
+            Err(super::git::GitError::NoRemote { source }) => assert_eq!(source, "pets"),
+            other => panic!("unexpected refresh result {:?}", other),
+        }
+
+        source.url = Some(dir.join("remote.git").to_string_lossy().to_string());
+        assert_eq!(super::refresh_git_repo(&source).unwrap(), first);
+        let file = dir.join("clone").join("pets.yaml");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v1");
+
+        //fast-forward, then nothing new
+        let second = commit_file(&remote, "pets.yaml", "v2");
+        assert_eq!(super::refresh_git_repo(&source).unwrap(), second);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "v2");
+        assert_eq!(super::refresh_git_repo(&source).unwrap(), second);
+
+        //local and remote branches have diverged
+        let clone = git2::Repository::open(dir.join("clone")).unwrap();
+        commit_file(&clone, "pets.yaml", "local");
+        commit_file(&remote, "pets.yaml", "v3");
+        match super::refresh_git_repo(&source) {
+            Err(super::git::GitError::NotFastForward { branch, .. }) => {
+                assert_eq!(branch, "master")
+            }
+            other => panic!("unexpected refresh result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_list_catalog_at_ref() {
+        let spec = |version: &str| {
+            format!(
+                "
+        openapi: 3.0.0
+        info:
+          version: {}
+          title: sample
+        paths: {{}}
+        ",
+                version
+            )
+        };
+        let repo = init_git_repo("at-ref", &[("pets.yaml", &spec("1.0.0"))]);
+        let first = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.tag_lightweight("release-1", first.as_object(), false)
+            .unwrap();
+        repo.branch("develop", &first, false).unwrap();
+        commit_file(&repo, "pets.yaml", &spec("2.0.0"));
+
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("**/*.yaml")];
+        let sources = vec![source];
+        let version = |git_ref: Option<&str>| {
+            let (specs, _) = super::list_catalog_at(&sources, git_ref).unwrap();
+            specs[0].api_spec.info.version.clone()
+        };
+        assert_eq!(version(None), "2.0.0");
+        assert_eq!(version(Some("release-1")), "1.0.0");
+        assert_eq!(version(Some("develop")), "1.0.0");
+        assert_eq!(version(Some(&first.id().to_string())), "1.0.0");
+        match super::list_catalog_at(&sources, Some("unknown")) {
+            Err(super::CatalogError::NotFound { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other.map(|_| ())),
+        }
+
+        //the file of the current revision, as of the ref
+        let id = super::list_specs(&sources)[0].id.clone();
+        let spec = super::get_spec_at(&sources, &id, Some("release-1"))
+            .pop()
+            .unwrap();
+        assert_eq!(spec.api_spec.info.version, "1.0.0");
+        assert_ne!(spec.id, id);
+        assert!(spec.path.ends_with("/pets.yaml"));
+    }
+
+    #[test]
+    fn test_spec_stable_id_and_history() {
+        let spec = |version: &str| {
+            format!(
+                "
+        openapi: 3.0.0
+        info:
+          version: {}
+          title: sample
+        paths: {{}}
+        ",
+                version
+            )
+        };
+        let repo = init_git_repo("history", &[("pets.yaml", &spec("1.0.0"))]);
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("*.yaml")];
+        let sources = vec![source];
+        let first = super::list_specs(&sources).pop().unwrap();
+
+        commit_file(&repo, "pets.yaml", &spec("2.0.0"));
+        let second = super::list_specs(&sources).pop().unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(first.stable_id, second.stable_id);
+
+        //rename
+        {
+            let head = repo.head().unwrap().peel_to_commit().unwrap();
+            let mut builder = repo.treebuilder(Some(&head.tree().unwrap())).unwrap();
+            let blob = builder.get("pets.yaml").unwrap().unwrap().id();
+            builder.remove("pets.yaml").unwrap();
+            builder.insert("animals.yaml", blob, 0o100644).unwrap();
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            let signature = git2::Signature::now("test", "test@test.com").unwrap();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "rename",
+                &tree,
+                &[&head],
+            )
+            .unwrap();
+        }
+        let renamed = super::list_specs(&sources).pop().unwrap();
+        assert!(renamed.path.ends_with("/animals.yaml"));
+        assert_eq!(renamed.stable_id, first.stable_id);
+        let spec = super::get_spec(&sources, &first.stable_id).pop().unwrap();
+        assert_eq!(spec.id, renamed.id);
+        assert_eq!(spec.stable_id, first.stable_id);
+
+        let history = super::get_spec_history(&sources, &first.stable_id).unwrap();
+        let revisions: Vec<(&str, Option<&str>)> = history
+            .iter()
+            .map(|revision| (revision.path.as_str(), revision.version.as_deref()))
+            .collect();
+        assert_eq!(
+            revisions,
+            vec![
+                ("animals.yaml", Some("2.0.0")),
+                ("pets.yaml", Some("2.0.0")),
+                ("pets.yaml", Some("1.0.0"))
+            ]
+        );
+        assert_eq!(history[2].spec_id, first.id);
+        assert_eq!(history[0].author, "test");
+        //from the id of a revision
+        assert_eq!(
+            super::get_spec_history(&sources, &renamed.id)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_spec_history_merge() {
+        let repo = init_git_repo("history-merge", &[("pets.yaml", "version: 1")]);
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("*.yaml")];
+        let init = repo.head().unwrap().peel_to_commit().unwrap();
+        let commit = |author: &str, files: &[(&str, &str)], parents: &[&git2::Commit]| {
+            let mut builder = repo.treebuilder(Some(&parents[0].tree().unwrap())).unwrap();
+            for (path, content) in files {
+                let blob = repo.blob(content.as_bytes()).unwrap();
+                builder.insert(path, blob, 0o100644).unwrap();
+            }
+            let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+            let signature = git2::Signature::now(author, "test@test.com").unwrap();
+            let oid = repo
+                .commit(None, &signature, &signature, author, &tree, parents)
+                .unwrap();
+            repo.find_commit(oid).unwrap()
+        };
+
+        //the spec is changed on a branch, merged once master moved on
+        let branch = commit("bob", &[("pets.yaml", "version: 2")], &[&init]);
+        let master = commit("carol", &[("orders.yaml", "version: 1")], &[&init]);
+        let merge = commit("dave", &[("pets.yaml", "version: 2")], &[&master, &branch]);
+
+        let history = super::history::get_history(&repo, &source, merge.id(), "pets.yaml").unwrap();
+        let revisions: Vec<(String, &str)> = history
+            .iter()
+            .map(|revision| (revision.commit_id.clone(), revision.author.as_str()))
+            .collect();
+        assert_eq!(
+            revisions,
+            vec![
+                (branch.id().to_string(), "bob"),
+                (init.id().to_string(), "test")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_catalog_errors() {
+        let spec = "
+        asyncapi: 2.0.0
+        info:
+          version: 1.0.0
+          title: events
+        channels: {}
+        ";
+        let repo = init_git_repo("catalog-errors", &[("events.yaml", spec)]);
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("*.yaml")];
+        let sources = vec![source];
+        let id = super::list_async_specs(&sources).pop().unwrap().id;
+
+        match super::lint_spec_by_id(&sources, &id) {
+            Err(super::CatalogError::Unsupported { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other),
+        }
+        match super::compare_specs(&sources, &id, &id) {
+            Err(super::CatalogError::Unsupported { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other),
+        }
+        match super::get_spec_history(&sources, &uuid::Uuid::new_v4().to_string()) {
+            Err(super::CatalogError::NotFound { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other),
+        }
+        //the repo of the source is missing
+        let mut missing = sources[0].clone();
+        missing.path = format!("{}-missing", missing.path.trim_end_matches('/'));
+        match super::list_catalog_at(&[missing], Some("master")) {
+            Err(super::CatalogError::Git { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_get_churn() {
+        let spec = |title: &str, audience: &str| {
+            format!(
+                "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: {}
+          x-audience: {}
+        servers:
+          - url: /v1/{}
+        paths: {{}}
+        ",
+                title, audience, title
+            )
+        };
+        let repo = init_git_repo(
+            "churn",
+            &[
+                ("pets.yaml", &spec("pets", "company-internal")),
+                ("orders.yaml", &spec("orders", "external-public")),
+            ],
+        );
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("*.yaml")];
+        source.dir = source.path.clone();
+        let sources = vec![source];
+        commit_file(&repo, "pets.yaml", &spec("pets", "external-public"));
+        let (specs, async_specs) = super::list_catalog(&sources);
+        assert_eq!(specs.len(), 2);
+
+        let now = chrono::Utc::now() + chrono::Duration::days(30);
+        let stats = super::get_churn(&specs, &async_specs, &sources, now, &[7, 90]);
+        assert_eq!(stats.len(), 2);
+        //commits are older than the window
+        assert_eq!(stats[0].window_days, 7);
+        assert_eq!(
+            stats[0].per_spec["pets.yaml"],
+            super::churn::Churn::default()
+        );
+        assert_eq!(stats[0].per_domain["/v1/orders"].commits, 0);
+
+        assert_eq!(stats[1].window_days, 90);
+        //added (10 lines) then changed, the audience line being replaced
+        assert_eq!(
+            stats[1].per_spec["pets.yaml"],
+            super::churn::Churn {
+                commits: 2,
+                lines: 10 + 2
+            }
+        );
+        assert_eq!(stats[1].per_spec["orders.yaml"].commits, 1);
+        assert_eq!(stats[1].per_domain["/v1/pets"].commits, 2);
+        //the first commit has added both specs
+        assert_eq!(stats[1].per_audience["external-public"].commits, 2);
+        assert_eq!(stats[1].per_audience["external-public"].lines, 10 + 2 + 10);
+    }
+
+    #[test]
+    fn test_catalog_snapshots() {
+        let spec = |version: &str, paths: &str| {
+            format!(
+                "
+        openapi: 3.0.0
+        info:
+          version: {}
+          title: sample
+        paths: {}
+        ",
+                version, paths
+            )
+        };
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-as-of-{}-{}",
+            module_path!().replace("::", "-"),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = git2::Repository::init(&dir).unwrap();
+        let now = chrono::Utc::now();
+        commit_file_at(
+            &repo,
+            "pets.yaml",
+            &spec("1.0.0", "{}"),
+            now - chrono::Duration::days(10),
+        );
+        commit_file_at(
+            &repo,
+            "pets.yaml",
+            &spec("2.0.0", "{/pets: {}}"),
+            now - chrono::Duration::days(2),
+        );
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("*.yaml")];
+        let sources = vec![source];
+
+        let dates = vec![
+            now - chrono::Duration::days(20),
+            now - chrono::Duration::days(5),
+            now,
+            now - chrono::Duration::days(4),
+        ];
+        let snapshots = super::get_catalog_snapshots(&sources, &dates);
+        assert_eq!(snapshots.len(), dates.len());
+
+        //before the first commit
+        assert!(snapshots[0].is_empty());
+
+        let (specs, _) = super::list_catalog_snapshot(&sources, &snapshots[1]);
+        assert_eq!(specs.len(), 1);
+        assert_eq!(specs[0].api_spec.info.version, "1.0.0");
+        assert!(specs[0].api_spec.paths.is_empty());
+        let first = specs[0].stable_id.clone();
+
+        let (specs, _) = super::list_catalog_snapshot(&sources, &snapshots[2]);
+        assert_eq!(specs[0].api_spec.info.version, "2.0.0");
+        assert_eq!(specs[0].api_spec.paths.len(), 1);
+        assert_eq!(specs[0].stable_id, first);
+
+        //dates need not be sorted
+        assert_eq!(snapshots[3][0].1.commit, snapshots[1][0].1.commit);
+    }
+
+    #[test]
+    fn test_index_incremental_update() {
+        let content = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        x-zally-ignore:
+          - 120 # Rest maturity evolving
+        paths: {}
+        components:
+          schemas:
+            Pets:
+              type: array
+              items:
+                $ref: 'common.yaml#/components/schemas/Pet'
+        ";
+        let common = |property: &str| {
+            format!(
+                "
+        components:
+          schemas:
+            Pet:
+              type: object
+              properties:
+                {}:
+                  type: string
+        ",
+                property
+            )
+        };
+        let repo = init_git_repo(
+            "index",
+            &[("pets.yaml", content), ("common.yaml", &common("name"))],
+        );
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("*.yaml")];
+        let sources = vec![source];
+        let is_spec = |spec_path: &str| spec_path.ends_with(".yaml");
+        let pet_properties = || {
+            let spec = super::list_specs(&sources).pop().unwrap();
+            match &spec.api_spec.components.unwrap().schemas["Pet"] {
+                openapiv3::ReferenceOr::Item(schema) => serde_json::to_value(schema).unwrap()
+                    ["properties"]
+                    .as_object()
+                    .unwrap()
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>(),
+                _ => panic!("Pet should have been hoisted"),
+            }
+        };
+
+        let head = repo.head().unwrap().peel_to_commit().unwrap().id();
+        assert_eq!(
+            super::index::update(&repo, &sources[0].path, head, is_spec).unwrap(),
+            2
+        );
+        assert_eq!(pet_properties(), vec!["name"]);
+        //zally ignores and lint are served from the index
+        let spec = super::list_specs(&sources).pop().unwrap();
+        let ignores = super::get_spec_zally_ignores(&spec);
+        assert_eq!(
+            ignores[0].rationale.as_deref(),
+            Some("Rest maturity evolving")
+        );
+        assert_eq!(
+            super::lint_spec(&spec),
+            super::lint::lint(&super::load_spec(content.as_bytes()).unwrap())
+        );
+
+        //the spec blob is the same, it is bundled again as the file it refers to has changed
+        let head = commit_file(&repo, "common.yaml", &common("nickname"));
+        assert_eq!(pet_properties(), vec!["nickname"]);
+        assert_eq!(
+            super::index::update(&repo, &sources[0].path, head, is_spec).unwrap(),
+            1
+        );
+        //already indexed at this commit
+        assert_eq!(
+            super::index::update(&repo, &sources[0].path, head, is_spec).unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_load_catalog_errors() {
+        let spec = "
+        openapi: 3.0.0
         info:
           version: 1.0.0
           title: sample
-        servers: 
-          - url: /v1/a/b
-        paths:
-          /resource_1:
-            get:
-              responses:
-                '206':
-                  description: Partial Content
+        paths: {}
+        ";
+        let no_paths = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        ";
+        //version is required by the openapi model
+        let no_version = "
+        openapi: 3.0.0
+        info:
+          title: sample
+        paths: {}
+        ";
+        let fragment = "
+        components:
+          schemas: {}
         ";
+        let repo = init_git_repo(
+            "load-errors",
+            &[
+                ("a-spec.yaml", spec),
+                ("b-broken.yaml", "openapi: 3.0.0\ninfo: {title: sample\n"),
+                ("c-no-paths.yaml", no_paths),
+                ("d-no-version.yaml", no_version),
+                ("e-fragment.yaml", fragment),
+            ],
+        );
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("*.yaml")];
+
+        let catalog = super::load_catalog(&[source], None).unwrap();
+        assert_eq!(catalog.specs.len(), 1);
+        let errors: Vec<(&str, &super::LoadError)> = catalog
+            .errors
+            .iter()
+            .map(|error| (error.path.rsplit('/').next().unwrap(), &error.error))
+            .collect();
+        assert_eq!(errors.len(), 3);
+        match errors[0] {
+            ("b-broken.yaml", super::LoadError::Yaml { line, column, .. }) => {
+                assert!(line.is_some() && column.is_some())
+            }
+            error => panic!("unexpected error [{:?}]", error),
+        }
+        assert_eq!(
+            errors[1],
+            (
+                "c-no-paths.yaml",
+                &super::LoadError::MissingSection {
+                    section: String::from("paths")
+                }
+            )
+        );
+        match errors[2] {
+            ("d-no-version.yaml", super::LoadError::Schema { message }) => {
+                assert!(message.contains("version"))
+            }
+            error => panic!("unexpected error [{:?}]", error),
+        }
+    }
 
-        let spec_item = super::SpecItem {
-            path: String::from("std::string::String"),
-            id: String::from("std::string::String"),
-            api_spec: serde_yaml::from_str(spec).unwrap(),
-            audience: String::from("std::string::String"),
-            domain: String::from("std::string::String"),
+    #[test]
+    fn test_parallel_map() {
+        let items: Vec<usize> = (0..100).collect();
+        let inits = std::sync::atomic::AtomicUsize::new(0);
+        let results = super::parallel::map(
+            &items,
+            || inits.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
+            |_, item| {
+                //later items are done first
+                std::thread::sleep(std::time::Duration::from_micros((100 - *item) as u64));
+                item * 2
+            },
+        );
+
+        assert_eq!(
+            results,
+            items.iter().map(|item| item * 2).collect::<Vec<usize>>()
+        );
+        //workers are set up once
+        assert!(inits.into_inner() <= items.len());
+        assert!(super::parallel::map(&Vec::<usize>::new(), || (), |_, item| *item).is_empty());
+    }
+
+    #[test]
+    fn test_get_parse_times() {
+        let spec = |title: &str| {
+            format!(
+                "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: {}
+        paths: {{}}
+        ",
+                title
+            )
         };
+        let files: Vec<(String, String)> = (0..20)
+            .map(|idx| {
+                (
+                    format!("spec-{:02}.yaml", idx),
+                    spec(&format!("parse-times-{}", idx)),
+                )
+            })
+            .collect();
+        let files: Vec<(&str, &str)> = files
+            .iter()
+            .map(|(path, content)| (path.as_str(), content.as_str()))
+            .collect();
+        let repo = init_git_repo("parse-times", &files);
+        let mut source = get_source(super::DEFAULT_SOURCE, &repo);
+        source.patterns = vec![String::from("*.yaml")];
+        let sources = vec![source];
+
+        //listed in the order of the files, whatever the worker having parsed them
+        let (specs, async_specs) = super::list_catalog(&sources);
+        let titles: Vec<&str> = specs
+            .iter()
+            .map(|spec| spec.api_spec.info.title.as_str())
+            .collect();
+        let expected: Vec<String> = (0..20).map(|idx| format!("parse-times-{}", idx)).collect();
+        assert_eq!(titles, expected);
+
+        let parse_times = super::get_parse_times(&specs, &async_specs);
+        let ids: Vec<&str> = parse_times.iter().map(|(id, _)| id.as_str()).collect();
+        let expected: Vec<&str> = specs.iter().map(|spec| spec.id.as_str()).collect();
+        assert_eq!(ids, expected);
+    }
 
-        specs.push(spec_item);
+    #[test]
+    fn test_to_json_1() {
+        let doc: serde_yaml::Value =
+            serde_yaml::from_str("responses: {200: {description: ok}}").unwrap();
 
+        let json = super::to_json(&doc);
+        assert_eq!(json["responses"]["200"]["description"], "ok");
+    }
+
+    #[test]
+    fn test_get_operations_1() {
         let spec = "
         openapi: 3.0.0
         info:
           version: 1.0.0
           title: sample
-        servers: 
-          - url: /v1/a/c
+        security:
+          - oauth2: [read]
         paths:
-          /resource_1:
+          /pets/{id}:
+            parameters:
+              - name: id
+                in: path
+                required: true
+                schema:
+                  type: string
             get:
+              operationId: getPet
+              tags: [pets]
+              parameters:
+                - $ref: '#/components/parameters/fields'
               responses:
-                '206':
-                  description: Partial Content
-                  /resource_1:
-            post:
+                '200':
+                  description: ok
+            delete:
+              deprecated: true
+              security:
+                - oauth2: [write]
               responses:
-                '206':
-                  description: Partial Content
+                '204':
+                  description: deleted
+        components:
+          parameters:
+            fields:
+              name: fields
+              in: query
+              schema:
+                type: string
         ";
 
-        let spec_item = super::SpecItem {
-            path: String::from("std::string::String"),
-            id: String::from("std::string::String"),
-            api_spec: serde_yaml::from_str(spec).unwrap(),
-            audience: String::from("std::string::String"),
-            domain: String::from("std::string::String"),
+        let openapi = match super::parse_spec(spec.as_bytes()) {
+            Ok(super::ParsedSpec::OpenApi(openapi, _)) => openapi,
+            _ => panic!("spec should be an openapi"),
         };
+        let operations = super::operations::get_operations(&openapi);
+
+        //1 path but 2 operations
+        assert_eq!(openapi.paths.len(), 1);
+        assert_eq!(operations.len(), 2);
+
+        let get = &operations[0];
+        assert_eq!(get.method, "GET");
+        assert_eq!(get.operation_id, Some(String::from("getPet")));
+        assert_eq!(get.tags, vec![String::from("pets")]);
+        assert_eq!(get.parameters.len(), 2);
+        assert_eq!(get.parameters[1].location, "query");
+        assert_eq!(get.security[0]["oauth2"], vec![String::from("read")]);
+
+        let delete = &operations[1];
+        assert_eq!(delete.method, "DELETE");
+        assert!(delete.deprecated);
+        assert_eq!(delete.parameters.len(), 1);
+        assert_eq!(delete.security[0]["oauth2"], vec![String::from("write")]);
+    }
 
-        specs.push(spec_item);
+    fn parse_openapi(spec: &str) -> openapiv3::OpenAPI {
+        match super::parse_spec(spec.as_bytes()) {
+            Ok(super::ParsedSpec::OpenApi(openapi, _)) => *openapi,
+            _ => panic!("spec should be an openapi"),
+        }
+    }
 
-        let spec = "
+    const COMPARE_BASE_SPEC: &str = "
         openapi: 3.0.0
         info:
           version: 1.0.0
           title: sample
         paths:
-          /resource_1:
+          /pets:
             get:
+              parameters:
+                - name: status
+                  in: query
+                  schema:
+                    type: string
+                    enum: [available, pending, sold]
               responses:
-                '206':
-                  description: Partial Content
+                '200':
+                  description: ok
+                  content:
+                    application/json:
+                      schema:
+                        type: array
+                        items:
+                          $ref: '#/components/schemas/Pet'
+            delete:
+              responses:
+                '204':
+                  description: deleted
+        components:
+          schemas:
+            Pet:
+              type: object
+              properties:
+                name:
+                  type: string
+                age:
+                  type: integer
+                tag:
+                  type: string
         ";
 
-        let spec_item = super::SpecItem {
-            path: String::from("std::string::String"),
-            id: String::from("std::string::String"),
-            api_spec: serde_yaml::from_str(spec).unwrap(),
-            audience: String::from("std::string::String"),
-            domain: String::from("std::string::String"),
-        };
-
-        specs.push(spec_item);
-
-        let data = super::get_endpoints_num_per_subdomain(&specs);
+    #[test]
+    fn test_compare_specs_no_change() {
+        let old = parse_openapi(COMPARE_BASE_SPEC);
+        let new = parse_openapi(COMPARE_BASE_SPEC);
 
-        assert_eq!(data.get("/v1/a/c").unwrap(), &1usize);
-        assert_eq!(data.get("/v1/a/b").unwrap(), &1usize);
-        assert_eq!(
-            data.get("NA - servers attribute not specified").unwrap(),
-            &1usize
-        );
+        assert!(super::compare::compare(&old, &new).is_empty());
     }
 
     #[test]
-    fn test_get_zally_ignore_metrics_1() {
-        let spec = "
-        openapi: \"3.0.0\"
+    fn test_compare_specs_breaking() {
+        let new_spec = "
+        openapi: 3.0.0
         info:
-          version: 1.0.0
-          title: an API ...
-        x-zally-ignore:
-          - 134
-          - 120 # Rest maturity evolving
-        
+          version: 2.0.0
+          title: sample
         paths:
-          /v1/a/b:
+          /pets:
             get:
-              description: get ...
+              parameters:
+                - name: status
+                  in: query
+                  schema:
+                    type: string
+                    enum: [available, pending]
+                - name: owner
+                  in: query
+                  required: true
+                  schema:
+                    type: string
               responses:
                 '200':
-                  description: returns...
+                  description: ok
+                  content:
+                    application/json:
+                      schema:
+                        type: array
+                        items:
+                          $ref: '#/components/schemas/Pet'
+        components:
+          schemas:
+            Pet:
+              type: object
+              properties:
+                name:
+                  type: string
+                age:
+                  type: string
+        ";
+        let old = parse_openapi(COMPARE_BASE_SPEC);
+        let new = parse_openapi(new_spec);
 
-          /v2/a/b:
-            x-zally-ignore:
-              - 164
+        let changes = super::compare::compare(&old, &new);
+        let kinds: Vec<super::compare::ChangeKind> =
+            changes.iter().map(|val| val.kind.clone()).collect();
+
+        assert!(super::compare::is_breaking(&changes));
+        assert!(changes.iter().all(|val| val.breaking));
+        assert_eq!(
+            kinds,
+            vec![
+                super::compare::ChangeKind::EnumNarrowed,
+                super::compare::ChangeKind::RequiredParameterAdded,
+                super::compare::ChangeKind::TypeChanged,
+                super::compare::ChangeKind::ResponseFieldRemoved,
+                super::compare::ChangeKind::OperationRemoved,
+            ]
+        );
+        assert_eq!(changes[0].pointer, "/paths/~1pets/get/parameters/0/schema");
+        assert_eq!(changes[1].pointer, "/paths/~1pets/get/parameters/1");
+        assert_eq!(
+            changes[3].pointer,
+            "/paths/~1pets/get/responses/200/content/application~1json/schema/items/properties/tag"
+        );
+        assert_eq!(changes[4].pointer, "/paths/~1pets/delete");
+    }
+
+    #[test]
+    fn test_compare_specs_non_breaking() {
+        let new_spec = "
+        openapi: 3.0.0
+        info:
+          version: 1.1.0
+          title: sample
+        paths:
+          /pets:
             get:
-              description: get ...
-              responses:
-                '200':
-                  description: returns...
-                    
-          /a/b:
-            x-zally-ignore:
-              - 164 # Rest maturity evolving
-              - 134
-            post:
               parameters:
-                - name: chunk
+                - name: status
+                  in: query
+                  schema:
+                    type: string
+                    enum: [available, pending, sold, reserved]
+                - name: limit
                   in: query
-                  required: true
                   schema:
                     type: integer
-                    format: int32
-                    minimum: 1
               responses:
-                200:
-                  description: ...     
+                '200':
+                  description: ok
+                  content:
+                    application/json:
+                      schema:
+                        type: array
+                        items:
+                          $ref: '#/components/schemas/Pet'
+            delete:
+              responses:
+                '204':
+                  description: deleted
+          /pets/{id}:
+            get:
+              responses:
+                '200':
+                  description: ok
+        components:
+          schemas:
+            Pet:
+              type: object
+              properties:
+                name:
+                  type: string
+                age:
+                  type: integer
+                tag:
+                  type: string
+                color:
+                  type: string
         ";
+        let old = parse_openapi(COMPARE_BASE_SPEC);
+        let new = parse_openapi(new_spec);
 
-        let results = super::get_zally_ignore_metrics(spec, "name");
-
-        println!("*** results : {:?}", results);
+        let changes = super::compare::compare(&old, &new);
+        let kinds: Vec<super::compare::ChangeKind> =
+            changes.iter().map(|val| val.kind.clone()).collect();
 
-        assert_eq!(results.get(&134i64).unwrap(), &3usize);
-        assert_eq!(results.get(&120i64).unwrap(), &3usize);
-        assert_eq!(results.get(&164i64).unwrap(), &2usize);
+        assert!(!super::compare::is_breaking(&changes));
+        assert_eq!(
+            kinds,
+            vec![
+                super::compare::ChangeKind::EnumWidened,
+                super::compare::ChangeKind::ParameterAdded,
+                super::compare::ChangeKind::FieldAdded,
+                super::compare::ChangeKind::OperationAdded,
+            ]
+        );
     }
 
     #[test]
-    fn test_get_zally_ignore_metrics_2() {
-        let spec = "
-        openapi: \"3.0.0\"
+    fn test_review_spec_contents_1() {
+        let before = "
+        openapi: 3.0.0
         info:
           version: 1.0.0
-          title: an API ...
-        
+          title: sample
+          x-audience: company-internal
         paths:
-          /v1/a/b:
+          /pets:
             get:
-              description: get ...
+              x-zally-ignore: [M10, 215]
               responses:
                 '200':
-                  description: returns...
-                    
-          /a/b:
-            x-zally-ignore:
-              - M10
-              - 164 
-            post:
-              parameters:
-                - name: chunk
-                  in: query
-                  required: true
-                  schema:
-                    type: integer
-                    format: int32
-                    minimum: 1
+                  description: ok
+            delete:
               responses:
-                200:
-                  description: ...       
+                '204':
+                  description: deleted
+        ";
+        let after = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+          x-audience: external-public
+        paths:
+          /pets:
+            get:
+              x-zally-ignore:
+                - M10
+                - 104
+              x-has-authority: pets.read
+              responses:
+                '200':
+                  description: ok
         ";
 
-        let results = super::get_zally_ignore_metrics(spec, "name");
+        let (items, breaking) =
+            super::review_spec_contents(Some(before.as_bytes()), Some(after.as_bytes()));
+        let items: Vec<(&str, super::review::ReviewObject, &str, &str)> = items
+            .iter()
+            .map(|val| {
+                (
+                    val.typ.as_str(),
+                    val.object.clone(),
+                    val.pointer.as_str(),
+                    val.value.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(breaking, Some(true));
+        assert_eq!(
+            items,
+            vec![
+                (
+                    "CHANGED",
+                    super::review::ReviewObject::Audience,
+                    "/info/x-audience",
+                    "external-public"
+                ),
+                (
+                    "CHANGED",
+                    super::review::ReviewObject::Operation,
+                    "/paths/~1pets/get",
+                    "GET /pets"
+                ),
+                (
+                    "REMOVED",
+                    super::review::ReviewObject::ZallyIgnore,
+                    "/paths/~1pets/get/x-zally-ignore/1",
+                    "215"
+                ),
+                (
+                    "ADDED",
+                    super::review::ReviewObject::ZallyIgnore,
+                    "/paths/~1pets/get/x-zally-ignore/1",
+                    "104"
+                ),
+                (
+                    "REMOVED",
+                    super::review::ReviewObject::Operation,
+                    "/paths/~1pets/delete",
+                    "DELETE /pets"
+                ),
+                (
+                    "ADDED",
+                    super::review::ReviewObject::Permission,
+                    "/paths/~1pets/get/x-has-authority",
+                    "pets.read"
+                ),
+            ]
+        );
+    }
 
-        println!("*** results : {:?}", results);
+    #[test]
+    fn test_review_spec_contents_added_file() {
+        let after = "
+        openapi: 3.0.0
+        info:
+          version: 1.0.0
+          title: sample
+        paths:
+          /pets:
+            get:
+              responses:
+                '200':
+                  description: ok
+        ";
 
-        assert_eq!(results.get(&164i64).unwrap(), &1usize);
-        assert_eq!(results.get(&-1i64).unwrap(), &1usize);
+        let (items, breaking) = super::review_spec_contents(None, Some(after.as_bytes()));
+
+        assert_eq!(breaking, Some(false));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].typ, "ADDED");
     }
 
     #[test]
-    fn test_get_endpoints_num_per_audience_ignore_metrics_1() {
+    fn test_lint_1() {
         let spec = "
-        openapi: \"3.0.0\"
+        openapi: 3.0.0
         info:
-          version: 1.0.0
-          title: an API ...
-          x-audience: an audience
-        
+          version: '1.0'
+          title: sample
+          description: sample
+          contact:
+            name: team
+            url: https://team.org
+            email: team@team.org
+          x-api-id: 8e2bcd3c-6d85-4e66-bd6a-bd2e3f8b0b43
+          x-audience: company-internal
         paths:
-          /v1/a/b:
+          /v1/pet/{id}:
             get:
-              description: get ...
+              parameters:
+                - name: petId
+                  in: query
+                  schema:
+                    type: string
               responses:
                 '200':
-                  description: returns...
-
-          /v2/a/b:
+                  description: ok
+                  content:
+                    application/json:
+                      schema:
+                        type: object
+                        properties:
+                          petName:
+                            type: string
+                          status:
+                            type: string
+                            enum: [AVAILABLE, sold]
+          /stores/:
+            x-zally-ignore: [151]
             get:
-              description: get ...
               responses:
                 '200':
-                  description: returns...  
+                  description: ok
+                '404':
+                  description: not found
+                  content:
+                    application/json:
+                      schema:
+                        type: object
         ";
+        let doc: serde_yaml::Value = serde_yaml::from_str(spec).unwrap();
 
-        let results = super::get_endpoints_num_per_audience_metrics(spec, "name");
+        let violations: Vec<(String, String)> = super::lint::lint(&doc)
+            .into_iter()
+            .map(|val| (val.rule, val.pointer))
+            .collect();
 
-        assert_eq!(results.get("an audience").unwrap(), &2usize);
+        assert_eq!(
+            violations,
+            vec![
+                (String::from("116"), String::from("/info/version")),
+                (String::from("115"), String::from("/paths/~1v1~1pet~1{id}")),
+                (String::from("134"), String::from("/paths/~1v1~1pet~1{id}")),
+                (String::from("130"), String::from("/paths/~1v1~1pet~1{id}/get/parameters/0")),
+                (String::from("151"), String::from("/paths/~1v1~1pet~1{id}/get/responses")),
+                (String::from("136"), String::from("/paths/~1stores~1")),
+                (
+                    String::from("176"),
+                    String::from("/paths/~1stores~1/get/responses/404/content")
+                ),
+                (
+                    String::from("118"),
+                    String::from("/paths/~1v1~1pet~1{id}/get/responses/200/content/application~1json/schema/properties/petName")
+                ),
+                (
+                    String::from("240"),
+                    String::from("/paths/~1v1~1pet~1{id}/get/responses/200/content/application~1json/schema/properties/status/enum")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_global_zally_ignore() {
+        let spec = "
+        openapi: 3.0.0
+        x-zally-ignore: [218, 215, 219]
+        info:
+          version: 1.0.0
+          title: sample
+        paths: {}
+        ";
+        let doc: serde_yaml::Value = serde_yaml::from_str(spec).unwrap();
+
+        assert!(super::lint::lint(&doc).is_empty());
+    }
+
+    fn get_claim(
+        stable_id: &str,
+        key: super::reconciliation::ApiKey,
+        tier: Option<&str>,
+    ) -> super::reconciliation::SpecClaim {
+        super::reconciliation::SpecClaim {
+            stable_id: String::from(stable_id),
+            key: key,
+            domain: String::from("/trading/pricing"),
+            tier: tier.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        use super::reconciliation::*;
+        use uuid::Uuid;
+
+        let api_id = Uuid::new_v4();
+        let new_api_id = Uuid::new_v4();
+        let claims = vec![
+            get_claim("a", ApiKey::Title(String::from("Pricing API")), None),
+            get_claim("b", ApiKey::Title(String::from("Quotes")), None),
+            get_claim("c", ApiKey::ApiId(new_api_id.to_string()), None),
+            get_claim("d", ApiKey::ApiId(new_api_id.to_string()), Some("tier-1")),
+        ];
+        let apis = vec![
+            RegisteredApi {
+                id: api_id,
+                name: String::from("pricing api"),
+                spec_ids: vec![String::from("e")],
+            },
+            RegisteredApi {
+                id: Uuid::new_v4(),
+                name: String::from("Quotes"),
+                spec_ids: vec![String::from("b")],
+            },
+        ];
+
+        let reconciliation = reconcile(&claims, &apis);
+
+        //b is claimed, c and d share their x-api-id
+        assert_eq!(reconciliation.proposals.len(), 2);
+        let find = |action: ReconciliationAction| {
+            reconciliation
+                .proposals
+                .iter()
+                .find(|proposal| proposal.action == action)
+                .unwrap()
+        };
+        let link = find(ReconciliationAction::LINK);
+        assert_eq!(link.api_id, Some(api_id));
+        assert_eq!(link.spec_ids, vec![String::from("a")]);
+        let create = find(ReconciliationAction::CREATE);
+        assert_eq!(create.api_id, Some(new_api_id));
+        assert_eq!(create.spec_ids, vec![String::from("c"), String::from("d")]);
+        assert_eq!(create.tier, Some(String::from("tier-1")));
+
+        //e is no longer in the catalog
+        assert_eq!(
+            reconciliation.orphans,
+            vec![Orphan {
+                api_id: api_id,
+                name: String::from("pricing api"),
+                missing_spec_ids: vec![String::from("e")],
+            }]
+        );
     }
 }
@@ -0,0 +1,140 @@
+use openapiv3::{OpenAPI, Operation, Parameter, PathItem, ReferenceOr, SecurityRequirement};
+use serde::{Deserialize, Serialize};
+
+use super::AsyncAPI;
+
+//one item per method + path, a path with GET / POST / DELETE gives 3 operations
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OperationItem {
+    pub method: String,
+    pub path: String,
+    pub operation_id: Option<String>,
+    pub tags: Vec<String>,
+    pub deprecated: bool,
+    pub security: Vec<SecurityRequirement>,
+    pub parameters: Vec<ParameterItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ParameterItem {
+    pub name: String,
+    pub location: String,
+    pub required: bool,
+}
+
+pub fn get_operations(spec: &OpenAPI) -> Vec<OperationItem> {
+    let mut operations = Vec::new();
+
+    for (path, item) in spec.paths.iter() {
+        let item = match item {
+            ReferenceOr::Item(item) => item,
+            //path items refs are resolved at bundling time, an unresolved one cannot be described
+            ReferenceOr::Reference { .. } => continue,
+        };
+
+        for (method, operation) in get_path_operations(item) {
+            operations.push(OperationItem {
+                method: String::from(method),
+                path: path.clone(),
+                operation_id: operation.operation_id.clone(),
+                tags: operation.tags.clone(),
+                deprecated: operation.deprecated,
+                //operation level requirements override the spec level ones
+                security: match operation.security.is_empty() {
+                    true => spec.security.clone(),
+                    false => operation.security.clone(),
+                },
+                parameters: get_parameters(spec, item, operation),
+            });
+        }
+    }
+
+    operations
+}
+
+//subscribe / publish are the asyncapi counterpart of http methods, and channels of paths
+pub fn get_async_operations(spec: &AsyncAPI) -> Vec<OperationItem> {
+    let mut operations = Vec::new();
+
+    for (channel, item) in spec.channels.iter() {
+        let methods = vec![("SUBSCRIBE", &item.subscribe), ("PUBLISH", &item.publish)];
+        for (method, operation) in methods {
+            if let Some(operation) = operation {
+                operations.push(OperationItem {
+                    method: String::from(method),
+                    path: channel.clone(),
+                    operation_id: operation.operation_id.clone(),
+                    tags: Vec::new(),
+                    deprecated: false,
+                    security: Vec::new(),
+                    parameters: Vec::new(),
+                });
+            }
+        }
+    }
+
+    operations
+}
+
+fn get_path_operations(item: &PathItem) -> Vec<(&str, &Operation)> {
+    let methods = vec![
+        ("GET", &item.get),
+        ("PUT", &item.put),
+        ("POST", &item.post),
+        ("DELETE", &item.delete),
+        ("OPTIONS", &item.options),
+        ("HEAD", &item.head),
+        ("PATCH", &item.patch),
+        ("TRACE", &item.trace),
+    ];
+
+    methods
+        .into_iter()
+        .filter_map(|(method, operation)| operation.as_ref().map(|operation| (method, operation)))
+        .collect()
+}
+
+//path level parameters apply to all operations, unless overridden (same name and location) by the operation
+fn get_parameters(spec: &OpenAPI, item: &PathItem, operation: &Operation) -> Vec<ParameterItem> {
+    let mut parameters: Vec<ParameterItem> = Vec::new();
+
+    for parameter in item.parameters.iter().chain(operation.parameters.iter()) {
+        if let Some(parameter) = get_parameter(spec, parameter) {
+            parameters
+                .retain(|val| !(val.name == parameter.name && val.location == parameter.location));
+            parameters.push(parameter);
+        }
+    }
+
+    parameters
+}
+
+fn get_parameter(spec: &OpenAPI, parameter: &ReferenceOr<Parameter>) -> Option<ParameterItem> {
+    let parameter = match parameter {
+        ReferenceOr::Item(parameter) => parameter,
+        ReferenceOr::Reference { reference } => {
+            let name = reference.trim_start_matches("#/components/parameters/");
+            match spec
+                .components
+                .as_ref()
+                .and_then(|components| components.parameters.get(name))
+            {
+                Some(ReferenceOr::Item(parameter)) => parameter,
+                _ => return None,
+            }
+        }
+    };
+
+    let (location, data) = match parameter {
+        Parameter::Query { parameter_data, .. } => ("query", parameter_data),
+        Parameter::Header { parameter_data, .. } => ("header", parameter_data),
+        Parameter::Path { parameter_data, .. } => ("path", parameter_data),
+        Parameter::Cookie { parameter_data, .. } => ("cookie", parameter_data),
+    };
+
+    Some(ParameterItem {
+        name: data.name.clone(),
+        location: String::from(location),
+        required: data.required,
+    })
+}
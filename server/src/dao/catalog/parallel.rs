@@ -0,0 +1,54 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/**
+ * Apply f to all the items over a pool of workers, each of them being set up once with init (e.g. to open the repo,
+ * which cannot be shared between threads). Results are in the order of the items, whatever the worker that got them.
+ */
+pub fn map<T, S, R>(
+    items: &[T],
+    init: impl Fn() -> S + Sync,
+    f: impl Fn(&mut S, &T) -> R + Sync,
+) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+{
+    let workers = thread::available_parallelism()
+        .map(|workers| workers.get())
+        .unwrap_or(1)
+        .min(items.len());
+    if workers <= 1 {
+        let mut state = init();
+        return items.iter().map(|item| f(&mut state, item)).collect();
+    }
+
+    //workers take the next item as soon as they are done with the previous one
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut state = init();
+                    let mut results = Vec::new();
+                    loop {
+                        let idx = next.fetch_add(1, Ordering::Relaxed);
+                        match items.get(idx) {
+                            Some(item) => results.push((idx, f(&mut state, item))),
+                            None => break,
+                        }
+                    }
+                    results
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect()
+    });
+    results.sort_by_key(|(idx, _)| *idx);
+
+    results.into_iter().map(|(_, result)| result).collect()
+}
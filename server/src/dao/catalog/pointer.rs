@@ -0,0 +1,49 @@
+use serde_yaml::Value;
+
+//methods of the operations of a path item
+pub const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+//segment of a json pointer, see RFC 6901
+pub fn escape(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+pub fn unescape(segment: &str) -> String {
+    segment.replace("~1", "/").replace("~0", "~")
+}
+
+//a scalar as is (a key, a value...), the yaml of a mapping or a sequence
+pub fn to_string(val: &Value) -> String {
+    match val {
+        Value::String(val) => val.clone(),
+        Value::Number(val) => val.to_string(),
+        Value::Bool(val) => val.to_string(),
+        Value::Null => String::from(""),
+        val => serde_yaml::to_string(val)
+            .map(|val| val.trim_start_matches("---").trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+//none if not a scalar
+pub fn json_to_string(val: &serde_json::Value) -> Option<String> {
+    match val {
+        serde_json::Value::String(val) => Some(val.clone()),
+        serde_json::Value::Number(val) => Some(val.to_string()),
+        serde_json::Value::Bool(val) => Some(val.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_escape() {
+        assert_eq!(super::escape("/pets/{id}"), "~1pets~1{id}");
+        assert_eq!(super::escape("a~/b"), "a~0~1b");
+        assert_eq!(super::unescape("a~0~1b"), "a~/b");
+        assert_eq!(super::unescape(&super::escape("~1")), "~1");
+    }
+}
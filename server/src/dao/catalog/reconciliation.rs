@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use std::collections::{BTreeMap, HashSet};
+
+use super::extensions::SpecExtensions;
+use super::{AsyncSpecItem, SpecItem};
+
+/**
+ * What a spec of the catalog tells about the api it belongs to
+ */
+#[derive(Debug, Clone)]
+pub struct SpecClaim {
+    pub stable_id: String,
+    //x-api-id of the spec, its title if not set
+    pub key: ApiKey,
+    pub domain: String,
+    pub tier: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ApiKey {
+    ApiId(String),
+    Title(String),
+}
+
+impl ApiKey {
+    fn name(&self) -> &str {
+        match self {
+            ApiKey::ApiId(name) | ApiKey::Title(name) => name,
+        }
+    }
+}
+
+//an api as registered in the database, along with the specs linked to it
+#[derive(Debug, Clone)]
+pub struct RegisteredApi {
+    pub id: Uuid,
+    pub name: String,
+    pub spec_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ReconciliationAction {
+    //link the specs to the registered api having the same key
+    LINK,
+    //register a new api and link the specs to it
+    CREATE,
+}
+
+//specs no registered api claims, grouped by key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Proposal {
+    pub action: ReconciliationAction,
+    pub key: ApiKey,
+    //the registered api to link to, or the x-api-id (if a uuid) of the api to create
+    pub api_id: Option<Uuid>,
+    pub name: String,
+    //x-domain of the specs, the domain derived from their path or servers if not set
+    pub domain: String,
+    pub tier: Option<String>,
+    pub spec_ids: Vec<String>,
+}
+
+//a registered api having specs linked that are no longer in the catalog
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Orphan {
+    pub api_id: Uuid,
+    pub name: String,
+    pub missing_spec_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Reconciliation {
+    pub proposals: Vec<Proposal>,
+    pub orphans: Vec<Orphan>,
+}
+
+//x-api-id, x-domain and x-tier are read from the info object, or from the document
+fn get_extension(extensions: &SpecExtensions, name: &str) -> Option<String> {
+    extensions
+        .info
+        .get(name)
+        .or_else(|| extensions.document.get(name))
+        .and_then(|val| match val {
+            serde_json::Value::String(val) => Some(val.clone()),
+            serde_json::Value::Number(val) => Some(val.to_string()),
+            _ => None,
+        })
+        .map(|val| val.trim().to_string())
+        .filter(|val| !val.is_empty())
+}
+
+fn get_claim(stable_id: &str, title: &str, domain: &str, extensions: &SpecExtensions) -> SpecClaim {
+    SpecClaim {
+        stable_id: String::from(stable_id),
+        key: match get_extension(extensions, "x-api-id") {
+            Some(api_id) => ApiKey::ApiId(api_id),
+            None => ApiKey::Title(title.trim().to_string()),
+        },
+        domain: get_extension(extensions, "x-domain").unwrap_or_else(|| String::from(domain)),
+        tier: get_extension(extensions, "x-tier"),
+    }
+}
+
+pub fn get_claims(all_specs: &[SpecItem], all_async_specs: &[AsyncSpecItem]) -> Vec<SpecClaim> {
+    let specs = all_specs.iter().map(|spec| {
+        get_claim(
+            &spec.stable_id,
+            &spec.api_spec.info.title,
+            &spec.domain_match.domain,
+            &spec.extensions,
+        )
+    });
+    let async_specs = all_async_specs.iter().map(|spec| {
+        get_claim(
+            &spec.stable_id,
+            &spec.api_spec.info.title,
+            &spec.domain_match.domain,
+            &spec.extensions,
+        )
+    });
+
+    specs.chain(async_specs).collect()
+}
+
+//an x-api-id matches the id of the api (or its name if not a uuid), a title matches its name
+fn find_api<'a>(apis: &'a [RegisteredApi], key: &ApiKey) -> Option<&'a RegisteredApi> {
+    match key {
+        ApiKey::ApiId(api_id) => match Uuid::parse_str(api_id) {
+            Ok(api_id) => apis.iter().find(|api| api.id == api_id),
+            Err(_) => apis
+                .iter()
+                .find(|api| api.name.eq_ignore_ascii_case(api_id)),
+        },
+        ApiKey::Title(title) => apis
+            .iter()
+            .find(|api| api.name.trim().eq_ignore_ascii_case(title)),
+    }
+}
+
+/**
+ * Proposals for the specs that no registered api claims, and the registered apis whose specs are gone.
+ * Specs having the same key are proposed for the same api.
+ */
+pub fn reconcile(claims: &[SpecClaim], apis: &[RegisteredApi]) -> Reconciliation {
+    let linked: HashSet<&str> = apis
+        .iter()
+        .flat_map(|api| api.spec_ids.iter().map(String::as_str))
+        .collect();
+    let mut unclaimed: BTreeMap<&ApiKey, Vec<&SpecClaim>> = BTreeMap::new();
+    for claim in claims {
+        if !linked.contains(claim.stable_id.as_str()) {
+            unclaimed.entry(&claim.key).or_default().push(claim);
+        }
+    }
+
+    let proposals = unclaimed
+        .into_iter()
+        .map(|(key, claims)| {
+            let (action, api_id, name) = match find_api(apis, key) {
+                Some(api) => (ReconciliationAction::LINK, Some(api.id), api.name.clone()),
+                None => (
+                    ReconciliationAction::CREATE,
+                    match key {
+                        ApiKey::ApiId(api_id) => Uuid::parse_str(api_id).ok(),
+                        ApiKey::Title(_) => None,
+                    },
+                    String::from(key.name()),
+                ),
+            };
+            Proposal {
+                action: action,
+                key: key.clone(),
+                api_id: api_id,
+                name: name,
+                domain: claims[0].domain.clone(),
+                tier: claims.iter().find_map(|claim| claim.tier.clone()),
+                spec_ids: claims.iter().map(|claim| claim.stable_id.clone()).collect(),
+            }
+        })
+        .collect();
+
+    let known: HashSet<&str> = claims
+        .iter()
+        .map(|claim| claim.stable_id.as_str())
+        .collect();
+    let orphans = apis
+        .iter()
+        .filter_map(|api| {
+            let missing_spec_ids: Vec<String> = api
+                .spec_ids
+                .iter()
+                .filter(|spec_id| !known.contains(spec_id.as_str()))
+                .cloned()
+                .collect();
+            match missing_spec_ids.is_empty() {
+                true => None,
+                false => Some(Orphan {
+                    api_id: api.id,
+                    name: api.name.clone(),
+                    missing_spec_ids: missing_spec_ids,
+                }),
+            }
+        })
+        .collect();
+
+    Reconciliation {
+        proposals: proposals,
+        orphans: orphans,
+    }
+}
@@ -0,0 +1,383 @@
+use log::debug;
+
+use serde_yaml::{Mapping, Value};
+
+use git2::{Oid, Repository, Tree};
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use super::pointer::unescape;
+
+/**
+ * Follow $ref pointing to other files of the same git tree (e.g. ../common/errors.yaml#/components/schemas/Problem)
+ * and build a self-contained document.
+ * - refs on a named component (#/components/{type}/{name}, or #/definitions/{name} etc. for swagger 2.0) are hoisted
+ *   into the same place of the root document and replaced by an internal ref
+ * - any other ref is inlined, a cycle being reported as an error as it cannot be inlined
+ */
+pub struct Resolver<'a> {
+    repo: &'a Repository,
+    tree: Tree<'a>,
+    documents: HashMap<String, Value>,
+}
+
+struct Bundle {
+    root_path: String,
+    //(file, pointer) -> internal ref it has been hoisted to
+    hoisted: HashMap<(String, String), String>,
+    additions: Vec<(Vec<String>, Value)>,
+    //(file, pointer) being inlined, to detect cycles
+    stack: Vec<(String, String)>,
+    //files referenced by the root document, directly or not
+    files: Vec<String>,
+}
+
+pub fn has_external_refs(doc: &Value) -> bool {
+    match doc {
+        Value::Mapping(mapping) => mapping
+            .iter()
+            .any(|(name, val)| match (name.as_str(), val) {
+                (Some("$ref"), Value::String(reference)) => is_file_ref(reference),
+                (_, val) => has_external_refs(val),
+            }),
+        Value::Sequence(sequence) => sequence.iter().any(has_external_refs),
+        _ => false,
+    }
+}
+
+fn is_file_ref(reference: &str) -> bool {
+    !reference.starts_with('#') && !reference.contains("://")
+}
+
+impl<'a> Resolver<'a> {
+    pub fn new(repo: &'a Repository, tree: Tree<'a>) -> Resolver<'a> {
+        Resolver {
+            repo: repo,
+            tree: tree,
+            documents: HashMap::new(),
+        }
+    }
+
+    /**
+     * spec_path is the path of the root spec, relative to the root of the git tree
+     */
+    pub fn bundle(&mut self, spec_path: &str, doc: &Value) -> Result<Value, String> {
+        self.bundle_files(spec_path, doc).0
+    }
+
+    /**
+     * Same as bundle, along with the files the spec refers to (even if they could not be loaded)
+     */
+    pub fn bundle_files(
+        &mut self,
+        spec_path: &str,
+        doc: &Value,
+    ) -> (Result<Value, String>, Vec<String>) {
+        let root_path = normalize_path(Path::new(spec_path));
+        self.documents.insert(root_path.clone(), doc.clone());
+
+        let mut bundle = Bundle {
+            root_path: root_path.clone(),
+            hoisted: HashMap::new(),
+            additions: Vec::new(),
+            stack: Vec::new(),
+            files: Vec::new(),
+        };
+
+        let mut bundled = doc.clone();
+        if let Err(why) = self.process(&mut bundled, &root_path, &mut bundle) {
+            return (Err(why), bundle.files);
+        }
+
+        for (location, val) in bundle.additions {
+            insert_at(&mut bundled, &location, val);
+        }
+
+        debug!(
+            "Bundled spec [{}] - [{}] hoisted refs",
+            root_path,
+            bundle.hoisted.len()
+        );
+
+        (Ok(bundled), bundle.files)
+    }
+
+    //blob of the file in the tree, none if it is missing
+    pub fn get_file_id(&self, file_path: &str) -> Option<Oid> {
+        self.tree
+            .get_path(Path::new(file_path))
+            .ok()
+            .map(|entry| entry.id())
+    }
+
+    fn process(
+        &mut self,
+        val: &mut Value,
+        base_path: &str,
+        bundle: &mut Bundle,
+    ) -> Result<(), String> {
+        let reference = match val.get("$ref") {
+            Some(Value::String(reference)) => Some(reference.to_string()),
+            _ => None,
+        };
+
+        match reference {
+            Some(reference) => self.process_ref(val, &reference, base_path, bundle),
+            None => {
+                match val {
+                    Value::Mapping(mapping) => {
+                        for (_, val) in mapping.iter_mut() {
+                            self.process(val, base_path, bundle)?;
+                        }
+                    }
+                    Value::Sequence(sequence) => {
+                        for val in sequence.iter_mut() {
+                            self.process(val, base_path, bundle)?;
+                        }
+                    }
+                    _ => {}
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn process_ref(
+        &mut self,
+        val: &mut Value,
+        reference: &str,
+        base_path: &str,
+        bundle: &mut Bundle,
+    ) -> Result<(), String> {
+        if reference.contains("://") {
+            debug!("Remote ref [{}] is not followed", reference);
+            return Ok(());
+        }
+
+        let (file, pointer) = match reference.find('#') {
+            Some(idx) => (&reference[..idx], &reference[idx + 1..]),
+            None => (reference, ""),
+        };
+        let file_path = if file.is_empty() {
+            String::from(base_path)
+        } else {
+            let parent = Path::new(base_path)
+                .parent()
+                .unwrap_or_else(|| Path::new(""));
+            normalize_path(&parent.join(file))
+        };
+
+        //internal ref of the root document, nothing to do
+        if file_path == bundle.root_path {
+            *val = internal_ref(pointer);
+            return Ok(());
+        }
+
+        if !bundle.files.contains(&file_path) {
+            bundle.files.push(file_path.clone());
+        }
+        let key = (file_path.clone(), String::from(pointer));
+        if let Some(internal) = bundle.hoisted.get(&key) {
+            *val = internal_ref(internal);
+            return Ok(());
+        }
+
+        match get_component_location(pointer) {
+            Some(mut location) => {
+                //register the hoisted ref before processing its content so that recursive schemas end up on the internal ref
+                let name = location.pop().unwrap_or_default();
+                let name = get_available_name(
+                    self.documents.get(&bundle.root_path),
+                    bundle,
+                    &location,
+                    &name,
+                );
+                location.push(name);
+                let internal = format!("/{}", location.join("/"));
+                bundle.hoisted.insert(key, internal.clone());
+
+                let mut target = self.get_fragment(&file_path, pointer)?;
+                self.process(&mut target, &file_path, bundle)?;
+                bundle.additions.push((location, target));
+
+                *val = internal_ref(&internal);
+            }
+            None => {
+                if bundle.stack.contains(&key) {
+                    let chain: Vec<String> = bundle
+                        .stack
+                        .iter()
+                        .map(|(file, pointer)| format!("{}#{}", file, pointer))
+                        .collect();
+                    return Err(format!(
+                        "Cycle detected while resolving [{}] - [{}]",
+                        reference,
+                        chain.join(" -> ")
+                    ));
+                }
+
+                bundle.stack.push(key);
+                let mut target = self.get_fragment(&file_path, pointer)?;
+                self.process(&mut target, &file_path, bundle)?;
+                bundle.stack.pop();
+
+                *val = target;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_fragment(&mut self, file_path: &str, pointer: &str) -> Result<Value, String> {
+        let doc = self.get_document(file_path)?;
+        match get_pointer(doc, pointer) {
+            Some(val) => Ok(val.clone()),
+            None => Err(format!(
+                "Unable to find [{}] in file [{}]",
+                pointer, file_path
+            )),
+        }
+    }
+
+    fn get_document(&mut self, file_path: &str) -> Result<&Value, String> {
+        if !self.documents.contains_key(file_path) {
+            debug!("Loading referenced file [{}]", file_path);
+            let entry = self
+                .tree
+                .get_path(Path::new(file_path))
+                .map_err(|why| format!("Unable to find file [{}] - [{}]", file_path, why))?;
+            let blob = entry
+                .to_object(self.repo)
+                .and_then(|object| object.peel_to_blob())
+                .map_err(|why| format!("Unable to get Blob [{}] - [{}]", file_path, why))?;
+            let doc: Value = serde_yaml::from_slice(blob.content())
+                .map_err(|why| format!("Unable to parse file [{}] - [{}]", file_path, why))?;
+            self.documents.insert(String::from(file_path), doc);
+        }
+
+        Ok(&self.documents[file_path])
+    }
+}
+
+fn internal_ref(pointer: &str) -> Value {
+    let mut mapping = Mapping::new();
+    mapping.insert(
+        Value::String(String::from("$ref")),
+        Value::String(format!("#{}", pointer)),
+    );
+
+    Value::Mapping(mapping)
+}
+
+//#/components/schemas/Problem -> [components, schemas, Problem], #/definitions/Problem -> [definitions, Problem]
+fn get_component_location(pointer: &str) -> Option<Vec<String>> {
+    let segments: Vec<String> = pointer
+        .trim_start_matches('/')
+        .split('/')
+        .map(unescape)
+        .collect();
+
+    match segments
+        .iter()
+        .map(|val| val.as_str())
+        .collect::<Vec<&str>>()
+        .as_slice()
+    {
+        ["components", _, _] => Some(segments),
+        ["definitions", _] | ["parameters", _] | ["responses", _] => Some(segments),
+        _ => None,
+    }
+}
+
+fn get_available_name(
+    root: Option<&Value>,
+    bundle: &Bundle,
+    location: &[String],
+    name: &str,
+) -> String {
+    let is_used = |candidate: &str| {
+        let mut path = location.to_vec();
+        path.push(String::from(candidate));
+        let in_root = root
+            .and_then(|root| get_pointer(root, &format!("/{}", path.join("/"))))
+            .is_some();
+        in_root
+            || bundle
+                .additions
+                .iter()
+                .any(|(location, _)| *location == path)
+            || bundle
+                .hoisted
+                .values()
+                .any(|val| *val == format!("/{}", path.join("/")))
+    };
+
+    let mut candidate = String::from(name);
+    let mut idx = 1;
+    while is_used(&candidate) {
+        candidate = format!("{}_{}", name, idx);
+        idx += 1;
+    }
+
+    candidate
+}
+
+pub fn get_pointer<'v>(doc: &'v Value, pointer: &str) -> Option<&'v Value> {
+    let pointer = pointer.trim_start_matches('/');
+    if pointer.is_empty() {
+        return Some(doc);
+    }
+
+    let mut current = doc;
+    for segment in pointer.split('/').map(unescape) {
+        current = match current {
+            Value::Mapping(mapping) => {
+                match mapping.get(&Value::String(segment.clone())) {
+                    Some(val) => val,
+                    //keys like response codes can be numbers
+                    None => mapping.get(&Value::Number(segment.parse::<u64>().ok()?.into()))?,
+                }
+            }
+            Value::Sequence(sequence) => sequence.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    Some(current)
+}
+
+fn insert_at(doc: &mut Value, location: &[String], val: Value) {
+    let mut current = doc;
+    for segment in &location[..location.len() - 1] {
+        let mapping = match current {
+            Value::Mapping(mapping) => mapping,
+            _ => return,
+        };
+        let key = Value::String(segment.clone());
+        if !mapping.contains_key(&key) {
+            mapping.insert(key.clone(), Value::Mapping(Mapping::new()));
+        }
+        current = mapping.get_mut(&key).unwrap();
+    }
+    if let Value::Mapping(mapping) = current {
+        mapping.insert(Value::String(location[location.len() - 1].clone()), val);
+    }
+}
+
+fn normalize_path(path: &Path) -> String {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+            Component::Normal(val) => normalized.push(val),
+        }
+    }
+
+    normalized
+        .to_string_lossy()
+        .replace(std::path::MAIN_SEPARATOR, "/")
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use super::pointer::{escape, to_string, unescape, METHODS};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ReviewObject {
+    Operation,
+    ZallyIgnore,
+    Audience,
+    Permission,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReviewItem {
+    //ADDED, REMOVED or CHANGED
+    pub typ: String,
+    pub object: ReviewObject,
+    //json pointer in the new revision (in the old one if removed)
+    pub pointer: String,
+    pub value: String,
+}
+
+/**
+ * YAML aware diff of two revisions of a spec (None if the file has been added or deleted),
+ * only the items relevant for a review are kept: operations, x-zally-ignore entries, x-audience and x-has-authority
+ */
+pub fn diff_specs(before: Option<&Value>, after: Option<&Value>) -> Vec<ReviewItem> {
+    let null = Value::Null;
+    let before = before.unwrap_or(&null);
+    let after = after.unwrap_or(&null);
+
+    let mut before_items = Vec::new();
+    collect_items(before, "", &mut before_items);
+    let mut after_items = Vec::new();
+    collect_items(after, "", &mut after_items);
+
+    let mut diffs = Vec::new();
+    for (pointer, object, val) in before_items.iter() {
+        match after_items
+            .iter()
+            .find(|(after_pointer, _, _)| after_pointer == pointer)
+        {
+            Some((_, _, after_val)) => {
+                if *object == ReviewObject::ZallyIgnore {
+                    diff_zally_ignore(pointer, val, after_val, &mut diffs);
+                } else if val != after_val {
+                    diffs.push(new_item("CHANGED", object, pointer, after_val));
+                }
+            }
+            None => {
+                if *object == ReviewObject::ZallyIgnore {
+                    diff_zally_ignore(pointer, val, &Value::Null, &mut diffs);
+                } else {
+                    diffs.push(new_item("REMOVED", object, pointer, val));
+                }
+            }
+        }
+    }
+    for (pointer, object, val) in after_items.iter() {
+        if before_items
+            .iter()
+            .any(|(before_pointer, _, _)| before_pointer == pointer)
+        {
+            continue;
+        }
+        if *object == ReviewObject::ZallyIgnore {
+            diff_zally_ignore(pointer, &Value::Null, val, &mut diffs);
+        } else {
+            diffs.push(new_item("ADDED", object, pointer, val));
+        }
+    }
+
+    diffs
+}
+
+fn new_item(typ: &str, object: &ReviewObject, pointer: &str, val: &Value) -> ReviewItem {
+    let value = match object {
+        //operations are displayed as METHOD /path, their content is summarized by the other items and the verdict
+        ReviewObject::Operation => {
+            let segments: Vec<String> = pointer
+                .trim_start_matches("/paths/")
+                .rsplitn(2, '/')
+                .map(unescape)
+                .collect();
+            format!(
+                "{} {}",
+                segments.first().cloned().unwrap_or_default().to_uppercase(),
+                segments.get(1).cloned().unwrap_or_default()
+            )
+        }
+        _ => to_string(val),
+    };
+
+    ReviewItem {
+        typ: String::from(typ),
+        object: object.clone(),
+        pointer: String::from(pointer),
+        value: value,
+    }
+}
+
+//x-zally-ignore is a list of rules, the diff is done per rule
+fn diff_zally_ignore(pointer: &str, before: &Value, after: &Value, diffs: &mut Vec<ReviewItem>) {
+    let empty = Vec::new();
+    let before = before.as_sequence().unwrap_or(&empty);
+    let after = after.as_sequence().unwrap_or(&empty);
+
+    for (idx, rule) in before.iter().enumerate() {
+        if !after.contains(rule) {
+            let pointer = format!("{}/{}", pointer, idx);
+            diffs.push(new_item(
+                "REMOVED",
+                &ReviewObject::ZallyIgnore,
+                &pointer,
+                rule,
+            ));
+        }
+    }
+    for (idx, rule) in after.iter().enumerate() {
+        if !before.contains(rule) {
+            let pointer = format!("{}/{}", pointer, idx);
+            diffs.push(new_item(
+                "ADDED",
+                &ReviewObject::ZallyIgnore,
+                &pointer,
+                rule,
+            ));
+        }
+    }
+}
+
+fn collect_items(val: &Value, pointer: &str, items: &mut Vec<(String, ReviewObject, Value)>) {
+    match val {
+        Value::Mapping(mapping) => {
+            for (key, val) in mapping.iter() {
+                let key = to_string(key);
+                let child_pointer = format!("{}/{}", pointer, escape(&key));
+                let object = match key.as_str() {
+                    "x-zally-ignore" => Some(ReviewObject::ZallyIgnore),
+                    "x-audience" => Some(ReviewObject::Audience),
+                    "x-has-authority" => Some(ReviewObject::Permission),
+                    method if is_operation(pointer, method) => Some(ReviewObject::Operation),
+                    _ => None,
+                };
+                if let Some(object) = object {
+                    items.push((child_pointer.clone(), object, val.clone()));
+                }
+                collect_items(val, &child_pointer, items);
+            }
+        }
+        Value::Sequence(sequence) => {
+            for (idx, val) in sequence.iter().enumerate() {
+                collect_items(val, &format!("{}/{}", pointer, idx), items);
+            }
+        }
+        _ => {}
+    }
+}
+
+//operations are /paths/{path}/{method}
+fn is_operation(parent_pointer: &str, key: &str) -> bool {
+    METHODS.contains(&key)
+        && parent_pointer.starts_with("/paths/")
+        && parent_pointer.matches('/').count() == 2
+}
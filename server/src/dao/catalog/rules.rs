@@ -0,0 +1,266 @@
+use log::{debug, warn};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::super::super::settings::CatalogSource;
+
+const DEFAULT_RULES: &str = include_str!("zally-rules.yaml");
+const RULES_FILE: &str = "zally-rules.yaml";
+const UNKNOWN_SEVERITY: &str = "UNKNOWN";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Rule {
+    //numbers (e.g. 134) and strings (e.g. M10) are both kept as string
+    #[serde(deserialize_with = "deserialize_id")]
+    pub id: String,
+    pub title: String,
+    pub severity: String,
+    pub link: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RuleStats {
+    pub id: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SeverityStats {
+    pub severity: String,
+    pub count: usize,
+    pub rules: Vec<RuleStats>,
+}
+
+fn deserialize_id<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let id = serde_yaml::Value::deserialize(deserializer)?;
+    Ok(to_rule_id(&id))
+}
+
+pub fn to_rule_id(val: &serde_yaml::Value) -> String {
+    match val {
+        serde_yaml::Value::String(val) => val.clone(),
+        serde_yaml::Value::Number(val) => val.to_string(),
+        val => format!("{:?}", val),
+    }
+}
+
+pub fn get_default_rules() -> Vec<Rule> {
+    serde_yaml::from_str(DEFAULT_RULES).unwrap_or_else(|why| {
+        warn!("Unable to parse the default rules - reason [{}]", why);
+        Vec::new()
+    })
+}
+
+/**
+ * Rules defined at the root of the first source having a rules file, or the default (bundled) ones
+ */
+pub fn get_rules(sources: &[CatalogSource]) -> Vec<Rule> {
+    for source in sources {
+        let rules_path = Path::new(&source.path).join(RULES_FILE);
+        let content = match std::fs::read_to_string(&rules_path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        match serde_yaml::from_str(&content) {
+            Ok(rules) => {
+                debug!(
+                    "Loaded rules of source [{}] from [{:?}]",
+                    source.name, rules_path
+                );
+                return rules;
+            }
+            Err(why) => {
+                warn!(
+                    "Unable to parse rules [{:?}], using default ones - reason [{}]",
+                    rules_path, why
+                );
+                return get_default_rules();
+            }
+        }
+    }
+
+    debug!("No rules file in the sources, using default ones");
+    get_default_rules()
+}
+
+pub fn get_rule(rules: &[Rule], id: &str) -> Rule {
+    match rules.iter().find(|rule| rule.id == id) {
+        Some(rule) => rule.clone(),
+        None => Rule {
+            id: String::from(id),
+            title: String::from("Unknown rule"),
+            severity: String::from(UNKNOWN_SEVERITY),
+            link: None,
+        },
+    }
+}
+
+/**
+ * Name and group per severity the counts per rule id, all_rules to also get the rules having no count
+ */
+pub fn group_per_severity(
+    rules: &[Rule],
+    stats: &HashMap<String, usize>,
+    all_rules: bool,
+) -> Vec<SeverityStats> {
+    let mut ids: Vec<String> = stats.keys().cloned().collect();
+    if all_rules {
+        ids.extend(
+            rules
+                .iter()
+                .filter(|rule| !stats.contains_key(&rule.id))
+                .map(|rule| rule.id.clone()),
+        );
+    }
+    ids.sort();
+
+    let mut groups: Vec<SeverityStats> = Vec::new();
+    for id in ids {
+        let rule = get_rule(rules, &id);
+        let severity = rule.severity;
+        let count = stats.get(&id).cloned().unwrap_or(0);
+        let stats = RuleStats {
+            id: rule.id,
+            title: rule.title,
+            link: rule.link,
+            count: count,
+        };
+        match groups.iter_mut().find(|group| group.severity == severity) {
+            Some(group) => {
+                group.count += count;
+                group.rules.push(stats);
+            }
+            None => groups.push(SeverityStats {
+                severity: severity,
+                count: count,
+                rules: vec![stats],
+            }),
+        }
+    }
+    groups.sort_by_key(|group| get_severity_rank(&group.severity));
+
+    groups
+}
+
+fn get_severity_rank(severity: &str) -> usize {
+    ["MUST", "SHOULD", "MAY", "HINT"]
+        .iter()
+        .position(|val| val.eq_ignore_ascii_case(severity))
+        .unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_source(name: &str, path: &Path) -> CatalogSource {
+        CatalogSource {
+            name: String::from(name),
+            path: path.to_string_lossy().to_string(),
+            dir: path.to_string_lossy().to_string(),
+            url: None,
+            credentials: None,
+            patterns: Vec::new(),
+            branch: None,
+            domain_prefix: None,
+            domain_rules_path: None,
+        }
+    }
+
+    #[test]
+    fn test_rule_ids() {
+        let rules: Vec<Rule> = serde_yaml::from_str(
+            "
+        - id: 134
+          title: Avoid Link in Header Rule
+          severity: MUST
+        - id: M10
+          title: Use camelCase for query parameters
+          severity: SHOULD
+          link: https://example.com/M10
+        ",
+        )
+        .unwrap();
+
+        assert_eq!(rules[0].id, "134");
+        assert_eq!(rules[1].id, "M10");
+        assert_eq!(
+            get_rule(&rules, "M10").title,
+            "Use camelCase for query parameters"
+        );
+
+        let unknown = get_rule(&rules, "999");
+        assert_eq!(unknown.id, "999");
+        assert_eq!(unknown.severity, UNKNOWN_SEVERITY);
+    }
+
+    #[test]
+    fn test_get_rules() {
+        //the module is compiled several times, hence tests run concurrently
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-rules-{}-{}",
+            module_path!().replace("::", "-"),
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        for name in ["pets", "stores", "broken"].iter() {
+            std::fs::create_dir_all(dir.join(name)).unwrap();
+        }
+        std::fs::write(
+            dir.join("stores").join(RULES_FILE),
+            "- id: M10\n  title: Use camelCase for query parameters\n  severity: SHOULD\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("broken").join(RULES_FILE), "- id: [").unwrap();
+        let pets = get_source("pets", &dir.join("pets"));
+        let stores = get_source("stores", &dir.join("stores"));
+        let broken = get_source("broken", &dir.join("broken"));
+
+        //the rules of a source other than the first one are read
+        let rules = get_rules(&[pets.clone(), stores.clone()]);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].id, "M10");
+
+        //no rules file, or one that does not parse
+        assert_eq!(get_rules(&[pets]), get_default_rules());
+        assert_eq!(get_rules(&[broken, stores]), get_default_rules());
+        assert!(!get_default_rules().is_empty());
+    }
+
+    #[test]
+    fn test_group_per_severity() {
+        let rule = |id: &str, severity: &str| Rule {
+            id: String::from(id),
+            title: format!("Rule {}", id),
+            severity: String::from(severity),
+            link: None,
+        };
+        let rules = vec![rule("1", "SHOULD"), rule("2", "MUST"), rule("3", "MUST")];
+        let mut stats = HashMap::new();
+        stats.insert(String::from("1"), 2);
+        stats.insert(String::from("2"), 3);
+        stats.insert(String::from("9"), 1);
+
+        let groups = group_per_severity(&rules, &stats, false);
+        let severities: Vec<&str> = groups.iter().map(|group| group.severity.as_str()).collect();
+        assert_eq!(severities, vec!["MUST", "SHOULD", UNKNOWN_SEVERITY]);
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(groups[0].rules.len(), 1);
+        assert_eq!(groups[2].rules[0].title, "Unknown rule");
+
+        //rules having no count are listed along
+        let groups = group_per_severity(&rules, &stats, true);
+        assert_eq!(groups[0].count, 3);
+        assert_eq!(groups[0].rules.len(), 2);
+        assert_eq!(groups[0].rules[1].id, "3");
+        assert_eq!(groups[0].rules[1].count, 0);
+    }
+}
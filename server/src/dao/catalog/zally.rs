@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::Marker;
+
+use super::super::repo_waivers::WaiverItem;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ZallyIgnoreItem {
+    //None for the ignores set at the root of the spec
+    pub path: Option<String>,
+    //set for the ignores set on an operation
+    pub method: Option<String>,
+    pub rule: String,
+    //trailing comment of the entry, e.g. - 120 # Rest maturity evolving
+    pub rationale: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum WaiverStatus {
+    WAIVED,
+    EXPIRED,
+    UNREGISTERED,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IgnoreCheck {
+    //stable id of the spec, the key of its waivers
+    pub spec_id: String,
+    pub spec: String,
+    pub ignore: ZallyIgnoreItem,
+    pub status: WaiverStatus,
+}
+
+enum Node {
+    Mapping { key: Option<String> },
+    Sequence,
+}
+
+//yaml_rust drops the comments, events markers are used to get back to the line of each entry
+struct IgnoresReceiver<'a> {
+    lines: Vec<&'a str>,
+    nodes: Vec<Node>,
+    ignores: Vec<ZallyIgnoreItem>,
+}
+
+impl<'a> MarkedEventReceiver for IgnoresReceiver<'a> {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        match ev {
+            Event::MappingStart(_) => self.nodes.push(Node::Mapping { key: None }),
+            Event::SequenceStart(_) => self.nodes.push(Node::Sequence),
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.nodes.pop();
+                self.on_value();
+            }
+            Event::Alias(_) => self.on_value(),
+            Event::Scalar(val, ..) => match self.nodes.last_mut() {
+                Some(Node::Mapping { key }) if key.is_none() => *key = Some(val),
+                Some(Node::Mapping { .. }) => self.on_value(),
+                Some(Node::Sequence) => self.on_entry(val, &mark),
+                None => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+impl<'a> IgnoresReceiver<'a> {
+    //a value has been read, the parent mapping now expects a key
+    fn on_value(&mut self) {
+        if let Some(Node::Mapping { key }) = self.nodes.last_mut() {
+            *key = None;
+        }
+    }
+
+    fn on_entry(&mut self, rule: String, mark: &Marker) {
+        //entries of a sequence directly under the x-zally-ignore key
+        let keys: Vec<&str> = match self.nodes.len().checked_sub(2) {
+            Some(idx) if !matches!(self.nodes[idx + 1], Node::Sequence) => return,
+            Some(idx) => self.nodes[..=idx]
+                .iter()
+                .filter_map(|node| match node {
+                    Node::Mapping { key } => key.as_deref(),
+                    Node::Sequence => None,
+                })
+                .collect(),
+            None => return,
+        };
+        let (path, method) = match keys.as_slice() {
+            ["x-zally-ignore"] => (None, None),
+            ["paths", path, "x-zally-ignore"] => (Some(path.to_string()), None),
+            ["paths", path, method, "x-zally-ignore"] => {
+                (Some(path.to_string()), Some(method.to_uppercase()))
+            }
+            _ => return,
+        };
+
+        let line = self.lines.get(mark.line().saturating_sub(1)).unwrap_or(&"");
+        self.ignores.push(ZallyIgnoreItem {
+            path: path,
+            method: method,
+            rule: rule,
+            rationale: get_trailing_comment(line, mark.col()),
+        });
+    }
+}
+
+/**
+ * All the x-zally-ignore entries of a spec (global, per path and per operation) along with their rationale
+ */
+pub fn get_ignores(spec: &str) -> Result<Vec<ZallyIgnoreItem>, String> {
+    let mut receiver = IgnoresReceiver {
+        lines: spec.lines().collect(),
+        nodes: Vec::new(),
+        ignores: Vec::new(),
+    };
+    Parser::new(spec.chars())
+        .load(&mut receiver, false)
+        .map_err(|why| why.to_string())?;
+
+    Ok(receiver.ignores)
+}
+
+/**
+ * Cross check an x-zally-ignore entry of a spec against the waivers registry, waivers being keyed on the stable id of the spec.
+ * A waiver without path covers the whole spec, revoked waivers are not taken into account
+ */
+pub fn check_ignore(
+    spec_id: &str,
+    spec: &str,
+    ignore: ZallyIgnoreItem,
+    waivers: &[WaiverItem],
+    now: DateTime<Utc>,
+) -> IgnoreCheck {
+    let waivers: Vec<&WaiverItem> = waivers
+        .iter()
+        .filter(|waiver| waiver.revoked_date_time.is_none())
+        .filter(|waiver| waiver.spec == spec_id && waiver.rule == ignore.rule)
+        .filter(|waiver| waiver.path.is_none() || waiver.path == ignore.path)
+        .collect();
+
+    let status = if waivers.iter().any(|waiver| waiver.expiry_date_time > now) {
+        WaiverStatus::WAIVED
+    } else if !waivers.is_empty() {
+        WaiverStatus::EXPIRED
+    } else {
+        WaiverStatus::UNREGISTERED
+    };
+
+    IgnoreCheck {
+        spec_id: String::from(spec_id),
+        spec: String::from(spec),
+        ignore: ignore,
+        status: status,
+    }
+}
+
+//a comment starts with a # preceded by a blank, outside of a quoted string
+fn get_trailing_comment(line: &str, from: usize) -> Option<String> {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (idx, current) in line.char_indices().skip(from) {
+        match (quote, current) {
+            (None, '\'') | (None, '"') => quote = Some(current),
+            (Some(val), _) if val == current => quote = None,
+            (None, '#') if previous.is_whitespace() => {
+                let comment = line[idx + 1..].trim();
+                return match comment.is_empty() {
+                    true => None,
+                    false => Some(String::from(comment)),
+                };
+            }
+            _ => {}
+        }
+        previous = current;
+    }
+
+    None
+}
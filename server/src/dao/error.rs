@@ -0,0 +1,366 @@
+use actix_web::dev::HttpResponseBuilder;
+use actix_web::error::{BlockingError, JsonPayloadError, QueryPayloadError};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+use super::catalog::git::GitError;
+use super::catalog::CatalogError;
+
+pub type Result<T> = std::result::Result<T, ApiError>;
+
+//a field of a request payload (or path, query) that is not valid
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/**
+ * Errors of the catalog, rendered by the HTTP API as RFC 7807 problem details (application/problem+json)
+ */
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound { message: String },
+    Validation { errors: Vec<FieldError> },
+    //e.g. a name that is already used
+    Conflict { message: String },
+    //a spec that cannot be parsed, or that the operation does not apply to
+    Unprocessable { message: String },
+    //Stash, the git remote of a source...
+    Upstream { service: String, message: String },
+    Storage { message: String },
+}
+
+impl ApiError {
+    pub fn not_found(message: String) -> ApiError {
+        ApiError::NotFound { message: message }
+    }
+
+    //a lookup returning no row is reported along with what has been looked for
+    pub fn from_lookup(why: rusqlite::Error, message: String) -> ApiError {
+        match why {
+            rusqlite::Error::QueryReturnedNoRows => ApiError::NotFound { message: message },
+            why => ApiError::from(why),
+        }
+    }
+
+    pub fn invalid(field: &str, message: &str) -> ApiError {
+        ApiError::Validation {
+            errors: vec![FieldError {
+                field: String::from(field),
+                message: String::from(message),
+            }],
+        }
+    }
+
+    fn problem_type(&self) -> &'static str {
+        match self {
+            ApiError::NotFound { .. } => "/problems/not-found",
+            ApiError::Validation { .. } => "/problems/validation",
+            ApiError::Conflict { .. } => "/problems/conflict",
+            ApiError::Unprocessable { .. } => "/problems/unprocessable",
+            ApiError::Upstream { .. } => "/problems/upstream",
+            ApiError::Storage { .. } => "/problems/storage",
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::NotFound { message } => write!(f, "{}", message),
+            ApiError::Validation { errors } => write!(
+                f,
+                "Invalid request - {}",
+                errors
+                    .iter()
+                    .map(|error| format!("[{}] {}", error.field, error.message))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            ApiError::Conflict { message } => write!(f, "{}", message),
+            ApiError::Unprocessable { message } => write!(f, "{}", message),
+            ApiError::Upstream { service, message } => {
+                write!(f, "Call to [{}] failed - [{}]", service, message)
+            }
+            ApiError::Storage { message } => write!(f, "Storage failure - [{}]", message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+//RFC 7807 problem details, errors being the field level messages of a validation problem
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub errors: Vec<FieldError>,
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound { .. } => StatusCode::NOT_FOUND,
+            ApiError::Validation { .. } => StatusCode::BAD_REQUEST,
+            ApiError::Conflict { .. } => StatusCode::CONFLICT,
+            ApiError::Unprocessable { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Upstream { .. } => StatusCode::BAD_GATEWAY,
+            ApiError::Storage { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let status = self.status_code();
+        let problem = Problem {
+            problem_type: String::from(self.problem_type()),
+            title: String::from(status.canonical_reason().unwrap_or("Error")),
+            status: status.as_u16(),
+            detail: self.to_string(),
+            errors: match self {
+                ApiError::Validation { errors } => errors.clone(),
+                _ => Vec::new(),
+            },
+        };
+
+        HttpResponseBuilder::new(status)
+            .content_type("application/problem+json")
+            .json(problem)
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(why: rusqlite::Error) -> ApiError {
+        match why {
+            rusqlite::Error::QueryReturnedNoRows => ApiError::NotFound {
+                message: String::from("No such record"),
+            },
+            rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error {
+                    code: rusqlite::ErrorCode::ConstraintViolation,
+                    ..
+                },
+                message,
+            ) => ApiError::Conflict {
+                message: message.unwrap_or_else(|| String::from("Constraint violation")),
+            },
+            why => ApiError::Storage {
+                message: why.to_string(),
+            },
+        }
+    }
+}
+
+impl From<GitError> for ApiError {
+    fn from(why: GitError) -> ApiError {
+        ApiError::Upstream {
+            service: String::from("git"),
+            message: why.to_string(),
+        }
+    }
+}
+
+impl From<CatalogError> for ApiError {
+    fn from(why: CatalogError) -> ApiError {
+        match why {
+            CatalogError::NotFound { message } => ApiError::NotFound { message: message },
+            CatalogError::Git { message } => ApiError::Upstream {
+                service: String::from("git"),
+                message: message,
+            },
+            //the spec is known, it is its content the operation cannot be done on
+            why @ CatalogError::Spec { .. } => ApiError::Unprocessable {
+                message: why.to_string(),
+            },
+            CatalogError::Unsupported { message } => ApiError::Unprocessable { message: message },
+        }
+    }
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(why: reqwest::Error) -> ApiError {
+        ApiError::Upstream {
+            service: String::from("stash"),
+            message: why.to_string(),
+        }
+    }
+}
+
+/**
+ * Gathers the field level messages of a request, to report all of them at once
+ */
+#[derive(Default)]
+pub struct Validator {
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    pub fn check(mut self, valid: bool, field: &str, message: &str) -> Validator {
+        if !valid {
+            self.errors.push(FieldError {
+                field: String::from(field),
+                message: String::from(message),
+            });
+        }
+        self
+    }
+
+    pub fn not_blank(self, value: &str, field: &str) -> Validator {
+        self.check(!value.trim().is_empty(), field, "must not be blank")
+    }
+
+    pub fn validate(self) -> Result<()> {
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(ApiError::Validation {
+                errors: self.errors,
+            }),
+        }
+    }
+}
+
+/**
+ * Run f on the thread pool of actix rather than on the worker serving the request,
+ * e.g. to list the catalog, which opens the repos and parses the changed specs
+ */
+pub async fn blocking<T, E>(
+    f: impl FnOnce() -> std::result::Result<T, E> + Send + 'static,
+) -> Result<T>
+where
+    T: Send + 'static,
+    E: Into<ApiError> + Send + fmt::Debug + 'static,
+{
+    web::block(f).await.map_err(|why| match why {
+        BlockingError::Error(why) => why.into(),
+        BlockingError::Canceled => ApiError::Storage {
+            message: String::from("Blocking call canceled"),
+        },
+    })
+}
+
+pub fn parse_uuid(value: &str, field: &str) -> Result<uuid::Uuid> {
+    uuid::Uuid::parse_str(value).map_err(|_| ApiError::invalid(field, "must be a UUID"))
+}
+
+//payloads that cannot be deserialized are reported as a validation problem of the body
+pub fn json_error_handler(why: JsonPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::invalid("body", &why.to_string()).into()
+}
+
+pub fn query_error_handler(why: QueryPayloadError, _req: &HttpRequest) -> actix_web::Error {
+    ApiError::invalid("query", &why.to_string()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_status() {
+        assert_eq!(
+            ApiError::from(rusqlite::Error::QueryReturnedNoRows).status_code(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            ApiError::invalid("name", "must not be blank").status_code(),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            ApiError::Conflict {
+                message: String::from("Domain [a] already exists")
+            }
+            .status_code(),
+            StatusCode::CONFLICT
+        );
+        assert_eq!(
+            ApiError::from(GitError::NoRemote {
+                source: String::from("default")
+            })
+            .status_code(),
+            StatusCode::BAD_GATEWAY
+        );
+        assert_eq!(
+            ApiError::from(CatalogError::Git {
+                message: String::from("Unable to get HEAD tree")
+            })
+            .status_code(),
+            StatusCode::BAD_GATEWAY
+        );
+        assert_eq!(
+            ApiError::from(CatalogError::Unsupported {
+                message: String::from("Only openapi specifications can be linted")
+            })
+            .status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            ApiError::from(CatalogError::Spec {
+                id: String::from("a"),
+                message: String::from("Unable to parse the spec")
+            })
+            .status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+        assert_eq!(
+            ApiError::from(CatalogError::NotFound {
+                message: String::from("Unknown spec [a]")
+            })
+            .status_code(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    #[test]
+    fn test_constraint_violation_is_conflict() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute(
+            "CREATE TABLE envs (name TEXT NOT NULL UNIQUE)",
+            rusqlite::NO_PARAMS,
+        )
+        .unwrap();
+        let insert = || {
+            conn.execute(
+                "INSERT INTO envs (name) VALUES ('dev')",
+                rusqlite::NO_PARAMS,
+            )
+            .map_err(ApiError::from)
+        };
+
+        assert!(insert().is_ok());
+        match insert() {
+            Err(ApiError::Conflict { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other),
+        }
+    }
+
+    #[test]
+    fn test_validator() {
+        assert!(Validator::new().not_blank("dev", "name").validate().is_ok());
+        match Validator::new()
+            .not_blank(" ", "name")
+            .check(false, "owner", "must be an email")
+            .validate()
+        {
+            Err(ApiError::Validation { errors }) => assert_eq!(
+                errors
+                    .iter()
+                    .map(|error| error.field.as_str())
+                    .collect::<Vec<&str>>(),
+                vec!["name", "owner"]
+            ),
+            other => panic!("unexpected result [{:?}]", other),
+        }
+        assert!(parse_uuid("not-a-uuid", "id").is_err());
+    }
+}
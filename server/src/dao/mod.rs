@@ -1,6 +1,8 @@
 pub mod catalog;
+pub mod error;
 pub mod repo_apis;
 pub mod repo_deployments;
 pub mod repo_domains;
 pub mod repo_envs;
 pub mod repo_metrics;
+pub mod repo_waivers;
@@ -3,14 +3,23 @@ extern crate rusqlite;
 extern crate time;
 extern crate uuid;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use rusqlite::NO_PARAMS;
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
 
-use log::{info, warn};
+use super::catalog::domain_rules;
+use super::catalog::reconciliation::{
+    self, Proposal, Reconciliation, ReconciliationAction, RegisteredApi,
+};
+use super::catalog::{AsyncSpecItem, SpecItem};
+use super::error::{ApiError, Result};
+use super::repo_domains::DomainItem;
 
+use log::{error, info, warn};
+
+use std::collections::HashMap;
 use std::sync::Once;
 
 #[derive(Debug)]
@@ -34,6 +43,16 @@ pub struct StatusItem {
     pub status: String,
 }
 
+#[derive(Debug)]
+pub struct StatusHistoryItem {
+    pub status: String,
+    pub actor: String,
+    pub comment: Option<String>,
+    pub start_date_time: DateTime<Utc>,
+    //not set for the current status
+    pub end_date_time: Option<DateTime<Utc>>,
+}
+
 static INIT_DB: Once = Once::new();
 
 fn get_init_db(rusqlite: &String) -> Result<String> {
@@ -86,7 +105,7 @@ fn get_init_db(rusqlite: &String) -> Result<String> {
 }
 
 pub fn list_all_apis(config: &super::super::settings::Database) -> Result<Vec<ApiItem>> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
+    let db_path = get_init_db(&config.rusqlite_path)?;
     let conn = Connection::open(db_path)?;
 
     let mut stmt = conn.prepare("SELECT id, name, domain_id, tier_id FROM apis")?;
@@ -132,7 +151,7 @@ pub fn list_all_apis(config: &super::super::settings::Database) -> Result<Vec<Ap
 }
 
 fn get_related_tier(config: &super::super::settings::Database, tier_id: Uuid) -> Result<TierItem> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
+    let db_path = get_init_db(&config.rusqlite_path)?;
     let conn = Connection::open(db_path)?;
 
     let mut stmt = conn.prepare("SELECT id, name FROM tiers WHERE id = ?1")?; //ORDER BY end_date_time DESC limit 1 //start_date_time, end_date_time
@@ -147,10 +166,13 @@ fn get_related_tier(config: &super::super::settings::Database, tier_id: Uuid) ->
 }
 
 fn get_last_status(config: &super::super::settings::Database, api_id: Uuid) -> Result<StatusItem> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
+    let db_path = get_init_db(&config.rusqlite_path)?;
     let conn = Connection::open(db_path)?;
 
-    let mut stmt = conn.prepare("SELECT api_id, status FROM status WHERE api_id = ?1")?; //ORDER BY end_date_time DESC limit 1 //start_date_time, end_date_time
+    //the current status is the one not ended yet
+    let mut stmt = conn.prepare(
+        "SELECT api_id, status FROM status WHERE api_id = ?1 AND end_date_time IS NULL ORDER BY status_id DESC LIMIT 1",
+    )?;
     let row = stmt.query_row(params![api_id], |row| {
         Ok(StatusItem {
             api_id: row.get(0)?,
@@ -161,62 +183,316 @@ fn get_last_status(config: &super::super::settings::Database, api_id: Uuid) -> R
     Ok(row)
 }
 
+//the api is not created if one of its specs cannot be linked
 pub fn add_api(
     config: &super::super::settings::Database,
     name: &str,
     domain_id: &Uuid,
-) -> Result<()> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
-    let conn = Connection::open(db_path)?;
+    spec_ids: &[String],
+) -> Result<Uuid> {
+    let db_path = get_init_db(&config.rusqlite_path)?;
+    let mut conn = Connection::open(db_path)?;
 
     let id = Uuid::new_v4();
-    conn.execute(
+    let tx = conn.transaction()?;
+    insert_api(&tx, id, name, domain_id, &Uuid::nil())?;
+    insert_links(&tx, id, spec_ids)?;
+    tx.commit()?;
+
+    Ok(id)
+}
+
+//the id is given, e.g. the x-api-id of the specs of the api
+pub fn register_api(
+    config: &super::super::settings::Database,
+    id: Uuid,
+    name: &str,
+    domain_id: &Uuid,
+    tier_id: &Uuid,
+    spec_ids: &[String],
+) -> Result<()> {
+    let db_path = get_init_db(&config.rusqlite_path)?;
+    let mut conn = Connection::open(db_path)?;
+
+    let tx = conn.transaction()?;
+    insert_api(&tx, id, name, domain_id, tier_id)?;
+    insert_links(&tx, id, spec_ids)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+fn insert_api(
+    tx: &Transaction,
+    id: Uuid,
+    name: &str,
+    domain_id: &Uuid,
+    tier_id: &Uuid,
+) -> Result<()> {
+    tx.execute(
         "INSERT INTO apis (id, name, domain_id, tier_id) VALUES (?1, ?2, ?3, ?4)",
-        params![id, name, domain_id, Uuid::nil()],
+        params![id, name, domain_id, tier_id],
     )?;
 
     //TODO manage status
 
-    conn.close().unwrap();
+    Ok(())
+}
+
+/**
+ * Link the specs (by their stable id) to the api. A spec belongs to a single api,
+ * linking it again to the same api is a no-op.
+ */
+pub fn link_specs(
+    config: &super::super::settings::Database,
+    api_id: Uuid,
+    spec_ids: &[String],
+) -> Result<()> {
+    let db_path = get_init_db(&config.rusqlite_path)?;
+    let mut conn = Connection::open(db_path)?;
+
+    //all or none of the specs are linked
+    let tx = conn.transaction()?;
+    insert_links(&tx, api_id, spec_ids)?;
+    tx.commit()?;
 
     Ok(())
 }
 
+fn insert_links(tx: &Transaction, api_id: Uuid, spec_ids: &[String]) -> Result<()> {
+    for spec_id in spec_ids {
+        let owner: Option<Uuid> = tx
+            .query_row(
+                "SELECT api_id FROM api_specs WHERE spec_id = ?1",
+                params![spec_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match owner {
+            Some(owner) if owner == api_id => continue,
+            Some(owner) => {
+                return Err(ApiError::Conflict {
+                    message: format!("Spec [{}] is already linked to api [{}]", spec_id, owner),
+                })
+            }
+            None => {
+                tx.execute(
+                    "INSERT INTO api_specs (api_id, spec_id, link_date_time) VALUES (?1, ?2, ?3)",
+                    params![api_id, spec_id, Utc::now()],
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn unlink_spec(
+    config: &super::super::settings::Database,
+    api_id: Uuid,
+    spec_id: &str,
+) -> Result<usize> {
+    let db_path = get_init_db(&config.rusqlite_path)?;
+    let conn = Connection::open(db_path)?;
+
+    let deleted = conn.execute(
+        "DELETE FROM api_specs WHERE api_id = ?1 AND spec_id = ?2",
+        params![api_id, spec_id],
+    )?;
+
+    Ok(deleted)
+}
+
+//stable ids of the specs linked to the api, in the order they have been linked
+pub fn get_api_specs(
+    config: &super::super::settings::Database,
+    api_id: Uuid,
+) -> Result<Vec<String>> {
+    let db_path = get_init_db(&config.rusqlite_path)?;
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt =
+        conn.prepare("SELECT spec_id FROM api_specs WHERE api_id = ?1 ORDER BY link_date_time")?;
+    let mut rows = stmt.query(params![api_id])?;
+
+    let mut spec_ids = Vec::new();
+    while let Some(row) = rows.next()? {
+        spec_ids.push(row.get("spec_id")?);
+    }
+
+    Ok(spec_ids)
+}
+
+//api owning each linked spec, by stable id of the spec
+pub fn list_all_api_specs(
+    config: &super::super::settings::Database,
+) -> Result<HashMap<String, Uuid>> {
+    let db_path = get_init_db(&config.rusqlite_path)?;
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare("SELECT api_id, spec_id FROM api_specs")?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+
+    let mut owners = HashMap::new();
+    while let Some(row) = rows.next()? {
+        owners.insert(row.get("spec_id")?, row.get("api_id")?);
+    }
+
+    Ok(owners)
+}
+
+/**
+ * Proposals for the specs of the catalog that no registered api claims,
+ * and the registered apis whose specs are no longer in the catalog
+ */
+pub fn reconcile_apis(
+    config: &super::super::settings::Database,
+    all_specs: &[SpecItem],
+    all_async_specs: &[AsyncSpecItem],
+) -> Result<Reconciliation> {
+    let mut spec_ids: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for (spec_id, api_id) in list_all_api_specs(config)? {
+        spec_ids.entry(api_id).or_default().push(spec_id);
+    }
+    let apis: Vec<RegisteredApi> = list_all_apis(config)?
+        .into_iter()
+        .map(|api| RegisteredApi {
+            spec_ids: spec_ids.remove(&api.id).unwrap_or_default(),
+            id: api.id,
+            name: api.name,
+        })
+        .collect();
+
+    let claims = reconciliation::get_claims(all_specs, all_async_specs);
+    Ok(reconciliation::reconcile(&claims, &apis))
+}
+
+/**
+ * Registers the apis and links the specs of the proposals. A proposal whose domain is not registered
+ * is skipped, the tier being left unset when unknown. A proposal failing to apply is logged and does not
+ * prevent the others from being applied. Returns the number of applied proposals.
+ */
+pub fn register_proposals(
+    config: &super::super::settings::Database,
+    reconciliation: &Reconciliation,
+) -> Result<usize> {
+    let domains = super::repo_domains::list_all_domains(config)?;
+    let tiers = list_all_tiers(config)?;
+
+    let mut applied = 0;
+    for proposal in &reconciliation.proposals {
+        match apply_proposal(config, proposal, &domains, &tiers) {
+            Ok(true) => applied += 1,
+            Ok(false) => (),
+            Err(why) => error!(
+                "Unable to apply the [{:?}] proposal for api [{}] - {}",
+                proposal.action, proposal.name, why
+            ),
+        }
+    }
+
+    Ok(applied)
+}
+
+//a new api is registered along with its specs, all or nothing
+fn apply_proposal(
+    config: &super::super::settings::Database,
+    proposal: &Proposal,
+    domains: &[DomainItem],
+    tiers: &[TierItem],
+) -> Result<bool> {
+    match (&proposal.action, proposal.api_id) {
+        (ReconciliationAction::LINK, Some(api_id)) => {
+            link_specs(config, api_id, &proposal.spec_ids)?;
+        }
+        (ReconciliationAction::LINK, None) => return Ok(false),
+        (ReconciliationAction::CREATE, api_id) => {
+            //x-domain is the name of a domain, the domain derived from the path or servers of the specs contains one
+            let domain = domains
+                .iter()
+                .find(|domain| domain.name.eq_ignore_ascii_case(&proposal.domain))
+                .or_else(|| {
+                    let names = domains.iter().map(|domain| domain.name.as_str());
+                    let name = domain_rules::find_domain(&proposal.domain, names)?;
+                    domains.iter().find(|domain| domain.name == name)
+                });
+            let domain = match domain {
+                Some(domain) => domain,
+                None => {
+                    warn!(
+                        "Unable to register api [{}] - unknown domain [{}]",
+                        proposal.name, proposal.domain
+                    );
+                    return Ok(false);
+                }
+            };
+            let tier_id = proposal
+                .tier
+                .as_ref()
+                .and_then(|name| {
+                    tiers
+                        .iter()
+                        .find(|tier| tier.name.eq_ignore_ascii_case(name))
+                })
+                .map(|tier| tier.id)
+                .unwrap_or_else(Uuid::nil);
+
+            let api_id = api_id.unwrap_or_else(Uuid::new_v4);
+            register_api(
+                config,
+                api_id,
+                &proposal.name,
+                &domain.id,
+                &tier_id,
+                &proposal.spec_ids,
+            )?;
+            info!(
+                "Registered api [{}] with id [{}] for specs [{:?}]",
+                proposal.name, api_id, proposal.spec_ids
+            );
+        }
+    }
+
+    Ok(true)
+}
+
 pub fn get_api_by_id(config: &super::super::settings::Database, api: Uuid) -> Result<ApiItem> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
+    let db_path = get_init_db(&config.rusqlite_path)?;
     let conn = Connection::open(db_path)?;
 
     let mut stmt = conn.prepare("SELECT id, name, domain_id, tier_id FROM apis WHERE id = ?1")?;
-    let row = stmt.query_row(params![api], |row| {
-        let id = row.get(0)?;
-        let tier_id = row.get(3)?;
-        //get last status
-        let status = match get_last_status(config, id) {
-            Ok(val) => val.status,
-            Err(why) => {
-                warn!("Unable to get status for api [{:?}] - [{:?}]", id, why);
-                String::from("NONE") //TODO - reuse enum
-            }
-        };
-        let tier = match get_related_tier(config, tier_id) {
-            Ok(val) => val,
-            Err(why) => {
-                warn!("Unable to get tier for api [{:?}] - [{:?}]", id, why);
-                TierItem {
-                    id: Uuid::nil(),
-                    name: String::from("N/A"),
+    let row = stmt
+        .query_row(params![api], |row| {
+            let id = row.get(0)?;
+            let tier_id = row.get(3)?;
+            //get last status
+            let status = match get_last_status(config, id) {
+                Ok(val) => val.status,
+                Err(why) => {
+                    warn!("Unable to get status for api [{:?}] - [{:?}]", id, why);
+                    String::from("NONE") //TODO - reuse enum
                 }
-            }
-        };
-
-        Ok(ApiItem {
-            name: row.get(1)?,
-            id: id,
-            tier: tier,
-            domain_id: row.get(2)?,
-            status: status,
+            };
+            let tier = match get_related_tier(config, tier_id) {
+                Ok(val) => val,
+                Err(why) => {
+                    warn!("Unable to get tier for api [{:?}] - [{:?}]", id, why);
+                    TierItem {
+                        id: Uuid::nil(),
+                        name: String::from("N/A"),
+                    }
+                }
+            };
+
+            Ok(ApiItem {
+                name: row.get(1)?,
+                id: id,
+                tier: tier,
+                domain_id: row.get(2)?,
+                status: status,
+            })
         })
-    })?;
+        .map_err(|why| ApiError::from_lookup(why, format!("No api [{}]", api)))?;
 
     Ok(row)
 }
@@ -225,7 +501,7 @@ pub fn get_apis_per_domain_id(
     config: &super::super::settings::Database,
     domain_id: Uuid,
 ) -> Result<Vec<ApiItem>> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
+    let db_path = get_init_db(&config.rusqlite_path)?;
     let conn = Connection::open(db_path)?;
 
     let mut stmt =
@@ -267,35 +543,110 @@ pub fn get_apis_per_domain_id(
     Ok(results)
 }
 
+//the lifecycle of an api, the settings allowing other transitions
+const STATUS_TRANSITIONS: [(&str, &str); 3] = [
+    ("NONE", "VALIDATED"),
+    ("VALIDATED", "DEPRECATED"),
+    ("DEPRECATED", "RETIRED"),
+];
+
+pub fn is_allowed_transition(
+    from: &str,
+    to: &str,
+    transitions: &[super::super::settings::StatusTransition],
+) -> bool {
+    STATUS_TRANSITIONS.iter().any(|(allowed_from, allowed_to)| {
+        allowed_from.eq_ignore_ascii_case(from) && allowed_to.eq_ignore_ascii_case(to)
+    }) || transitions.iter().any(|transition| {
+        transition.from.eq_ignore_ascii_case(from) && transition.to.eq_ignore_ascii_case(to)
+    })
+}
+
+/**
+ * Changes the status of the api if the transition is allowed. The current status is ended
+ * rather than deleted, so that the status table keeps the whole history of the api.
+ */
 pub fn update_api_status(
     config: &super::super::settings::Database,
     status: StatusItem,
+    actor: &str,
+    comment: Option<&str>,
+    transitions: &[super::super::settings::StatusTransition],
 ) -> Result<()> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
-    let conn = Connection::open(db_path)?;
+    let db_path = get_init_db(&config.rusqlite_path)?;
+    let mut conn = Connection::open(db_path)?;
+
+    let tx = conn.transaction()?;
+    let current: Option<String> = tx
+        .query_row(
+            "SELECT status FROM status WHERE api_id = ?1 AND end_date_time IS NULL ORDER BY status_id DESC LIMIT 1",
+            params![status.api_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let from = current.unwrap_or_else(|| String::from("NONE"));
+    let to = status.status.to_uppercase();
+    if !is_allowed_transition(&from, &to, transitions) {
+        return Err(ApiError::Conflict {
+            message: format!(
+                "Status of api [{}] cannot go from [{}] to [{}]",
+                status.api_id, from, to
+            ),
+        });
+    }
 
-    //At this stage, start_date_time / end_date_time is not managed so we can delete then insert
-    conn.execute(
-        "DELETE FROM status WHERE api_id = ?1",
-        params![status.api_id],
+    let now = Utc::now();
+    tx.execute(
+        "UPDATE status SET end_date_time = ?1 WHERE api_id = ?2 AND end_date_time IS NULL",
+        params![now, status.api_id],
     )?;
-
-    conn.execute(
-        "INSERT INTO status (api_id, status, start_date_time) VALUES (?1, ?2, ?3)",
-        params![status.api_id, status.status.to_uppercase(), Utc::now()],
+    tx.execute(
+        "INSERT INTO status (api_id, status, start_date_time, actor, comment) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![status.api_id, to, now, actor, comment],
     )?;
+    tx.commit()?;
 
-    conn.close().unwrap();
+    info!(
+        "Status of api [{}] changed from [{}] to [{}] by [{}]",
+        status.api_id, from, to, actor
+    );
 
     Ok(())
 }
 
+//the statuses of the api, from the first one to the current one
+pub fn get_status_history(
+    config: &super::super::settings::Database,
+    api_id: Uuid,
+) -> Result<Vec<StatusHistoryItem>> {
+    let db_path = get_init_db(&config.rusqlite_path)?;
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT status, actor, comment, start_date_time, end_date_time FROM status WHERE api_id = ?1 ORDER BY status_id",
+    )?;
+    let mut rows = stmt.query(params![api_id])?;
+
+    let mut history = Vec::new();
+    while let Some(row) = rows.next()? {
+        history.push(StatusHistoryItem {
+            status: row.get("status")?,
+            actor: row.get("actor")?,
+            comment: row.get("comment")?,
+            start_date_time: row.get("start_date_time")?,
+            end_date_time: row.get("end_date_time")?,
+        });
+    }
+
+    Ok(history)
+}
+
 pub fn update_api_tier(
     config: &super::super::settings::Database,
     api_id: Uuid,
     tier_id: Uuid,
 ) -> Result<()> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
+    let db_path = get_init_db(&config.rusqlite_path)?;
     let conn = Connection::open(db_path)?;
 
     //At this stage, start_date_time / end_date_time is not managed so we can delete then insert
@@ -304,13 +655,13 @@ pub fn update_api_tier(
         params![tier_id, api_id],
     )?;
 
-    conn.close().unwrap();
+    conn.close().map_err(|(_, why)| why)?;
 
     Ok(())
 }
 
 pub fn add_tier(config: &super::super::settings::Database, name: &str) -> Result<Uuid> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
+    let db_path = get_init_db(&config.rusqlite_path)?;
     let conn = Connection::open(db_path)?;
 
     let id = Uuid::new_v4();
@@ -319,13 +670,13 @@ pub fn add_tier(config: &super::super::settings::Database, name: &str) -> Result
         params![id, name],
     )?;
 
-    conn.close().unwrap();
+    conn.close().map_err(|(_, why)| why)?;
 
     Ok(id)
 }
 
 pub fn list_all_tiers(config: &super::super::settings::Database) -> Result<Vec<TierItem>> {
-    let db_path = get_init_db(&config.rusqlite_path).unwrap();
+    let db_path = get_init_db(&config.rusqlite_path)?;
     let conn = Connection::open(db_path)?;
 
     let mut stmt = conn.prepare("SELECT id, name FROM tiers")?;
@@ -343,3 +694,241 @@ pub fn list_all_tiers(config: &super::super::settings::Database) -> Result<Vec<T
 
     Ok(tuples)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_link_specs() {
+        //the module is compiled several times, hence tests run concurrently
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-api-specs-{}",
+            module_path!().replace("::", "-")
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = super::super::super::settings::Database {
+            rusqlite_path: dir.to_string_lossy().to_string(),
+        };
+        let conn = Connection::open(dir.join("apis-catalog-all.db")).unwrap();
+        conn.execute_batch(include_str!(
+            "../../../migrations/2020-12-12-101733_api_specs/down.sql"
+        ))
+        .unwrap();
+        conn.execute_batch(include_str!(
+            "../../../migrations/2020-12-12-101733_api_specs/up.sql"
+        ))
+        .unwrap();
+
+        let (api, other_api) = (Uuid::new_v4(), Uuid::new_v4());
+        let specs = vec![String::from("spec-a"), String::from("spec-b")];
+        link_specs(&config, api, &specs).unwrap();
+        //linking again is a no-op
+        link_specs(&config, api, &specs[..1]).unwrap();
+        assert_eq!(get_api_specs(&config, api).unwrap(), specs);
+
+        //a spec belongs to a single api, none of the specs is linked on conflict
+        match link_specs(
+            &config,
+            other_api,
+            &[String::from("spec-c"), specs[1].clone()],
+        ) {
+            Err(ApiError::Conflict { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other),
+        }
+        assert!(get_api_specs(&config, other_api).unwrap().is_empty());
+
+        assert_eq!(unlink_spec(&config, other_api, &specs[0]).unwrap(), 0);
+        assert_eq!(unlink_spec(&config, api, &specs[0]).unwrap(), 1);
+        let owners = list_all_api_specs(&config).unwrap();
+        assert_eq!(owners.len(), 1);
+        assert_eq!(owners.get(&specs[1]), Some(&api));
+
+        //the api is not created when one of its specs cannot be linked
+        conn.execute_batch("DROP TABLE IF EXISTS apis").unwrap();
+        conn.execute_batch(include_str!(
+            "../../../migrations/2020-06-30-060925_tiers/up.sql"
+        ))
+        .unwrap();
+        let count_apis = || -> i64 {
+            conn.query_row("SELECT COUNT(*) FROM apis", NO_PARAMS, |row| row.get(0))
+                .unwrap()
+        };
+        match add_api(&config, "pets", &Uuid::new_v4(), &specs) {
+            Err(ApiError::Conflict { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other),
+        }
+        assert_eq!(count_apis(), 0);
+        let created = add_api(&config, "pets", &Uuid::new_v4(), &specs[..1]).unwrap();
+        assert_eq!(count_apis(), 1);
+        assert_eq!(get_api_specs(&config, created).unwrap(), &specs[..1]);
+    }
+
+    #[test]
+    fn test_status_history() {
+        //the module is compiled several times, hence tests run concurrently
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-status-{}",
+            module_path!().replace("::", "-")
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = super::super::super::settings::Database {
+            rusqlite_path: dir.to_string_lossy().to_string(),
+        };
+        let conn = Connection::open(dir.join("apis-catalog-all.db")).unwrap();
+        conn.execute_batch("DROP TABLE IF EXISTS status").unwrap();
+        conn.execute_batch(include_str!(
+            "../../../migrations/2020-06-30-060925_tiers/up.sql"
+        ))
+        .unwrap();
+        conn.execute_batch(include_str!(
+            "../../../migrations/2020-12-19-092841_status_history/up.sql"
+        ))
+        .unwrap();
+
+        let api = Uuid::new_v4();
+        let update =
+            |status: &str, transitions: &[super::super::super::settings::StatusTransition]| {
+                update_api_status(
+                    &config,
+                    StatusItem {
+                        api_id: api,
+                        status: String::from(status),
+                    },
+                    "jdoe",
+                    Some("reviewed"),
+                    transitions,
+                )
+            };
+
+        update("validated", &[]).unwrap();
+        update("DEPRECATED", &[]).unwrap();
+        //not part of the lifecycle unless allowed by the settings
+        match update("VALIDATED", &[]) {
+            Err(ApiError::Conflict { .. }) => (),
+            other => panic!("unexpected result [{:?}]", other),
+        }
+        let transitions = vec![super::super::super::settings::StatusTransition {
+            from: String::from("DEPRECATED"),
+            to: String::from("VALIDATED"),
+        }];
+        update("VALIDATED", &transitions).unwrap();
+        assert!(update("RETIRED", &transitions).is_err());
+
+        let history = get_status_history(&config, api).unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|item| item.status.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["VALIDATED", "DEPRECATED", "VALIDATED"]
+        );
+        assert_eq!(history[0].actor, "jdoe");
+        assert_eq!(history[0].comment.as_deref(), Some("reviewed"));
+        assert_eq!(history[0].end_date_time, Some(history[1].start_date_time));
+        assert!(history[2].end_date_time.is_none());
+        assert_eq!(get_last_status(&config, api).unwrap().status, "VALIDATED");
+    }
+
+    #[test]
+    fn test_register_proposals() {
+        //the module is compiled several times, hence tests run concurrently
+        let dir = std::env::temp_dir().join(format!(
+            "apis-catalog-proposals-{}",
+            module_path!().replace("::", "-")
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let _ = std::fs::remove_file(dir.join("apis-catalog-all.db"));
+        let config = super::super::super::settings::Database {
+            rusqlite_path: dir.to_string_lossy().to_string(),
+        };
+        let conn = Connection::open(dir.join("apis-catalog-all.db")).unwrap();
+        for migration in &[
+            include_str!("../../../migrations/2020-06-30-060925_tiers/up.sql"),
+            include_str!("../../../migrations/2020-10-31-082946_alter_domains_owner/up.sql"),
+            include_str!("../../../migrations/2020-12-12-101733_api_specs/up.sql"),
+        ] {
+            conn.execute_batch(migration).unwrap();
+        }
+        let trading =
+            super::super::repo_domains::add_domain(&config, "/trading", "trading", "jdoe").unwrap();
+
+        let other_api = Uuid::new_v4();
+        link_specs(&config, other_api, &[String::from("spec-a")]).unwrap();
+        let proposal = |action: ReconciliationAction,
+                        api_id: Option<Uuid>,
+                        name: &str,
+                        domain: &str,
+                        spec_id: &str| Proposal {
+            action: action,
+            key: reconciliation::ApiKey::Title(String::from(name)),
+            api_id: api_id,
+            name: String::from(name),
+            domain: String::from(domain),
+            tier: None,
+            spec_ids: vec![String::from(spec_id)],
+        };
+        let reconciliation = Reconciliation {
+            proposals: vec![
+                //spec-a is already linked, the api is not registered
+                proposal(
+                    ReconciliationAction::CREATE,
+                    None,
+                    "orders",
+                    "/trading",
+                    "spec-a",
+                ),
+                proposal(
+                    ReconciliationAction::CREATE,
+                    None,
+                    "pets",
+                    "/trading",
+                    "spec-b",
+                ),
+                proposal(
+                    ReconciliationAction::LINK,
+                    Some(other_api),
+                    "other",
+                    "/trading",
+                    "spec-c",
+                ),
+                proposal(
+                    ReconciliationAction::CREATE,
+                    None,
+                    "unknown",
+                    "/unknown",
+                    "spec-d",
+                ),
+                //no x-domain, the domain is derived from the path of the spec
+                proposal(
+                    ReconciliationAction::CREATE,
+                    None,
+                    "stores",
+                    "/v1/trading/stores",
+                    "spec-e",
+                ),
+            ],
+            orphans: Vec::new(),
+        };
+
+        assert_eq!(register_proposals(&config, &reconciliation).unwrap(), 3);
+        let mut apis: Vec<(String, Uuid)> = list_all_apis(&config)
+            .unwrap()
+            .into_iter()
+            .map(|api| (api.name, api.domain_id))
+            .collect();
+        apis.sort();
+        assert_eq!(
+            apis,
+            vec![
+                (String::from("pets"), trading),
+                (String::from("stores"), trading)
+            ]
+        );
+        let owners = list_all_api_specs(&config).unwrap();
+        assert_eq!(owners.len(), 4);
+        assert_eq!(owners.get("spec-a"), Some(&other_api));
+        assert_eq!(owners.get("spec-c"), Some(&other_api));
+        assert!(!owners.contains_key("spec-d"));
+    }
+}
@@ -4,7 +4,9 @@ extern crate time;
 extern crate uuid;
 
 use rusqlite::{named_params, NO_PARAMS};
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection};
+
+use super::error::Result;
 
 //use rustbreak::{FileDatabase, deser::Ron};
 use log::debug;
@@ -35,7 +37,7 @@ pub fn release(config: &super::super::settings::Database, api: String, env: Stri
         params![api, env],
     )?;
 
-    conn.close().unwrap();
+    conn.close().map_err(|(_, why)| why)?;
 
     Ok(())
 }
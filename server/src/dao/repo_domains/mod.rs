@@ -6,7 +6,9 @@ extern crate uuid;
 use uuid::Uuid;
 
 use rusqlite::NO_PARAMS;
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection};
+
+use super::error::{ApiError, Result};
 
 //use rustbreak::{FileDatabase, deser::Ron};
 use log::debug;
@@ -66,13 +68,25 @@ pub fn add_domain(
 
     let conn = Connection::open(db_path)?;
 
+    //names are not unique in the table, hence the check
+    let existing: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM domains WHERE name = ?1",
+        params![name],
+        |row| row.get(0),
+    )?;
+    if existing > 0 {
+        return Err(ApiError::Conflict {
+            message: format!("Domain [{}] already exists", name),
+        });
+    }
+
     let id = Uuid::new_v4();
     conn.execute(
         "INSERT INTO domains (id, name, description, owner) VALUES (?1, ?2, ?3, ?4)",
         params![id, name, description, owner],
     )?;
 
-    conn.close().unwrap();
+    conn.close().map_err(|(_, why)| why)?;
     Ok(id)
 }
 
@@ -85,15 +99,18 @@ pub fn get_domain(config: &super::super::settings::Database, id: Uuid) -> Result
 
     let conn = Connection::open(db_path)?;
 
-    let mut stmt = conn.prepare("SELECT id, name, description FROM domains WHERE id = ?1")?;
-    let row = stmt.query_row(params![id], |row| {
-        Ok(DomainItem {
-            name: row.get(1)?,
-            id: row.get(0)?,
-            description: row.get(2)?,
-            owner: row.get(3)?,
+    let mut stmt =
+        conn.prepare("SELECT id, name, description, owner FROM domains WHERE id = ?1")?;
+    let row = stmt
+        .query_row(params![id], |row| {
+            Ok(DomainItem {
+                name: row.get(1)?,
+                id: row.get(0)?,
+                description: row.get(2)?,
+                owner: row.get(3)?,
+            })
         })
-    })?;
+        .map_err(|why| ApiError::from_lookup(why, format!("No domain [{}]", id)))?;
 
     Ok(row)
 }
@@ -6,7 +6,9 @@ extern crate uuid;
 use uuid::Uuid;
 
 use rusqlite::NO_PARAMS;
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection};
+
+use super::error::{ApiError, Result};
 
 //use rustbreak::{FileDatabase, deser::Ron};
 use log::debug;
@@ -70,13 +72,15 @@ pub fn get_env(config: &super::super::settings::Database, id: Uuid) -> Result<En
     // )?;
 
     let mut stmt = conn.prepare("SELECT id, name, description FROM envs WHERE id = ?1")?;
-    let row = stmt.query_row(params![id], |row| {
-        Ok(EnvItem {
-            name: row.get(1)?,
-            id: row.get(0)?,
-            description: row.get(2)?,
+    let row = stmt
+        .query_row(params![id], |row| {
+            Ok(EnvItem {
+                name: row.get(1)?,
+                id: row.get(0)?,
+                description: row.get(2)?,
+            })
         })
-    })?;
+        .map_err(|why| ApiError::from_lookup(why, format!("No env [{}]", id)))?;
 
     Ok(row)
 }
@@ -107,7 +111,7 @@ pub fn add_env(
         params![id, name, description],
     )?;
 
-    conn.close().unwrap();
+    conn.close().map_err(|(_, why)| why)?;
 
     Ok(())
 }
@@ -5,7 +5,9 @@ use std::collections::HashMap;
 use chrono::{DateTime, Utc};
 
 use rusqlite::NO_PARAMS;
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection};
+
+use super::error::Result;
 
 use log::{debug, info, warn};
 
@@ -235,10 +237,170 @@ pub fn get_metrics_endpoints_number(
     Ok(timeseries)
 }
 
+pub fn save_metrics_operations_num(
+    config: &super::super::settings::Database,
+    datetime: DateTime<Utc>,
+    size: i32,
+) -> Result<()> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Saving metrics_operations_num into Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    conn.execute(
+        "INSERT INTO metrics_operations_num (date_time, value) VALUES (?1, ?2)",
+        params![datetime, size],
+    )?;
+
+    Ok(())
+}
+
+pub fn get_metrics_operations_number(
+    config: &super::super::settings::Database,
+) -> Result<TimeSeries> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Reading all [metrics_operations_num] metrics into Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare("SELECT date_time, value FROM metrics_operations_num")?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+
+    let mut points = Vec::new();
+    while let Some(row) = rows.next()? {
+        let time = row.get("date_time")?;
+        let val = row.get("value")?;
+
+        points.push((time, val));
+    }
+
+    let timeseries = TimeSeries { points: points };
+
+    Ok(timeseries)
+}
+
+pub fn save_metrics_messages_num(
+    config: &super::super::settings::Database,
+    datetime: DateTime<Utc>,
+    size: i32,
+) -> Result<()> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Saving metrics_messages_num into Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    conn.execute(
+        "INSERT INTO metrics_messages_num (date_time, value) VALUES (?1, ?2)",
+        params![datetime, size],
+    )?;
+
+    Ok(())
+}
+
+pub fn get_metrics_messages_number(
+    config: &super::super::settings::Database,
+) -> Result<TimeSeries> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Reading all [metrics_messages_num] metrics into Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare("SELECT date_time, value FROM metrics_messages_num")?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+
+    let mut points = Vec::new();
+    while let Some(row) = rows.next()? {
+        let time = row.get("date_time")?;
+        let val = row.get("value")?;
+
+        points.push((time, val));
+    }
+
+    let timeseries = TimeSeries { points: points };
+
+    Ok(timeseries)
+}
+
+pub fn save_metrics_spec_errors(
+    config: &super::super::settings::Database,
+    datetime: DateTime<Utc>,
+    size: i32,
+) -> Result<()> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Saving metrics_spec_errors into Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    conn.execute(
+        "INSERT INTO metrics_spec_errors (date_time, value) VALUES (?1, ?2)",
+        params![datetime, size],
+    )?;
+
+    Ok(())
+}
+
+pub fn get_metrics_spec_errors(config: &super::super::settings::Database) -> Result<TimeSeries> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Reading all [metrics_spec_errors] metrics into Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare("SELECT date_time, value FROM metrics_spec_errors")?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+
+    let mut points = Vec::new();
+    while let Some(row) = rows.next()? {
+        let time = row.get("date_time")?;
+        let val = row.get("value")?;
+
+        points.push((time, val));
+    }
+
+    let timeseries = TimeSeries { points: points };
+
+    Ok(timeseries)
+}
+
 pub fn save_metrics_zally_ignore(
     config: &super::super::settings::Database,
     datetime: DateTime<Utc>,
-    stats: std::collections::HashMap<i64, usize>,
+    stats: std::collections::HashMap<String, usize>,
 ) -> Result<()> {
     let mut db_path = String::from(&config.rusqlite_path);
     db_path.push_str("/apis-catalog-all.db");
@@ -261,6 +423,32 @@ pub fn save_metrics_zally_ignore(
     Ok(())
 }
 
+pub fn save_metrics_lint_violations(
+    config: &super::super::settings::Database,
+    datetime: DateTime<Utc>,
+    stats: std::collections::HashMap<String, usize>,
+) -> Result<()> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Saving [metrics_lint_violations] metrics into Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let stats_as_yaml = serde_yaml::to_string(&stats)
+        .unwrap_or(String::from("Error: Unable to get yaml from stats"));
+    debug!("Saving stats {:?}", stats_as_yaml);
+    conn.execute(
+        "INSERT INTO metrics_lint_violations (date_time, data_points) VALUES (?1, ?2)",
+        params![datetime, stats_as_yaml],
+    )?;
+    Ok(())
+}
+
 pub fn save_metrics_endpoints_num_per_audience(
     config: &super::super::settings::Database,
     datetime: DateTime<Utc>,
@@ -287,14 +475,24 @@ pub fn save_metrics_endpoints_num_per_audience(
     Ok(())
 }
 
-#[derive(Debug)]
-pub struct i64BasedTimeSeries {
-    pub points: Vec<(DateTime<Utc>, std::collections::HashMap<i64, usize>)>,
+//rule ids used to be saved as numbers, they are now strings to keep ids like M10
+fn get_rule_data_points(val: &str) -> std::collections::HashMap<String, usize> {
+    let points: std::collections::HashMap<serde_yaml::Value, usize> =
+        serde_yaml::from_str(val).unwrap_or(std::collections::HashMap::new());
+
+    points
+        .into_iter()
+        .map(|(key, val)| match key {
+            serde_yaml::Value::Number(key) => (key.to_string(), val),
+            serde_yaml::Value::String(key) => (key, val),
+            key => (format!("{:?}", key), val),
+        })
+        .collect()
 }
 
 pub fn get_metrics_zally_ignore(
     config: &super::super::settings::Database,
-) -> Result<i64BasedTimeSeries> {
+) -> Result<StringBasedTimeSeries> {
     let mut db_path = String::from(&config.rusqlite_path);
     db_path.push_str("/apis-catalog-all.db");
     {
@@ -313,13 +511,39 @@ pub fn get_metrics_zally_ignore(
     while let Some(row) = rows.next()? {
         let time = row.get("date_time")?;
         let val: String = row.get("data_points")?;
-        points.push((
-            time,
-            serde_yaml::from_str(val.as_str()).unwrap_or(std::collections::HashMap::new()),
-        ));
+        points.push((time, get_rule_data_points(val.as_str())));
     }
 
-    let timeseries = i64BasedTimeSeries { points: points };
+    let timeseries = StringBasedTimeSeries { points: points };
+
+    Ok(timeseries)
+}
+
+pub fn get_metrics_lint_violations(
+    config: &super::super::settings::Database,
+) -> Result<StringBasedTimeSeries> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Reading all [metrics_lint_violations] metrics from Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare("SELECT date_time, data_points FROM metrics_lint_violations")?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+
+    let mut points = Vec::new();
+    while let Some(row) = rows.next()? {
+        let time = row.get("date_time")?;
+        let val: String = row.get("data_points")?;
+        points.push((time, get_rule_data_points(val.as_str())));
+    }
+
+    let timeseries = StringBasedTimeSeries { points: points };
 
     Ok(timeseries)
 }
@@ -361,3 +585,61 @@ pub fn get_metrics_endpoints_per_audience(
 
     Ok(timeseries)
 }
+
+pub fn save_metrics_churn(
+    config: &super::super::settings::Database,
+    datetime: DateTime<Utc>,
+    stats: Vec<super::catalog::churn::ChurnStats>,
+) -> Result<()> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Saving [metrics_churn] metrics into Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let stats_as_yaml = serde_yaml::to_string(&stats)
+        .unwrap_or(String::from("Error: Unable to get yaml from stats"));
+    debug!("Saving stats {:?}", stats_as_yaml);
+    conn.execute(
+        "INSERT INTO metrics_churn (date_time, data_points) VALUES (?1, ?2)",
+        params![datetime, stats_as_yaml],
+    )?;
+    Ok(())
+}
+
+#[derive(Debug)]
+pub struct ChurnTimeSeries {
+    pub points: Vec<(DateTime<Utc>, Vec<super::catalog::churn::ChurnStats>)>,
+}
+
+pub fn get_metrics_churn(config: &super::super::settings::Database) -> Result<ChurnTimeSeries> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Reading all [metrics_churn] metrics from Metrics_Database [{:?}]",
+            db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare("SELECT date_time, data_points FROM metrics_churn")?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+
+    let mut points = Vec::new();
+    while let Some(row) = rows.next()? {
+        let time = row.get("date_time")?;
+        let val: String = row.get("data_points")?;
+        points.push((time, serde_yaml::from_str(val.as_str()).unwrap_or_default()));
+    }
+
+    let timeseries = ChurnTimeSeries { points: points };
+
+    Ok(timeseries)
+}
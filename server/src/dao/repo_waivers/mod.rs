@@ -0,0 +1,110 @@
+extern crate rusqlite;
+extern crate uuid;
+
+use uuid::Uuid;
+
+use chrono::{DateTime, Utc};
+
+use rusqlite::NO_PARAMS;
+use rusqlite::{params, Connection};
+
+use super::error::Result;
+
+use log::debug;
+
+//an exception to a zally rule, for a whole spec (path is None) or a single path of the spec
+#[derive(Debug, Clone)]
+pub struct WaiverItem {
+    pub id: Uuid,
+    //stable id of the spec, its path may be the same in several sources and blob ids change on every revision
+    pub spec: String,
+    pub path: Option<String>,
+    pub rule: String,
+    pub approver: String,
+    pub expiry_date_time: DateTime<Utc>,
+    pub start_date_time: DateTime<Utc>,
+    pub revoked_date_time: Option<DateTime<Utc>>,
+}
+
+pub fn list_all_waivers(config: &super::super::settings::Database) -> Result<Vec<WaiverItem>> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!("Reading all waivers from Waiver_Database [{:?}]", db_path);
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, spec, path, rule, approver, expiry_date_time, start_date_time, revoked_date_time FROM waivers",
+    )?;
+    let mut rows = stmt.query(NO_PARAMS)?;
+
+    let mut tuples = Vec::new();
+    while let Some(row) = rows.next()? {
+        let waiver = WaiverItem {
+            id: row.get("id")?,
+            spec: row.get("spec")?,
+            path: row.get("path")?,
+            rule: row.get("rule")?,
+            approver: row.get("approver")?,
+            expiry_date_time: row.get("expiry_date_time")?,
+            start_date_time: row.get("start_date_time")?,
+            revoked_date_time: row.get("revoked_date_time")?,
+        };
+
+        tuples.push(waiver);
+    }
+
+    Ok(tuples)
+}
+
+pub fn add_waiver(
+    config: &super::super::settings::Database,
+    spec: &str,
+    path: Option<&str>,
+    rule: &str,
+    approver: &str,
+    expiry_date_time: DateTime<Utc>,
+) -> Result<Uuid> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Creating waiver for rule [{}] on [{}] into Waiver_Database [{:?}]",
+            rule, spec, db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let id = Uuid::new_v4();
+    conn.execute(
+        "INSERT INTO waivers (id, spec, path, rule, approver, expiry_date_time, start_date_time) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![id, spec, path, rule, approver, expiry_date_time, Utc::now()],
+    )?;
+
+    conn.close().map_err(|(_, why)| why)?;
+    Ok(id)
+}
+
+//waivers are kept once revoked, for the record
+pub fn revoke_waiver(config: &super::super::settings::Database, id: Uuid) -> Result<usize> {
+    let mut db_path = String::from(&config.rusqlite_path);
+    db_path.push_str("/apis-catalog-all.db");
+    {
+        debug!(
+            "Revoke waiver [{}] into Waiver_Database [{:?}]",
+            id, db_path
+        );
+    }
+
+    let conn = Connection::open(db_path)?;
+
+    let mut stmt = conn.prepare(
+        "UPDATE waivers SET revoked_date_time = ?1 WHERE id = ?2 AND revoked_date_time IS NULL",
+    )?;
+    let updated = stmt.execute(params![Utc::now(), id])?;
+
+    Ok(updated)
+}
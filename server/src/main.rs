@@ -74,6 +74,9 @@ async fn main() {
         App::new()
             .wrap(Logger::default())
             .wrap(Logger::new("%a %{User-Agent}i"))
+            //payloads and queries that cannot be read are reported as problem+json too
+            .app_data(web::JsonConfig::default().error_handler(dao::error::json_error_handler))
+            .app_data(web::QueryConfig::default().error_handler(dao::error::query_error_handler))
             //deployment related endpoints
             .service(app::deployments::add_deployment)
             .service(app::deployments::get_deployments)
@@ -86,6 +89,7 @@ async fn main() {
             .service(app::domains::get_domains_stats)
             .service(app::domains::create_domain)
             .service(app::domains::get_domains_errors)
+            .service(app::domains::get_specs_domains)
             .service(web::scope("/v1/domains").service(
                 web::resource("/{id}").route(web::delete().to(app::domains::delete_domain)),
             ))
@@ -96,18 +100,57 @@ async fn main() {
             ) //TODO rework url
             .service(app::apis::list_all_reviews)
             .service(app::apis::get_all_specs)
+            .service(
+                web::scope("/v1/specs")
+                    .service(
+                        web::resource("/errors").route(web::get().to(app::apis::get_spec_errors)),
+                    )
+                    .service(
+                        web::resource("/{id}/bundled")
+                            .route(web::get().to(app::apis::get_bundled_spec)),
+                    )
+                    .service(
+                        web::resource("/{id}/compare/{other_id}")
+                            .route(web::get().to(app::apis::compare_specs)),
+                    )
+                    .service(web::resource("/{id}/lint").route(web::get().to(app::apis::lint_spec)))
+                    .service(
+                        web::resource("/{id}/extensions")
+                            .route(web::get().to(app::apis::get_spec_extensions)),
+                    )
+                    .service(
+                        web::resource("/{id}/history")
+                            .route(web::get().to(app::apis::get_spec_history)),
+                    ),
+            )
             .service(app::apis::create_api)
             .service(app::apis::list_all_apis)
             .service(
                 web::scope("/v1/apis")
+                    .service(
+                        web::resource("/reconciliation")
+                            .route(web::get().to(app::apis::get_reconciliation)),
+                    )
                     .service(web::resource("/{api}").route(web::get().to(app::apis::get_api_by_id)))
                     .service(
                         web::resource("/{api}/status")
                             .route(web::post().to(app::apis::update_api_status_by_id)),
                     )
+                    .service(
+                        web::resource("/{api}/status/history")
+                            .route(web::get().to(app::apis::get_api_status_history)),
+                    )
                     .service(
                         web::resource("/{api}/tier")
                             .route(web::post().to(app::apis::update_api_tier_by_id)),
+                    )
+                    .service(
+                        web::resource("/{api}/specs")
+                            .route(web::post().to(app::apis::link_api_specs)),
+                    )
+                    .service(
+                        web::resource("/{api}/specs/{spec_id}")
+                            .route(web::delete().to(app::apis::unlink_api_spec)),
                     ),
             )
             //end related endpoints
@@ -122,6 +165,16 @@ async fn main() {
             .service(app::apis::get_oldest_pr)
             .service(app::apis::get_merged_pr)
             .service(app::metrics::refresh_metrics)
+            .service(app::metrics::backfill_metrics)
+            //zally rules related endpoints
+            .service(app::zally::get_rules)
+            .service(app::zally::get_zally_ignores)
+            //waivers related endpoints
+            .service(app::waivers::get_waivers)
+            .service(app::waivers::create_waiver)
+            .service(web::scope("/v1/waivers").service(
+                web::resource("/{id}").route(web::delete().to(app::waivers::delete_waiver)),
+            ))
             //Static resources mapping
             .route("/", web::get().to(index))
             .route("/static", web::get().to(index))
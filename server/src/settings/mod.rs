@@ -25,10 +25,102 @@ pub struct Database {
     pub rusqlite_path: String,
 }
 
+//name of the source built from catalog_path and catalog_dir when no source is set
+pub const DEFAULT_SOURCE: &str = "default";
+
+/**
+ * Credentials used to clone and fetch a catalog repo
+ */
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GitCredentials {
+    //https, e.g. a personal access token
+    Token {
+        username: Option<String>,
+        token: String,
+    },
+    Ssh {
+        username: Option<String>,
+        private_key_path: String,
+        passphrase: Option<String>,
+    },
+}
+
+/**
+ * A git repo of specs, e.g. the one of a business line
+ */
+#[derive(Debug, Deserialize, Clone)]
+pub struct CatalogSource {
+    //part of the ids of its specs, e.g. trading:6c0b...
+    pub name: String,
+    //local clone of the repo, cloned from url if missing
+    pub path: String,
+    //remote of the repo, origin of the local clone if not set
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub credentials: Option<GitCredentials>,
+    //dir of the specs, the short paths being relative to it
+    pub dir: String,
+    //globs of the spec files, relative to path
+    #[serde(default = "default_patterns")]
+    pub patterns: Vec<String>,
+    //checked out and pulled on refresh, the current branch if not set
+    #[serde(default)]
+    pub branch: Option<String>,
+    //prepended to the domain of its specs, e.g. /trading
+    #[serde(default)]
+    pub domain_prefix: Option<String>,
+    //rules deriving the domain of its specs, the domain_rules_path of the settings if not set
+    #[serde(default)]
+    pub domain_rules_path: Option<String>,
+}
+
+fn default_patterns() -> Vec<String> {
+    vec![String::from("**/*.yaml"), String::from("**/*.yml")]
+}
+
+/**
+ * What the refresh does with the specs that no registered api claims
+ */
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Reconciliation {
+    #[default]
+    Off,
+    //logs the apis to register and the specs to link
+    Propose,
+    //registers the apis and links the specs
+    Register,
+}
+
+//a change of status allowed on top of NONE -> VALIDATED -> DEPRECATED -> RETIRED, e.g. DEPRECATED -> VALIDATED
+#[derive(Debug, Deserialize, Clone)]
+pub struct StatusTransition {
+    pub from: String,
+    pub to: String,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     pub catalog_path: String,
     pub catalog_dir: String,
+    //remote and credentials of catalog_path
+    #[serde(default)]
+    pub catalog_url: Option<String>,
+    #[serde(default)]
+    pub catalog_credentials: Option<GitCredentials>,
+    //repos of specs, catalog_path and catalog_dir being the only one if not set
+    #[serde(default)]
+    pub sources: Vec<CatalogSource>,
+    //ordered rules used to derive the domain of the specs, the default ones if not set
+    #[serde(default)]
+    pub domain_rules_path: Option<String>,
+    //reconciliation of the apis with the specs on refresh, off if not set
+    #[serde(default)]
+    pub reconciliation: Reconciliation,
+    #[serde(default)]
+    pub status_transitions: Vec<StatusTransition>,
     pub stash_config: StashConfig,
     pub database: Database,
     pub server: Server,
@@ -50,4 +142,31 @@ impl Settings {
         info!("Configuration has been loaded - [{:?}]", settings);
         settings.try_into()
     }
+
+    pub fn get_sources(&self) -> Vec<CatalogSource> {
+        match self.sources.is_empty() {
+            true => vec![CatalogSource {
+                name: String::from(DEFAULT_SOURCE),
+                path: self.catalog_path.clone(),
+                url: self.catalog_url.clone(),
+                credentials: self.catalog_credentials.clone(),
+                dir: self.catalog_dir.clone(),
+                patterns: default_patterns(),
+                branch: None,
+                domain_prefix: None,
+                domain_rules_path: self.domain_rules_path.clone(),
+            }],
+            false => self
+                .sources
+                .iter()
+                .map(|source| CatalogSource {
+                    domain_rules_path: source
+                        .domain_rules_path
+                        .clone()
+                        .or_else(|| self.domain_rules_path.clone()),
+                    ..source.clone()
+                })
+                .collect(),
+        }
+    }
 }